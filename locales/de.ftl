@@ -0,0 +1,78 @@
+# German, see en.ftl for every name.
+
+## nav
+
+nav-sites = Seiten
+nav-incidents = Vorfälle
+nav-account = Konto
+nav-logout = Abmelden
+nav-logging-out = Wird abgemeldet
+nav-home = Start
+nav-login = Anmelden
+
+## login
+
+login-code-placeholder = Dein Anmeldecode
+login-code-empty = Gib deinen Anmeldecode ein
+login-code-wrong = Dieser Anmeldecode hat nicht funktioniert
+login-too-many-attempts = Zu viele Versuche, warte eine Minute und versuche es noch einmal
+login-failed = Etwas ist schiefgelaufen, versuche es noch einmal
+login-button = Anmelden
+login-pending = Wird angemeldet
+login-passkey = Mit einem Passkey anmelden
+login-lost-code = Anmeldecode verloren?
+login-recovery-placeholder = Die Wiederherstellungs-E-Mail deines Kontos
+login-recovery-button = Schick mir einen Anmeldelink
+login-recovery-sent = Wenn diese E-Mail-Adresse an einem Konto bestätigt ist, ist ein Anmeldelink unterwegs
+
+## monitors
+
+monitors-search-placeholder = Nach URL oder Name suchen
+monitors-empty-title = Noch keine Seiten
+monitors-empty-detail = Füge die erste mit dem +-Knopf hinzu, wir prüfen sie dann alle paar Minuten.
+monitors-examples = Mit Beispielseiten umsehen
+monitors-no-matches-title = Keine passenden Seiten
+monitors-no-matches-detail = Versuche eine andere Suche oder zeige wieder alle Seiten.
+monitors-clear-filters = Filter zurücksetzen
+monitors-delete = Löschen
+monitors-confirm-delete =
+    { $count ->
+        [one] { $count } Seite wirklich löschen?
+       *[other] { $count } Seiten wirklich löschen?
+    }
+
+## incidents
+
+incidents-empty-title = Nichts zu berichten
+incidents-empty-detail = Vorfälle erscheinen hier, sobald eine deiner Seiten ausfällt.
+incidents-quiet-title = Alles ruhig
+incidents-quiet-detail = Keine deiner Seiten ist zwischen diesen Daten ausgefallen.
+
+## onboarding
+
+onboarding-step = Schritt { $number } von { $count }
+onboarding-check-now = Jetzt prüfen
+onboarding-next = Weiter
+onboarding-saved-code = Gespeichert
+onboarding-done = Fertig
+onboarding-skip = Weiter zu deinen Seiten
+onboarding-site-added-title = Alles eingerichtet
+onboarding-site-added-detail = Wir prüfen { $site } alle { $interval } und sagen dir Bescheid, wenn sie ausfällt.
+onboarding-no-site = Füge mit dem +-Knopf eine Seite hinzu, um mit der Überwachung zu beginnen.
+onboarding-login-code-title = Speichere deinen Anmeldecode
+onboarding-channel-title = Wohin sollen Alarme gehen?
+
+## account settings
+
+settings-title = Einstellungen
+settings-name-placeholder = Dein Name, optional
+settings-name-too-long = Namen dürfen höchstens 64 Zeichen lang sein
+settings-timezone-invalid = Zeitzonen sehen aus wie Europe/Berlin
+settings-interval-invalid = Wähle eines der Intervalle
+settings-digest-off = Keine Zusammenfassung
+settings-digest-daily = Tägliche Zusammenfassung
+settings-digest-weekly = Wöchentliche Zusammenfassung
+settings-interval = Neue Seiten alle { $interval } prüfen
+settings-save = Einstellungen speichern
+settings-saved = Einstellungen gespeichert
+settings-save-failed = Deine Einstellungen konnten nicht gespeichert werden, versuche es noch einmal
@@ -0,0 +1,79 @@
+# The app's strings, other locales fall back to these. Names are grouped
+# by the view they're shown in.
+
+## nav
+
+nav-sites = Sites
+nav-incidents = Incidents
+nav-account = Account
+nav-logout = Logout
+nav-logging-out = Logging out
+nav-home = Home
+nav-login = Login
+
+## login
+
+login-code-placeholder = Your login code goes here
+login-code-empty = Enter your login code
+login-code-wrong = That login code didn't work
+login-too-many-attempts = Too many attempts, wait a minute and try again
+login-failed = Something went wrong, try again
+login-button = Login
+login-pending = Logging in
+login-passkey = Login with a passkey
+login-lost-code = Lost your login code?
+login-recovery-placeholder = The recovery email on your account
+login-recovery-button = Email me a login link
+login-recovery-sent = If that email is verified on an account, a login link is on its way
+
+## monitors
+
+monitors-search-placeholder = Search by url or name
+monitors-empty-title = No sites yet
+monitors-empty-detail = Add the first one with the + button and we'll check it every few minutes.
+monitors-examples = Look around with example sites
+monitors-no-matches-title = No sites match
+monitors-no-matches-detail = Try another search, or show every site again.
+monitors-clear-filters = Clear filters
+monitors-delete = Delete
+monitors-confirm-delete =
+    { $count ->
+        [one] Really delete { $count } site?
+       *[other] Really delete { $count } sites?
+    }
+
+## incidents
+
+incidents-empty-title = Nothing to report
+incidents-empty-detail = Incidents show up here once a site you monitor goes down.
+incidents-quiet-title = All quiet
+incidents-quiet-detail = None of your sites went down between these dates.
+
+## onboarding
+
+onboarding-step = Step { $number } of { $count }
+onboarding-check-now = Check it now
+onboarding-next = Next
+onboarding-saved-code = I saved it
+onboarding-done = Done
+onboarding-skip = Skip to your sites
+onboarding-site-added-title = You're all set up
+onboarding-site-added-detail = We'll check { $site } every { $interval } and tell you when it goes down.
+onboarding-no-site = Add a site with the + button to start monitoring.
+onboarding-login-code-title = Save your login code
+onboarding-channel-title = Where should alerts go?
+
+## account settings
+
+settings-title = Settings
+settings-name-placeholder = Your name, optional
+settings-name-too-long = Names can't be longer than 64 characters
+settings-timezone-invalid = Timezones look like Europe/Berlin
+settings-interval-invalid = Pick one of the intervals
+settings-digest-off = No digest
+settings-digest-daily = Daily digest
+settings-digest-weekly = Weekly digest
+settings-interval = Check new sites every { $interval }
+settings-save = Save settings
+settings-saved = Settings saved
+settings-save-failed = Couldn't save your settings, try again
@@ -0,0 +1,20 @@
+drop table site_tags;
+drop table tags;
+drop table checks_daily;
+drop table checks_hourly;
+drop table queued_notifications;
+drop table expiry_rules;
+drop table expiries;
+drop table latency_rules;
+drop table notifications_sent;
+drop table watch_cycles;
+drop table checks;
+drop table notification_routes;
+drop table used_tokens;
+drop table incidents;
+drop table site_transfers;
+drop table notification_channels;
+drop table responses;
+drop table logins;
+drop table sites;
+drop table users;
@@ -0,0 +1,216 @@
+create table if not exists users (
+    id bigint generated by default as identity primary key,
+    login_code text not null unique,
+    created_at bigint not null,
+    updated_at bigint not null,
+    digest text not null default 'off',
+    digest_sent_at bigint,
+    timezone text not null default 'UTC',
+    quiet_start bigint,
+    quiet_end bigint
+);
+
+create table if not exists sites (
+    id bigint generated by default as identity primary key,
+    name text,
+    url text not null,
+    updated_at bigint not null,
+    created_at bigint not null,
+    user_id bigint not null references users(id),
+    critical boolean not null default false,
+    maintenance_until bigint,
+    interval_secs bigint not null default 300,
+    failure_threshold bigint not null default 1
+);
+
+create unique index if not exists sites_user_id_url on sites (user_id, url);
+
+create table if not exists logins (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    created_at bigint not null,
+    ip_address text,
+    user_agent text
+);
+
+create index if not exists logins_user_id on logins (user_id);
+
+create table if not exists responses (
+    id bigint generated by default as identity primary key,
+    site_id bigint not null references sites(id),
+    status_code bigint not null,
+    created_at bigint not null,
+    updated_at bigint not null,
+    unique (site_id, status_code)
+);
+
+create index if not exists responses_site_id_updated_at on responses (site_id, updated_at);
+
+create table if not exists notification_channels (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    kind text not null,
+    config text not null,
+    verified boolean not null default false,
+    created_at bigint not null,
+    updated_at bigint not null,
+    cooldown_secs bigint not null default 0,
+    renotify_secs bigint not null default 0,
+    template text
+);
+
+create index if not exists notification_channels_user_id on notification_channels (user_id);
+
+create table if not exists site_transfers (
+    id bigint generated by default as identity primary key,
+    site_id bigint not null references sites(id),
+    from_user_id bigint not null references users(id),
+    to_user_id bigint references users(id),
+    token text not null unique,
+    accepted_at bigint,
+    created_at bigint not null
+);
+
+create index if not exists site_transfers_site_id on site_transfers (site_id);
+
+create table if not exists incidents (
+    id bigint generated by default as identity primary key,
+    site_id bigint not null references sites(id),
+    status_code bigint not null,
+    started_at bigint not null,
+    resolved_at bigint,
+    last_status_code bigint,
+    acknowledged_at bigint,
+    acknowledged_by bigint references users(id),
+    flapping boolean not null default false
+);
+
+create index if not exists incidents_site_id_started_at on incidents (site_id, started_at);
+create index if not exists incidents_open_site_id on incidents (site_id) where resolved_at is null;
+
+create table if not exists used_tokens (
+    nonce text not null primary key,
+    expires_at bigint not null,
+    created_at bigint not null
+);
+
+create table if not exists notification_routes (
+    channel_id bigint not null references notification_channels(id),
+    site_id bigint not null references sites(id),
+    primary key (channel_id, site_id)
+);
+
+create table if not exists checks (
+    id bigint generated by default as identity primary key,
+    site_id bigint not null references sites(id),
+    status_code bigint not null,
+    latency_ms bigint not null,
+    failure text,
+    ip text,
+    created_at bigint not null,
+    maintenance boolean not null default false
+);
+
+create index if not exists checks_site_id_created_at on checks (site_id, created_at);
+create index if not exists checks_created_at on checks (created_at);
+
+create table if not exists watch_cycles (
+    id bigint generated by default as identity primary key,
+    sites_checked bigint not null,
+    failures bigint not null,
+    new_incidents bigint not null,
+    duration_ms bigint not null,
+    lag_ms bigint not null,
+    created_at bigint not null
+);
+
+create table if not exists notifications_sent (
+    id bigint generated by default as identity primary key,
+    channel_id bigint not null references notification_channels(id),
+    site_id bigint not null references sites(id),
+    incident_id bigint references incidents(id),
+    created_at bigint not null
+);
+
+create index if not exists notifications_sent_channel_id_site_id on notifications_sent (channel_id, site_id, created_at);
+
+create table if not exists latency_rules (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    site_id bigint not null references sites(id),
+    percentile bigint not null default 95,
+    window_secs bigint not null default 900,
+    threshold_ms bigint not null default 2000,
+    triggered_at bigint,
+    created_at bigint not null
+);
+
+create index if not exists latency_rules_site_id on latency_rules (site_id);
+
+create table if not exists expiries (
+    site_id bigint not null references sites(id),
+    kind text not null,
+    expires_at bigint,
+    checked_at bigint not null,
+    notified_at bigint,
+    primary key (site_id, kind)
+);
+
+create table if not exists expiry_rules (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    site_id bigint not null references sites(id),
+    kind text not null,
+    days bigint not null,
+    created_at bigint not null,
+    unique (site_id, kind)
+);
+
+create table if not exists queued_notifications (
+    id bigint generated by default as identity primary key,
+    channel_id bigint not null references notification_channels(id),
+    user_id bigint not null references users(id),
+    notification text not null,
+    created_at bigint not null
+);
+
+create table if not exists checks_hourly (
+    site_id bigint not null references sites(id),
+    hour bigint not null,
+    checks bigint not null,
+    up bigint not null,
+    maintenance bigint not null,
+    latency_sum_ms bigint not null,
+    latency_min_ms bigint not null,
+    latency_max_ms bigint not null,
+    primary key (site_id, hour)
+);
+
+create table if not exists checks_daily (
+    site_id bigint not null references sites(id),
+    day bigint not null,
+    checks bigint not null,
+    up bigint not null,
+    maintenance bigint not null,
+    latency_sum_ms bigint not null,
+    latency_min_ms bigint not null,
+    latency_max_ms bigint not null,
+    primary key (site_id, day)
+);
+
+create table if not exists tags (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    name text not null,
+    created_at bigint not null
+);
+
+create unique index if not exists tags_user_id_name on tags (user_id, name);
+
+create table if not exists site_tags (
+    site_id bigint not null references sites(id),
+    tag_id bigint not null references tags(id),
+    primary key (site_id, tag_id)
+);
+
+create index if not exists site_tags_tag_id on site_tags (tag_id);
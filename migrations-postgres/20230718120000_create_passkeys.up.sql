@@ -0,0 +1,10 @@
+create table if not exists passkeys (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    credential_id text not null unique,
+    passkey text not null,
+    created_at bigint not null,
+    last_used_at bigint
+);
+
+create index if not exists passkeys_user_id on passkeys (user_id);
@@ -0,0 +1,18 @@
+create table if not exists organizations (
+    id bigint generated by default as identity primary key,
+    name text not null,
+    user_id bigint not null unique references users(id),
+    created_at bigint not null,
+    updated_at bigint not null
+);
+
+create table if not exists memberships (
+    id bigint generated by default as identity primary key,
+    organization_id bigint not null references organizations(id),
+    user_id bigint not null references users(id),
+    role text not null default 'member',
+    created_at bigint not null,
+    unique (organization_id, user_id)
+);
+
+create index if not exists memberships_user_id on memberships (user_id);
@@ -0,0 +1,10 @@
+create table if not exists invitations (
+    id bigint generated by default as identity primary key,
+    organization_id bigint not null references organizations(id),
+    role text not null default 'member',
+    created_by bigint not null references users(id),
+    expires_at bigint not null,
+    created_at bigint not null
+);
+
+create index if not exists invitations_organization_id on invitations (organization_id);
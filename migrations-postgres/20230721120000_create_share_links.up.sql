@@ -0,0 +1,9 @@
+create table if not exists share_links (
+    id bigint generated by default as identity primary key,
+    site_id bigint not null references sites(id),
+    user_id bigint not null references users(id),
+    expires_at bigint,
+    created_at bigint not null
+);
+
+create index if not exists share_links_site_id on share_links (site_id);
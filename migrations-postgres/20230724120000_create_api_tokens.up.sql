@@ -0,0 +1,11 @@
+create table if not exists api_tokens (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    name text not null,
+    token_hash text not null unique,
+    scopes text not null,
+    last_used_at bigint,
+    created_at bigint not null
+);
+
+create index if not exists api_tokens_user_id on api_tokens (user_id);
@@ -0,0 +1,22 @@
+create table if not exists webhooks (
+    id bigint generated by default as identity primary key,
+    user_id bigint not null references users(id),
+    url text not null,
+    events text not null,
+    secret text not null,
+    created_at bigint not null
+);
+
+create index if not exists webhooks_user_id on webhooks (user_id);
+
+create table if not exists webhook_deliveries (
+    id bigint generated by default as identity primary key,
+    webhook_id bigint not null references webhooks(id),
+    event text not null,
+    status_code bigint,
+    error text,
+    duration_ms bigint not null,
+    created_at bigint not null
+);
+
+create index if not exists webhook_deliveries_webhook_id on webhook_deliveries (webhook_id);
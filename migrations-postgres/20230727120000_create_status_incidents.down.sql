@@ -0,0 +1,2 @@
+drop table incident_updates;
+drop table status_incidents;
@@ -0,0 +1,11 @@
+create table if not exists subscribers (
+    id bigint generated by default as identity primary key,
+    share_link_id bigint not null references share_links(id),
+    site_id bigint not null references sites(id),
+    email text not null,
+    confirmed_at bigint,
+    created_at bigint not null,
+    unique (share_link_id, email)
+);
+
+create index if not exists subscribers_site_id on subscribers (site_id);
@@ -0,0 +1,10 @@
+create table if not exists maintenance_windows (
+    id bigint generated by default as identity primary key,
+    site_id bigint not null references sites(id),
+    title text not null,
+    starts_at bigint not null,
+    ends_at bigint not null,
+    created_at bigint not null
+);
+
+create index if not exists maintenance_windows_site_id on maintenance_windows (site_id, starts_at);
@@ -0,0 +1,2 @@
+alter table users drop column default_interval_secs;
+alter table users drop column name;
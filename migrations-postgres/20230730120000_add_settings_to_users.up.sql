@@ -0,0 +1,2 @@
+alter table users add column name text;
+alter table users add column default_interval_secs bigint not null default 300;
@@ -0,0 +1,6 @@
+create table if not exists site_icons (
+    site_id bigint not null primary key references sites(id),
+    content_type text,
+    data bytea,
+    fetched_at bigint not null
+);
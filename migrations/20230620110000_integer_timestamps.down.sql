@@ -0,0 +1,2 @@
+-- fractional seconds are gone for good, nothing to undo
+select 1;
\ No newline at end of file
@@ -0,0 +1,4 @@
+update users set created_at = cast(created_at as integer), updated_at = cast(updated_at as integer);
+update sites set created_at = cast(created_at as integer), updated_at = cast(updated_at as integer);
+update logins set created_at = cast(created_at as integer);
+update responses set created_at = cast(created_at as integer), updated_at = cast(updated_at as integer);
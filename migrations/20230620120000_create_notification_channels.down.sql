@@ -0,0 +1 @@
+drop table notification_channels;
\ No newline at end of file
@@ -0,0 +1,11 @@
+create table if not exists notification_channels (
+    id integer not null primary key,
+    user_id integer not null references users(id),
+    kind text not null,
+    config text not null,
+    verified integer not null default 0,
+    created_at integer not null,
+    updated_at integer not null
+);
+
+create index if not exists notification_channels_user_id on notification_channels (user_id);
@@ -0,0 +1 @@
+drop table site_transfers;
\ No newline at end of file
@@ -0,0 +1,11 @@
+create table if not exists site_transfers (
+    id integer not null primary key,
+    site_id integer not null references sites(id),
+    from_user_id integer not null references users(id),
+    to_user_id integer references users(id),
+    token text not null unique,
+    accepted_at integer,
+    created_at integer not null
+);
+
+create index if not exists site_transfers_site_id on site_transfers (site_id);
@@ -0,0 +1 @@
+drop table incidents;
\ No newline at end of file
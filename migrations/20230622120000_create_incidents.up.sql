@@ -0,0 +1,9 @@
+create table if not exists incidents (
+    id integer not null primary key,
+    site_id integer not null references sites(id),
+    status_code integer not null,
+    started_at integer not null,
+    resolved_at integer
+);
+
+create index if not exists incidents_site_id_started_at on incidents (site_id, started_at);
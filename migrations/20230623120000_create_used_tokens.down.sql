@@ -0,0 +1 @@
+drop table used_tokens;
\ No newline at end of file
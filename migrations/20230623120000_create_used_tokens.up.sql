@@ -0,0 +1,5 @@
+create table if not exists used_tokens (
+    nonce text not null primary key,
+    expires_at integer not null,
+    created_at integer not null
+);
@@ -0,0 +1 @@
+drop table notification_routes;
\ No newline at end of file
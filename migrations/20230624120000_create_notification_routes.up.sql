@@ -0,0 +1,5 @@
+create table if not exists notification_routes (
+    channel_id integer not null references notification_channels(id),
+    site_id integer not null references sites(id),
+    primary key (channel_id, site_id)
+);
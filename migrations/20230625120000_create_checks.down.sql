@@ -0,0 +1 @@
+drop table checks;
\ No newline at end of file
@@ -0,0 +1,11 @@
+create table if not exists checks (
+    id integer not null primary key,
+    site_id integer not null references sites(id),
+    status_code integer not null,
+    latency_ms integer not null,
+    failure text,
+    ip text,
+    created_at integer not null
+);
+
+create index if not exists checks_site_id_created_at on checks (site_id, created_at);
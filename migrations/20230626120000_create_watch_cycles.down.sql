@@ -0,0 +1 @@
+drop table watch_cycles;
\ No newline at end of file
@@ -0,0 +1,9 @@
+create table if not exists watch_cycles (
+    id integer not null primary key,
+    sites_checked integer not null,
+    failures integer not null,
+    new_incidents integer not null,
+    duration_ms integer not null,
+    lag_ms integer not null,
+    created_at integer not null
+);
@@ -0,0 +1,3 @@
+drop table notifications_sent;
+alter table notification_channels drop column cooldown_secs;
+alter table notification_channels drop column renotify_secs;
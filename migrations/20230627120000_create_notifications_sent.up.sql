@@ -0,0 +1,12 @@
+alter table notification_channels add column cooldown_secs integer not null default 0;
+alter table notification_channels add column renotify_secs integer not null default 0;
+
+create table if not exists notifications_sent (
+    id integer not null primary key,
+    channel_id integer not null references notification_channels(id),
+    site_id integer not null references sites(id),
+    incident_id integer references incidents(id),
+    created_at integer not null
+);
+
+create index if not exists notifications_sent_channel_id_site_id on notifications_sent (channel_id, site_id, created_at);
@@ -0,0 +1 @@
+alter table incidents drop column last_status_code;
\ No newline at end of file
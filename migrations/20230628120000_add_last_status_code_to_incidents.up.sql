@@ -0,0 +1,3 @@
+alter table incidents add column last_status_code integer;
+
+update incidents set last_status_code = status_code;
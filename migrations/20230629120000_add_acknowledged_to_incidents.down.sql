@@ -0,0 +1,2 @@
+alter table incidents drop column acknowledged_by;
+alter table incidents drop column acknowledged_at;
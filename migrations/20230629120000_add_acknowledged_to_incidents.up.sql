@@ -0,0 +1,2 @@
+alter table incidents add column acknowledged_at integer;
+alter table incidents add column acknowledged_by integer references users(id);
@@ -0,0 +1 @@
+drop table latency_rules;
\ No newline at end of file
@@ -0,0 +1,12 @@
+create table if not exists latency_rules (
+    id integer not null primary key,
+    user_id integer not null references users(id),
+    site_id integer not null references sites(id),
+    percentile integer not null default 95,
+    window_secs integer not null default 900,
+    threshold_ms integer not null default 2000,
+    triggered_at integer,
+    created_at integer not null
+);
+
+create index if not exists latency_rules_site_id on latency_rules (site_id);
@@ -0,0 +1,2 @@
+drop table expiry_rules;
+drop table expiries;
\ No newline at end of file
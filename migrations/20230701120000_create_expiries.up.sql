@@ -0,0 +1,18 @@
+create table if not exists expiries (
+    site_id integer not null references sites(id),
+    kind text not null,
+    expires_at integer,
+    checked_at integer not null,
+    notified_at integer,
+    primary key (site_id, kind)
+);
+
+create table if not exists expiry_rules (
+    id integer not null primary key,
+    user_id integer not null references users(id),
+    site_id integer not null references sites(id),
+    kind text not null,
+    days integer not null,
+    created_at integer not null,
+    unique (site_id, kind)
+);
@@ -0,0 +1,2 @@
+alter table users drop column digest_sent_at;
+alter table users drop column digest;
\ No newline at end of file
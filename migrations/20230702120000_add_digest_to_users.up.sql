@@ -0,0 +1,2 @@
+alter table users add column digest text not null default 'off';
+alter table users add column digest_sent_at integer;
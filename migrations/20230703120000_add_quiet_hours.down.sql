@@ -0,0 +1,5 @@
+drop table queued_notifications;
+alter table sites drop column critical;
+alter table users drop column quiet_end;
+alter table users drop column quiet_start;
+alter table users drop column timezone;
\ No newline at end of file
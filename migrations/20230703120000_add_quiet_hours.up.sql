@@ -0,0 +1,12 @@
+alter table users add column timezone text not null default 'UTC';
+alter table users add column quiet_start integer;
+alter table users add column quiet_end integer;
+alter table sites add column critical boolean not null default false;
+
+create table if not exists queued_notifications (
+    id integer not null primary key,
+    channel_id integer not null references notification_channels(id),
+    user_id integer not null references users(id),
+    notification text not null,
+    created_at integer not null
+);
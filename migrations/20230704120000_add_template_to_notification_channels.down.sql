@@ -0,0 +1 @@
+alter table notification_channels drop column template;
\ No newline at end of file
@@ -0,0 +1 @@
+alter table notification_channels add column template text;
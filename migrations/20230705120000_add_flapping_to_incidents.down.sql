@@ -0,0 +1 @@
+alter table incidents drop column flapping;
\ No newline at end of file
@@ -0,0 +1 @@
+alter table incidents add column flapping boolean not null default false;
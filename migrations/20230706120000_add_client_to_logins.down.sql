@@ -0,0 +1,2 @@
+alter table logins drop column user_agent;
+alter table logins drop column ip_address;
\ No newline at end of file
@@ -0,0 +1,2 @@
+alter table logins add column ip_address text;
+alter table logins add column user_agent text;
@@ -0,0 +1,2 @@
+drop index logins_user_id;
+drop index responses_site_id_updated_at;
\ No newline at end of file
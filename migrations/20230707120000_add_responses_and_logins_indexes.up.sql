@@ -0,0 +1,2 @@
+create index if not exists responses_site_id_updated_at on responses (site_id, updated_at);
+create index if not exists logins_user_id on logins (user_id);
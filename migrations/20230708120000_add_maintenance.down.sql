@@ -0,0 +1,2 @@
+alter table checks drop column maintenance;
+alter table sites drop column maintenance_until;
\ No newline at end of file
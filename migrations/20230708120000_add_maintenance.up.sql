@@ -0,0 +1,2 @@
+alter table sites add column maintenance_until integer;
+alter table checks add column maintenance boolean not null default false;
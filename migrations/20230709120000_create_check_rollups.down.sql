@@ -0,0 +1,3 @@
+drop index checks_created_at;
+drop table checks_daily;
+drop table checks_hourly;
\ No newline at end of file
@@ -0,0 +1,25 @@
+create table if not exists checks_hourly (
+    site_id integer not null references sites(id),
+    hour integer not null,
+    checks integer not null,
+    up integer not null,
+    maintenance integer not null,
+    latency_sum_ms integer not null,
+    latency_min_ms integer not null,
+    latency_max_ms integer not null,
+    primary key (site_id, hour)
+);
+
+create table if not exists checks_daily (
+    site_id integer not null references sites(id),
+    day integer not null,
+    checks integer not null,
+    up integer not null,
+    maintenance integer not null,
+    latency_sum_ms integer not null,
+    latency_min_ms integer not null,
+    latency_max_ms integer not null,
+    primary key (site_id, day)
+);
+
+create index if not exists checks_created_at on checks (created_at);
@@ -0,0 +1,4 @@
+update users set created_at = created_at * 1000, updated_at = updated_at * 1000;
+update logins set created_at = created_at * 1000;
+update sites set created_at = created_at * 1000, updated_at = updated_at * 1000;
+update responses set created_at = created_at * 1000, updated_at = updated_at * 1000;
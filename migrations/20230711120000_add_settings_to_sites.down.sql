@@ -0,0 +1,2 @@
+alter table sites drop column failure_threshold;
+alter table sites drop column interval_secs;
@@ -0,0 +1,2 @@
+alter table sites add column interval_secs integer not null default 300;
+alter table sites add column failure_threshold integer not null default 1;
@@ -0,0 +1,2 @@
+drop index sites_user_id_url;
+create unique index unique_url_user_id on sites (url, user_id);
@@ -0,0 +1,2 @@
+drop index if exists unique_url_user_id;
+create unique index if not exists sites_user_id_url on sites (user_id, url);
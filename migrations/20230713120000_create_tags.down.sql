@@ -0,0 +1,2 @@
+drop table site_tags;
+drop table tags;
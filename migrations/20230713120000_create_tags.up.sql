@@ -0,0 +1,16 @@
+create table if not exists tags (
+    id integer not null primary key,
+    user_id integer not null references users(id),
+    name text not null,
+    created_at integer not null
+);
+
+create unique index if not exists tags_user_id_name on tags (user_id, name);
+
+create table if not exists site_tags (
+    site_id integer not null references sites(id),
+    tag_id integer not null references tags(id),
+    primary key (site_id, tag_id)
+);
+
+create index if not exists site_tags_tag_id on site_tags (tag_id);
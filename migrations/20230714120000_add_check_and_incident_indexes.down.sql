@@ -0,0 +1 @@
+drop index incidents_open_site_id;
@@ -0,0 +1 @@
+create index if not exists incidents_open_site_id on incidents (site_id) where resolved_at is null;
@@ -0,0 +1,9 @@
+create table if not exists login_attempts (
+    id integer not null primary key,
+    ip_address text,
+    login_code text not null,
+    created_at integer not null
+);
+
+create index if not exists login_attempts_ip_address on login_attempts (ip_address, created_at);
+create index if not exists login_attempts_login_code on login_attempts (login_code, created_at);
@@ -0,0 +1,4 @@
+alter table users add column email text;
+alter table users add column email_verified_at integer;
+
+create index if not exists users_email on users (lower(email));
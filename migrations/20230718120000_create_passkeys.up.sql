@@ -0,0 +1,10 @@
+create table if not exists passkeys (
+    id integer not null primary key,
+    user_id integer not null references users(id),
+    credential_id text not null unique,
+    passkey text not null,
+    created_at integer not null,
+    last_used_at integer
+);
+
+create index if not exists passkeys_user_id on passkeys (user_id);
@@ -0,0 +1,18 @@
+create table if not exists organizations (
+    id integer not null primary key,
+    name text not null,
+    user_id integer not null unique references users(id),
+    created_at integer not null,
+    updated_at integer not null
+);
+
+create table if not exists memberships (
+    id integer not null primary key,
+    organization_id integer not null references organizations(id),
+    user_id integer not null references users(id),
+    role text not null default 'member',
+    created_at integer not null,
+    unique (organization_id, user_id)
+);
+
+create index if not exists memberships_user_id on memberships (user_id);
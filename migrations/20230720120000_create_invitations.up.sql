@@ -0,0 +1,10 @@
+create table if not exists invitations (
+    id integer not null primary key,
+    organization_id integer not null references organizations(id),
+    role text not null default 'member',
+    created_by integer not null references users(id),
+    expires_at integer not null,
+    created_at integer not null
+);
+
+create index if not exists invitations_organization_id on invitations (organization_id);
@@ -0,0 +1,9 @@
+create table if not exists share_links (
+    id integer not null primary key,
+    site_id integer not null references sites(id),
+    user_id integer not null references users(id),
+    expires_at integer,
+    created_at integer not null
+);
+
+create index if not exists share_links_site_id on share_links (site_id);
@@ -0,0 +1 @@
+alter table users add column max_sites integer;
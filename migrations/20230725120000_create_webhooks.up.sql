@@ -0,0 +1,22 @@
+create table if not exists webhooks (
+    id integer not null primary key,
+    user_id integer not null references users(id),
+    url text not null,
+    events text not null,
+    secret text not null,
+    created_at integer not null
+);
+
+create index if not exists webhooks_user_id on webhooks (user_id);
+
+create table if not exists webhook_deliveries (
+    id integer not null primary key,
+    webhook_id integer not null references webhooks(id),
+    event text not null,
+    status_code integer,
+    error text,
+    duration_ms integer not null,
+    created_at integer not null
+);
+
+create index if not exists webhook_deliveries_webhook_id on webhook_deliveries (webhook_id);
@@ -0,0 +1,3 @@
+drop index share_links_domain;
+
+alter table share_links drop column domain;
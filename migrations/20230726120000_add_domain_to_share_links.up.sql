@@ -0,0 +1,3 @@
+alter table share_links add column domain text;
+
+create unique index if not exists share_links_domain on share_links (domain);
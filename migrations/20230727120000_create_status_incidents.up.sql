@@ -0,0 +1,21 @@
+create table if not exists status_incidents (
+    id integer not null primary key,
+    site_id integer not null references sites(id),
+    title text not null,
+    status text not null,
+    created_at integer not null,
+    resolved_at integer
+);
+
+create index if not exists status_incidents_site_id on status_incidents (site_id);
+
+create table if not exists incident_updates (
+    id integer not null primary key,
+    status_incident_id integer not null references status_incidents(id),
+    site_id integer not null references sites(id),
+    status text not null,
+    message text not null,
+    created_at integer not null
+);
+
+create index if not exists incident_updates_site_id on incident_updates (site_id);
@@ -0,0 +1,11 @@
+create table if not exists subscribers (
+    id integer not null primary key,
+    share_link_id integer not null references share_links(id),
+    site_id integer not null references sites(id),
+    email text not null,
+    confirmed_at integer,
+    created_at integer not null,
+    unique (share_link_id, email)
+);
+
+create index if not exists subscribers_site_id on subscribers (site_id);
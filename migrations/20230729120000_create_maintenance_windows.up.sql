@@ -0,0 +1,10 @@
+create table if not exists maintenance_windows (
+    id integer not null primary key,
+    site_id integer not null references sites(id),
+    title text not null,
+    starts_at integer not null,
+    ends_at integer not null,
+    created_at integer not null
+);
+
+create index if not exists maintenance_windows_site_id on maintenance_windows (site_id, starts_at);
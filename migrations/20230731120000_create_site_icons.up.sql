@@ -0,0 +1,6 @@
+create table if not exists site_icons (
+    site_id integer not null primary key references sites(id),
+    content_type text,
+    data blob,
+    fetched_at integer not null
+);
@@ -0,0 +1 @@
+alter table sites add column paused_at integer;
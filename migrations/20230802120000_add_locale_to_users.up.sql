@@ -0,0 +1 @@
+alter table users add column locale text not null default 'en';
@@ -0,0 +1,2 @@
+alter table api_tokens add column created_by integer references users(id);
+update api_tokens set created_by = user_id where user_id not in (select user_id from organizations);
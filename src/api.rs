@@ -0,0 +1,720 @@
+//! The versioned api under `/api/v1`. Unlike the json endpoints the ui
+//! talks to, every error the handlers return comes back in one envelope,
+//! `{"error": {"code": "DuplicateSite", "message": "..."}}`, with a status
+//! that matches it.
+
+use crate::{db, emit_sites_created, rate_limit_retry_after, site_error_message};
+use chrono::{TimeZone, Utc};
+use futures::{stream, StreamExt};
+use salvo::{
+    async_trait, handler,
+    http::Method,
+    hyper::header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE, RETRY_AFTER},
+    prelude::StatusCode,
+    writer::Json,
+    Depot, FlowCtrl, Request, Response, Router, Writer,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use updown::{
+    models::Check, webhooks::Event, ApiToken, AppError, BatchError, CheckBucket, Database, Page,
+    Resolution, Scope, Site, SiteOp, SiteOpStatus, Uptime, User, Webhook, WebhookDelivery,
+};
+
+pub fn router() -> Router {
+    Router::with_path("api/v1")
+        .hoop(rate_limit_handler)
+        .hoop(token_handler)
+        .push(Router::with_path("sites").get(list_sites).post(add_site))
+        .push(Router::with_path("sites:batch").post(batch_sites))
+        .push(
+            Router::with_path("sites/<id>")
+                .get(show_site)
+                .patch(update_site)
+                .delete(delete_site),
+        )
+        .push(Router::with_path("sites/<id>/checks").get(site_checks))
+        .push(Router::with_path("sites/<id>/checks.csv").get(site_checks_csv))
+        .push(Router::with_path("sites/<id>/uptime").get(site_uptime))
+        .push(
+            Router::with_path("webhooks")
+                .get(list_webhooks)
+                .post(add_webhook),
+        )
+        .push(Router::with_path("webhooks/<id>").delete(delete_webhook))
+        .push(Router::with_path("webhooks/<id>/deliveries").get(webhook_deliveries))
+}
+
+#[derive(Serialize, Debug)]
+struct ErrorBody {
+    code: String,
+    message: String,
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    body: ErrorBody,
+}
+
+impl ApiError {
+    fn not_found() -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            body: ErrorBody {
+                code: "NotFound".into(),
+                message: "There's no site with that id".into(),
+            },
+        }
+    }
+}
+
+impl ApiError {
+    /// Like the `From` impl but with the missing row reported as a missing
+    /// webhook.
+    fn webhook(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => Self {
+                status: StatusCode::NOT_FOUND,
+                body: ErrorBody {
+                    code: "NotFound".into(),
+                    message: "There's no webhook with that id".into(),
+                },
+            },
+            source => AppError::DatabaseSelect { source }.into(),
+        }
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(err: AppError) -> Self {
+        let status = match &err {
+            AppError::Login | AppError::ApiToken => StatusCode::UNAUTHORIZED,
+            AppError::JsonParse | AppError::QueryParams | AppError::BatchSize => {
+                StatusCode::BAD_REQUEST
+            }
+            AppError::Webhook => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::UrlEmpty
+            | AppError::UrlInvalid
+            | AppError::UrlScheme
+            | AppError::SiteSettings => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::DuplicateSite => StatusCode::CONFLICT,
+            AppError::Role | AppError::SiteLimit | AppError::Scope => StatusCode::FORBIDDEN,
+            AppError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            AppError::DatabaseSelect {
+                source: sqlx::Error::RowNotFound,
+            } => return Self::not_found(),
+            _ => {
+                tracing::error!("api: {}", err.chain());
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        let message = match err {
+            AppError::Login => "Log in or send an api token as a bearer token",
+            AppError::ApiToken => "That api token doesn't exist or was revoked",
+            AppError::Scope => "That api token can't do this",
+            AppError::QueryParams => "Check from, to, resolution and window",
+            AppError::RateLimited => "Too many requests, wait as long as Retry-After says",
+            AppError::BatchSize => "A batch can have up to 100 operations",
+            AppError::Webhook => {
+                "A webhook needs an http or https url and at least one known event"
+            }
+            AppError::JsonParse => "The body isn't the json this endpoint expects",
+            AppError::Role => "Your role can't change sites",
+            _ => site_error_message(&err),
+        };
+        Self {
+            status,
+            body: ErrorBody {
+                code: err.to_string(),
+                message: message.into(),
+            },
+        }
+    }
+}
+
+impl From<salvo::Error> for ApiError {
+    fn from(err: salvo::Error) -> Self {
+        tracing::error!("api: {}", err);
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            body: ErrorBody {
+                code: "Internal".into(),
+                message: "Something went wrong, try again".into(),
+            },
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(source: sqlx::Error) -> Self {
+        AppError::DatabaseSelect { source }.into()
+    }
+}
+
+#[async_trait]
+impl Writer for ApiError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        #[derive(Serialize)]
+        struct Envelope {
+            error: ErrorBody,
+        }
+        res.set_status_code(self.status);
+        res.render(Json(Envelope { error: self.body }));
+    }
+}
+
+type Result<T> = std::result::Result<T, ApiError>;
+
+/// The same limit as the public routes, with the error in the envelope.
+#[handler]
+async fn rate_limit_handler(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    ctrl: &mut FlowCtrl,
+) {
+    let Some(secs) = rate_limit_retry_after(req).await else {
+        return;
+    };
+    if res.add_header(RETRY_AFTER, secs.to_string(), true).is_err() {
+        tracing::warn!("could not set retry-after");
+    }
+    ApiError::from(AppError::RateLimited)
+        .write(req, depot, res)
+        .await;
+    ctrl.skip_rest();
+}
+
+/// A bearer token stands in for the session on this request. Reading
+/// needs the `read` scope and everything else `write`.
+#[handler]
+async fn token_handler(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    ctrl: &mut FlowCtrl,
+) {
+    let Some(header) = req.header::<String>(AUTHORIZATION) else {
+        return;
+    };
+    let scope = match [Method::GET, Method::HEAD, Method::OPTIONS].contains(req.method()) {
+        true => Scope::Read,
+        false => Scope::Write,
+    };
+    match authorize(&header, scope).await {
+        Ok((user, api_token)) => {
+            depot.inject(user);
+            depot.inject(api_token);
+        }
+        Err(err) => {
+            err.write(req, depot, res).await;
+            ctrl.skip_rest();
+        }
+    }
+}
+
+async fn authorize(header: &str, scope: Scope) -> Result<(User, ApiToken)> {
+    let token = header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::ApiToken)?
+        .trim();
+    let api_token = db()
+        .use_api_token(token)
+        .await
+        .map_err(|_| AppError::ApiToken)?;
+    if !api_token.allows(scope) {
+        return Err(AppError::Scope.into());
+    }
+    let user = db().user_by_id(api_token.user_id).await?;
+    // an organization's token can do what the member who made it still can
+    match db().organization_by_user_id(user.id).await {
+        Ok(organization) => {
+            let membership = db()
+                .membership(organization.id, api_token.created_by.unwrap_or_default())
+                .await
+                .map_err(|_| AppError::ApiToken)?;
+            if scope == Scope::Write && !membership.role.can_edit() {
+                return Err(AppError::Role.into());
+            }
+        }
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(err) => return Err(err.into()),
+    }
+    Ok((user, api_token))
+}
+
+fn current_user(depot: &Depot) -> Result<&User> {
+    Ok(depot.obtain::<User>().ok_or(AppError::Login)?)
+}
+
+/// The site when it belongs to the user, sites of other users are
+/// reported as missing so ids can't be probed.
+async fn owned_site(user_id: i64, req: &Request) -> Result<Site> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    site_owned_by(user_id, id).await
+}
+
+async fn site_owned_by(user_id: i64, id: i64) -> Result<Site> {
+    let site = db().site_by_id(id).await?;
+    match site.user_id == user_id {
+        true => Ok(site),
+        false => Err(ApiError::not_found()),
+    }
+}
+
+#[derive(Deserialize)]
+struct NewSite {
+    url: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    interval_secs: Option<i64>,
+    #[serde(default)]
+    failure_threshold: Option<i64>,
+}
+
+impl NewSite {
+    fn site(self, user_id: i64) -> std::result::Result<Site, AppError> {
+        let mut site = Site::default();
+        site.user_id = user_id;
+        site.url = Site::normalize_url(&self.url)?;
+        site.name = self.name.filter(|name| !name.trim().is_empty());
+        site.interval_secs = self.interval_secs.unwrap_or(Site::INTERVALS[0]);
+        site.failure_threshold = self.failure_threshold.unwrap_or(1);
+        site.validate()?;
+        // left out, the site gets the account's default interval
+        site.interval_secs = self.interval_secs.unwrap_or_default();
+        Ok(site)
+    }
+}
+
+/// Fields left out keep their value, an empty name clears it.
+#[derive(Deserialize)]
+struct SiteChanges {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    interval_secs: Option<i64>,
+    #[serde(default)]
+    failure_threshold: Option<i64>,
+}
+
+impl SiteChanges {
+    fn apply(self, mut site: Site) -> std::result::Result<Site, AppError> {
+        if let Some(url) = self.url {
+            site.url = Site::normalize_url(&url)?;
+        }
+        if let Some(name) = self.name {
+            site.name = Some(name).filter(|name| !name.trim().is_empty());
+        }
+        site.interval_secs = self.interval_secs.unwrap_or(site.interval_secs);
+        site.failure_threshold = self.failure_threshold.unwrap_or(site.failure_threshold);
+        site.validate()?;
+        Ok(site)
+    }
+}
+
+#[handler]
+async fn list_sites(depot: &mut Depot, req: &mut Request) -> Result<Json<Page<Site>>> {
+    let user = current_user(depot)?;
+    let cursor = req.query::<i64>("cursor");
+    let limit = Page::<Site>::limit(req.query::<i64>("limit"));
+    let page = db().sites_page(user.id, cursor, limit).await?;
+    Ok(Json(page))
+}
+
+#[handler]
+async fn add_site(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<Json<Site>> {
+    let user_id = current_user(depot)?.id;
+    let site = req
+        .parse_json::<NewSite>()
+        .await
+        .map_err(|_| AppError::JsonParse)?
+        .site(user_id)?;
+    let site = db().insert_site(site).await?;
+    emit_sites_created(std::slice::from_ref(&site));
+    res.set_status_code(StatusCode::CREATED);
+    Ok(Json(site))
+}
+
+#[handler]
+async fn show_site(depot: &mut Depot, req: &mut Request) -> Result<Json<Site>> {
+    let user = current_user(depot)?;
+    let site = owned_site(user.id, req).await?;
+    Ok(Json(site))
+}
+
+#[handler]
+async fn update_site(depot: &mut Depot, req: &mut Request) -> Result<Json<Site>> {
+    let user_id = current_user(depot)?.id;
+    let site = owned_site(user_id, req).await?;
+    let site = req
+        .parse_json::<SiteChanges>()
+        .await
+        .map_err(|_| AppError::JsonParse)?
+        .apply(site)?;
+    let site = db().update_site(site).await?;
+    Ok(Json(site))
+}
+
+#[handler]
+async fn delete_site(depot: &mut Depot, req: &mut Request) -> Result<Json<Site>> {
+    let user_id = current_user(depot)?.id;
+    let site = owned_site(user_id, req).await?;
+    let site = db().delete_site(user_id, site.id).await?;
+    Ok(Json(site))
+}
+
+/// Raw checks are capped, ask for hours or days over long ranges.
+const MAX_CHECKS: i64 = 10_000;
+
+/// Either raw checks or hourly or daily buckets, depending on the
+/// `resolution` asked for.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum History {
+    Checks(Vec<Check>),
+    Buckets(Vec<CheckBucket>),
+}
+
+/// `from` and `to` in unix seconds, the last day when they're left out.
+fn time_range(req: &Request) -> Result<(i64, i64)> {
+    let to = req.query::<i64>("to").unwrap_or_else(Database::now);
+    let from = req.query::<i64>("from").unwrap_or(to - 24 * 60 * 60);
+    match from < to {
+        true => Ok((from, to)),
+        false => Err(AppError::QueryParams.into()),
+    }
+}
+
+/// `from` and `to` are unix seconds and default to the last day,
+/// `resolution` is `raw`, `hour` or `day`.
+#[handler]
+async fn site_checks(depot: &mut Depot, req: &mut Request) -> Result<Json<History>> {
+    let user = current_user(depot)?;
+    let site = owned_site(user.id, req).await?;
+    let (from, to) = time_range(req)?;
+    let history = match req.query::<String>("resolution").as_deref() {
+        None | Some("raw") => History::Checks(
+            db().checks_between(site.id, from, to, 0, MAX_CHECKS)
+                .await?,
+        ),
+        Some("hour") => History::Buckets(
+            db().check_buckets(site.id, from, to, Resolution::Hour)
+                .await?,
+        ),
+        Some("day") => History::Buckets(
+            db().check_buckets(site.id, from, to, Resolution::Day)
+                .await?,
+        ),
+        Some(_) => return Err(AppError::QueryParams.into()),
+    };
+    Ok(Json(history))
+}
+
+/// Most operations one batch can hold.
+const MAX_BATCH: usize = 100;
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Operation {
+    Create(NewSite),
+    Update {
+        id: i64,
+        #[serde(flatten)]
+        changes: SiteChanges,
+    },
+    Delete {
+        id: i64,
+    },
+}
+
+#[derive(Deserialize)]
+struct Batch {
+    operations: Vec<Operation>,
+}
+
+/// What happened to one operation. When one fails the others come back
+/// as `rolled_back` or `skipped`.
+#[derive(Serialize)]
+struct OperationResult {
+    index: usize,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    site: Option<Site>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorBody>,
+}
+
+#[derive(Serialize)]
+struct BatchResults {
+    results: Vec<OperationResult>,
+}
+
+impl BatchResults {
+    /// Marks the operations before `index` rolled back and the ones after
+    /// it skipped.
+    fn failed(len: usize, index: usize, error: ErrorBody) -> Self {
+        let mut error = Some(error);
+        let results = (0..len)
+            .map(|i| OperationResult {
+                index: i,
+                status: match i.cmp(&index) {
+                    Ordering::Less => "rolled_back",
+                    Ordering::Equal => "failed",
+                    Ordering::Greater => "skipped",
+                },
+                site: None,
+                error: match i == index {
+                    true => error.take(),
+                    false => None,
+                },
+            })
+            .collect();
+        Self { results }
+    }
+}
+
+/// Creates, updates and deletes sites in one transaction, all or
+/// nothing. Sending the same batch again is safe: creating a url that's
+/// already monitored and deleting a site that's gone both succeed.
+#[handler]
+async fn batch_sites(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let user_id = current_user(depot)?.id;
+    let batch = req
+        .parse_json::<Batch>()
+        .await
+        .map_err(|_| AppError::JsonParse)?;
+    if batch.operations.len() > MAX_BATCH {
+        return Err(AppError::BatchSize.into());
+    }
+    let len = batch.operations.len();
+    let mut ops = vec![];
+    for (index, operation) in batch.operations.into_iter().enumerate() {
+        let op = match operation {
+            Operation::Create(new_site) => new_site
+                .site(user_id)
+                .map(SiteOp::Create)
+                .map_err(ApiError::from),
+            Operation::Update { id, changes } => match site_owned_by(user_id, id).await {
+                Ok(site) => changes
+                    .apply(site)
+                    .map(SiteOp::Update)
+                    .map_err(ApiError::from),
+                Err(err) => Err(err),
+            },
+            Operation::Delete { id } => Ok(SiteOp::Delete(id)),
+        };
+        match op {
+            Ok(op) => ops.push(op),
+            Err(err) => {
+                res.set_status_code(err.status);
+                res.render(Json(BatchResults::failed(len, index, err.body)));
+                return Ok(());
+            }
+        }
+    }
+    match db().apply_site_ops(user_id, ops).await {
+        Ok(results) => {
+            let created = results
+                .iter()
+                .filter(|result| result.status == SiteOpStatus::Created)
+                .filter_map(|result| result.site.clone())
+                .collect::<Vec<_>>();
+            emit_sites_created(&created);
+            res.render(Json(BatchResults {
+                results: results
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, result)| OperationResult {
+                        index,
+                        status: result.status.as_str(),
+                        site: result.site,
+                        error: None,
+                    })
+                    .collect(),
+            }));
+        }
+        Err(BatchError { index, error }) => {
+            let error = ApiError::from(error);
+            res.set_status_code(error.status);
+            res.render(Json(BatchResults::failed(len, index, error.body)));
+        }
+    }
+    Ok(())
+}
+
+/// One csv line per check, the timestamp in rfc 3339 so spreadsheets
+/// read it as a date.
+fn csv_line(check: &Check) -> String {
+    let timestamp = Utc
+        .timestamp_opt(check.created_at, 0)
+        .single()
+        .map(|at| at.to_rfc3339())
+        .unwrap_or_default();
+    let failure = check
+        .failure
+        .map(|kind| format!("{:?}", kind).to_lowercase())
+        .unwrap_or_default();
+    format!(
+        "{},{},{},{}\n",
+        timestamp, check.status_code, check.latency_ms, failure
+    )
+}
+
+/// The raw checks between `from` and `to` as csv. Rows are read and sent
+/// a batch at a time so long ranges don't pile up in memory.
+#[handler]
+async fn site_checks_csv(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    const BATCH: i64 = 1000;
+    let user = current_user(depot)?;
+    let site = owned_site(user.id, req).await?;
+    let (from, to) = time_range(req)?;
+    let site_id = site.id;
+    let header = stream::once(async {
+        Ok::<_, sqlx::Error>("timestamp,status_code,latency_ms,failure\n".to_string())
+    });
+    // the state is the id to continue after, none once a batch came up short
+    let rows = stream::unfold(Some(0), move |after| async move {
+        let after = after?;
+        match db().checks_between(site_id, from, to, after, BATCH).await {
+            Ok(checks) if checks.is_empty() => None,
+            Ok(checks) => {
+                let next = match checks.len() as i64 == BATCH {
+                    true => checks.last().map(|check| check.id),
+                    false => None,
+                };
+                Some((Ok(checks.iter().map(csv_line).collect::<String>()), next))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+    res.add_header(CONTENT_TYPE, "text/csv; charset=utf-8", true)?;
+    res.add_header(
+        CONTENT_DISPOSITION,
+        format!("attachment; filename=\"site-{}-checks.csv\"", site_id),
+        true,
+    )?;
+    res.streaming(header.chain(rows))?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct UptimeBody {
+    #[serde(flatten)]
+    uptime: Uptime,
+    percentage: Option<f64>,
+}
+
+/// Reads windows like `30d`, `12h` or plain seconds.
+pub(crate) fn window_secs(window: &str) -> Option<i64> {
+    let (count, unit) = match window.char_indices().last()? {
+        (at, 'd') => (&window[..at], 86_400),
+        (at, 'h') => (&window[..at], 3600),
+        _ => (window, 1),
+    };
+    count
+        .parse::<i64>()
+        .ok()
+        .filter(|count| *count > 0)
+        .and_then(|count| count.checked_mul(unit))
+}
+
+/// `window` defaults to `30d`.
+#[handler]
+async fn site_uptime(depot: &mut Depot, req: &mut Request) -> Result<Json<UptimeBody>> {
+    let user = current_user(depot)?;
+    let site = owned_site(user.id, req).await?;
+    let window = req
+        .query::<String>("window")
+        .unwrap_or_else(|| "30d".to_string());
+    let secs = window_secs(&window).ok_or(AppError::QueryParams)?;
+    let uptime = db().uptime_by_site(site.id, secs).await?;
+    Ok(Json(UptimeBody {
+        percentage: uptime.percentage(),
+        uptime,
+    }))
+}
+
+#[derive(Deserialize)]
+struct NewWebhook {
+    url: String,
+    events: Vec<String>,
+}
+
+impl NewWebhook {
+    fn events(&self) -> std::result::Result<Vec<Event>, AppError> {
+        let url = reqwest::Url::parse(&self.url).map_err(|_| AppError::Webhook)?;
+        if !matches!(url.scheme(), "http" | "https") || self.events.is_empty() {
+            return Err(AppError::Webhook);
+        }
+        self.events
+            .iter()
+            .map(|name| Event::parse(name).ok_or(AppError::Webhook))
+            .collect()
+    }
+}
+
+/// The secret is only sent back when the webhook is made.
+#[derive(Serialize)]
+struct CreatedWebhook {
+    webhook: Webhook,
+    secret: String,
+}
+
+#[handler]
+async fn list_webhooks(depot: &mut Depot) -> Result<Json<Vec<Webhook>>> {
+    let user = current_user(depot)?;
+    let webhooks = db().webhooks_by_user_id(user.id).await?;
+    Ok(Json(webhooks))
+}
+
+#[handler]
+async fn add_webhook(
+    depot: &mut Depot,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<Json<CreatedWebhook>> {
+    let user_id = current_user(depot)?.id;
+    let new_webhook = req
+        .parse_json::<NewWebhook>()
+        .await
+        .map_err(|_| AppError::JsonParse)?;
+    let events = new_webhook.events()?;
+    let (webhook, secret) = db()
+        .insert_webhook(user_id, &new_webhook.url, &events)
+        .await
+        .map_err(|source| AppError::DatabaseInsert { source })?;
+    res.set_status_code(StatusCode::CREATED);
+    Ok(Json(CreatedWebhook { webhook, secret }))
+}
+
+#[handler]
+async fn delete_webhook(depot: &mut Depot, req: &mut Request) -> Result<Json<Webhook>> {
+    let user_id = current_user(depot)?.id;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let webhook = db()
+        .delete_webhook(user_id, id)
+        .await
+        .map_err(ApiError::webhook)?;
+    Ok(Json(webhook))
+}
+
+/// The last [`Database::WEBHOOK_DELIVERIES`], newest first.
+#[handler]
+async fn webhook_deliveries(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<Vec<WebhookDelivery>>> {
+    let user_id = current_user(depot)?.id;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let webhook = db()
+        .webhook_by_id(user_id, id)
+        .await
+        .map_err(ApiError::webhook)?;
+    let deliveries = db().webhook_deliveries(webhook.id).await?;
+    Ok(Json(deliveries))
+}
@@ -0,0 +1,53 @@
+use crate::Database;
+use chrono::{TimeZone, Utc};
+use std::path::{Path, PathBuf};
+
+/// Backups are named `updown-<utc time>.sqlite3`, so sorting them by name
+/// sorts them by age.
+const PREFIX: &str = "updown-";
+const EXTENSION: &str = ".sqlite3";
+
+/// Snapshots the database into `dir` and deletes all but the newest `keep`
+/// backups there.
+pub async fn run(db: &Database, dir: &Path, keep: usize) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(Database::now()));
+    db.backup(&path.to_string_lossy()).await?;
+    let removed = rotate(dir, keep)?;
+    if !removed.is_empty() {
+        tracing::info!(removed = removed.len(), "rotated old backups");
+    }
+    Ok(path)
+}
+
+pub fn file_name(now: i64) -> String {
+    let time = Utc
+        .timestamp_opt(now, 0)
+        .single()
+        .map(|time| time.format("%Y%m%d-%H%M%S").to_string())
+        .unwrap_or_else(|| now.to_string());
+    format!("{}{}{}", PREFIX, time, EXTENSION)
+}
+
+/// Deletes the oldest backups in `dir` so `keep` are left, and returns the
+/// ones it deleted. Other files in `dir` are left alone.
+pub fn rotate(dir: &Path, keep: usize) -> std::io::Result<Vec<PathBuf>> {
+    let mut backups = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.starts_with(PREFIX) && name.ends_with(EXTENSION)
+                })
+        })
+        .collect::<Vec<_>>();
+    backups.sort();
+    let stale = backups.len().saturating_sub(keep);
+    let removed = backups.drain(..stale).collect::<Vec<_>>();
+    for path in &removed {
+        std::fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
@@ -0,0 +1,166 @@
+use crate::{models::Response, Database, Site};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// Hard caps that apply to every check regardless of the site's settings,
+/// so a broken or hostile target can't tie up the watcher.
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+pub const MAX_CHECK_DURATION: Duration = Duration::from_secs(30);
+pub const MAX_DNS_DURATION: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum FailureKind {
+    Dns,
+    Connect,
+    Timeout,
+    Status,
+    Limit,
+    Other,
+}
+
+impl FailureKind {
+    /// What one failed check ran into, where `Display` names many.
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::Dns => "dns error",
+            FailureKind::Connect => "connection error",
+            FailureKind::Timeout => "timeout",
+            FailureKind::Status => "bad status code",
+            FailureKind::Limit => "exceeded check limits",
+            FailureKind::Other => "request error",
+        }
+    }
+}
+
+impl Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            FailureKind::Dns => "dns errors",
+            FailureKind::Connect => "connection errors",
+            FailureKind::Timeout => "timeouts",
+            FailureKind::Status => "bad status codes",
+            FailureKind::Limit => "exceeded check limits",
+            FailureKind::Other => "request errors",
+        };
+        f.write_str(kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub site_id: i64,
+    pub status_code: i64,
+    pub ip: Option<IpAddr>,
+    pub failure: Option<FailureKind>,
+    pub latency_ms: i64,
+    /// Taken during the site's maintenance window.
+    pub maintenance: bool,
+}
+
+impl Check {
+    fn new(site: &Site) -> Self {
+        Self {
+            site_id: site.id,
+            status_code: 0,
+            ip: None,
+            failure: None,
+            latency_ms: 0,
+            maintenance: site.in_maintenance(Database::now()),
+        }
+    }
+
+    pub fn response(&self) -> Response {
+        let mut response = Response::default();
+        response.site_id = self.site_id;
+        response.status_code = self.status_code;
+        response
+    }
+}
+
+/// Requests the site once, recording which address it resolved to and
+/// why it failed if it did. A site that can't be reached at all, or takes
+/// longer than the global limits, counts as down with a status code of 0.
+pub async fn check(client: &reqwest::Client, site: &Site) -> Check {
+    let started = Instant::now();
+    match tokio::time::timeout(MAX_CHECK_DURATION, run(client, site)).await {
+        Ok(check) => check,
+        Err(_) => {
+            let mut check = Check::new(site);
+            check.failure = Some(FailureKind::Limit);
+            check.latency_ms = started.elapsed().as_millis() as i64;
+            check
+        }
+    }
+}
+
+async fn run(client: &reqwest::Client, site: &Site) -> Check {
+    let mut check = Check::new(site);
+    let Ok(url) = Url::parse(&site.url) else {
+        check.failure = Some(FailureKind::Other);
+        return check;
+    };
+    check.ip = match tokio::time::timeout(MAX_DNS_DURATION, resolve(&url)).await {
+        Ok(Some(ip)) => Some(ip),
+        Ok(None) => {
+            check.failure = Some(FailureKind::Dns);
+            return check;
+        }
+        Err(_) => {
+            check.failure = Some(FailureKind::Limit);
+            return check;
+        }
+    };
+    let started = Instant::now();
+    let result = client.get(url).send().await;
+    check.latency_ms = started.elapsed().as_millis() as i64;
+    match result {
+        Ok(res) => {
+            check.status_code = res.status().as_u16() as i64;
+            if !check.response().is_up() {
+                check.failure = Some(FailureKind::Status);
+            }
+            if let Some(failure) = drain(res).await {
+                check.failure = Some(failure);
+            }
+        }
+        Err(err) if err.is_timeout() => check.failure = Some(FailureKind::Timeout),
+        Err(err) if err.is_connect() => check.failure = Some(FailureKind::Connect),
+        Err(_) => check.failure = Some(FailureKind::Other),
+    }
+    check
+}
+
+/// Reads the body chunk by chunk without keeping it, stopping once it
+/// goes over `MAX_BODY_BYTES`.
+async fn drain(mut res: reqwest::Response) -> Option<FailureKind> {
+    let mut read = 0;
+    loop {
+        match res.chunk().await {
+            Ok(Some(chunk)) => {
+                read += chunk.len();
+                if read > MAX_BODY_BYTES {
+                    return Some(FailureKind::Limit);
+                }
+            }
+            Ok(None) => return None,
+            Err(_) => return Some(FailureKind::Other),
+        }
+    }
+}
+
+async fn resolve(url: &Url) -> Option<IpAddr> {
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    tokio::net::lookup_host((host, port))
+        .await
+        .ok()?
+        .next()
+        .map(|addr| addr.ip())
+}
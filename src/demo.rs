@@ -0,0 +1,94 @@
+use crate::{
+    checker::{Check, FailureKind},
+    AppError, Database, Incident, Site,
+};
+use rand::Rng;
+
+/// The example sites, their names and typical latency in ms.
+pub const SITES: [(&str, &str, i64); 3] = [
+    ("https://example.com", "Marketing site", 120),
+    ("https://example.org", "Docs", 310),
+    ("https://example.net", "API", 80),
+];
+/// How far back the made up checks go.
+pub const HISTORY_SECS: i64 = 24 * 60 * 60;
+/// The last site had an outage this long, this long ago.
+const OUTAGE_SECS: i64 = 20 * 60;
+const OUTAGE_AGO_SECS: i64 = 6 * 60 * 60;
+
+/// Adds the example sites to the user's account with a day of made up
+/// checks and one resolved outage, so a new instance has something to
+/// look at. Sites the user already monitors are left alone.
+pub async fn seed(db: &Database, user_id: i64) -> Result<Vec<Site>, AppError> {
+    let sites = SITES
+        .iter()
+        .map(|(url, name, _)| Site {
+            user_id,
+            url: url.to_string(),
+            name: Some(name.to_string()),
+            interval_secs: Site::INTERVALS[0],
+            failure_threshold: 1,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    let (inserted, _) = db.insert_sites(&sites).await?;
+    let now = Database::now();
+    let outage = (now - OUTAGE_AGO_SECS)..(now - OUTAGE_AGO_SECS + OUTAGE_SECS);
+    let mut rng = rand::thread_rng();
+    let mut checks = vec![];
+    let mut incidents = vec![];
+    for site in &inserted {
+        let latency_ms = SITES
+            .iter()
+            .find(|(url, _, _)| *url == site.url)
+            .map_or(100, |(_, _, latency_ms)| *latency_ms);
+        let has_outage = SITES.last().is_some_and(|(url, _, _)| *url == site.url);
+        let down_during = |at: i64| has_outage && outage.contains(&at);
+        let mut at = now - HISTORY_SECS;
+        while at < now {
+            let (status_code, failure, latency_ms) = match down_during(at) {
+                true => (503, Some(FailureKind::Status), rng.gen_range(20..60)),
+                false => (
+                    200,
+                    None,
+                    latency_ms + rng.gen_range(-latency_ms / 4..=latency_ms / 2),
+                ),
+            };
+            let check = Check {
+                site_id: site.id,
+                status_code,
+                ip: None,
+                failure,
+                latency_ms,
+                maintenance: false,
+            };
+            checks.push((check, at));
+            at += site.interval_secs;
+        }
+        if has_outage {
+            incidents.push(Incident {
+                site_id: site.id,
+                status_code: 503,
+                started_at: outage.start,
+                resolved_at: Some(outage.end),
+                last_status_code: Some(503),
+                ..Default::default()
+            });
+        }
+    }
+    db.insert_history(&checks, &incidents)
+        .await
+        .map_err(|source| AppError::DatabaseInsert { source })?;
+    for site in &inserted {
+        if let Some((check, _)) = checks
+            .iter()
+            .rev()
+            .find(|(check, _)| check.site_id == site.id)
+        {
+            db.upsert_response(check.response())
+                .await
+                .map_err(|source| AppError::DatabaseInsert { source })?;
+        }
+    }
+    Ok(inserted)
+}
@@ -0,0 +1,134 @@
+use crate::{
+    expiry::ExpiryKind,
+    notifications::{format_duration, Dispatcher},
+    Database, User,
+};
+use serde::{Deserialize, Serialize};
+
+/// How many of the slowest sites a digest lists.
+pub const SLOWEST: usize = 3;
+/// Certificates expiring within this many seconds make it into the digest.
+pub const EXPIRY_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Frequency {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl Frequency {
+    pub fn period_secs(&self) -> Option<i64> {
+        match self {
+            Frequency::Off => None,
+            Frequency::Daily => Some(24 * 60 * 60),
+            Frequency::Weekly => Some(7 * 24 * 60 * 60),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Frequency::Off => "",
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+        }
+    }
+}
+
+/// Mails every user whose digest is due a summary of the period.
+pub async fn send_digests(db: &Database, dispatcher: &Dispatcher<'_>) {
+    let users = match db.users_due_digest(Database::now()).await {
+        Ok(users) => users,
+        Err(err) => {
+            tracing::error!("could not load digest users: {}", err);
+            return;
+        }
+    };
+    for user in users {
+        let body = match body(db, &user).await {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::error!(user_id = user.id, "could not build digest: {}", err);
+                continue;
+            }
+        };
+        let subject = format!("Your {} updown digest", user.digest.name());
+        match dispatcher.digest(user.id, &subject, body).await {
+            Ok(_) => _ = db.set_digest_sent(user.id).await,
+            Err(err) => {
+                tracing::error!(user_id = user.id, "could not send digest: {}", err.chain())
+            }
+        }
+    }
+}
+
+async fn body(db: &Database, user: &User) -> Result<String, sqlx::Error> {
+    let now = Database::now();
+    let since = now - user.digest.period_secs().unwrap_or_default();
+    let summaries = db.site_summaries(user.id, since).await?;
+    let incidents = db.incidents_since(user.id, since).await?;
+    let expiries = db
+        .expiries_before(user.id, ExpiryKind::Certificate, now + EXPIRY_WINDOW_SECS)
+        .await?;
+    let url = |site_id: i64| {
+        summaries
+            .iter()
+            .find(|summary| summary.site_id == site_id)
+            .map(|summary| summary.url.clone())
+            .unwrap_or_default()
+    };
+
+    let mut lines = vec!["Uptime".to_string()];
+    for summary in &summaries {
+        let uptime = match summary.uptime() {
+            Some(uptime) => format!("{:.2}%", uptime),
+            None => "no checks".to_string(),
+        };
+        lines.push(format!("  {} {}", summary.url, uptime));
+    }
+
+    let mut slowest = summaries
+        .iter()
+        .filter(|summary| summary.latency_ms.is_some())
+        .collect::<Vec<_>>();
+    slowest.sort_by_key(|summary| std::cmp::Reverse(summary.latency_ms));
+    lines.push(String::new());
+    lines.push("Slowest".to_string());
+    for summary in slowest.iter().take(SLOWEST) {
+        let latency_ms = summary.latency_ms.unwrap_or_default();
+        lines.push(format!("  {} {} ms", summary.url, latency_ms));
+    }
+
+    lines.push(String::new());
+    lines.push("Incidents".to_string());
+    if incidents.is_empty() {
+        lines.push("  none".to_string());
+    }
+    for incident in &incidents {
+        let duration = match incident.duration() {
+            Some(secs) => format!("down for {}", format_duration(secs)),
+            None => "still down".to_string(),
+        };
+        lines.push(format!(
+            "  {} {} ({})",
+            url(incident.site_id),
+            duration,
+            incident.status_code
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push("Certificates expiring".to_string());
+    if expiries.is_empty() {
+        lines.push("  none".to_string());
+    }
+    for expiry in &expiries {
+        let days = (expiry.expires_at.unwrap_or(now) - now).max(0) / 86_400;
+        lines.push(format!("  {} in {} days", url(expiry.site_id), days));
+    }
+
+    Ok(lines.join("\n"))
+}
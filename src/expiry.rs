@@ -0,0 +1,144 @@
+use crate::Site;
+use reqwest::Url;
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, ClientConfig, ServerName,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, sync::Arc, time::Duration, time::SystemTime};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+/// Expiry dates are looked up again once they are older than this.
+pub const REFRESH_SECS: i64 = 24 * 60 * 60;
+pub const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+pub const RDAP_URL: &str = "https://rdap.org/domain";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum ExpiryKind {
+    Certificate,
+    Domain,
+}
+
+impl ExpiryKind {
+    pub const ALL: [ExpiryKind; 2] = [ExpiryKind::Certificate, ExpiryKind::Domain];
+
+    /// How many days ahead to alert when the site has no override.
+    pub fn default_days(&self) -> i64 {
+        match self {
+            ExpiryKind::Certificate => 14,
+            ExpiryKind::Domain => 30,
+        }
+    }
+}
+
+impl Display for ExpiryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ExpiryKind::Certificate => "certificate",
+            ExpiryKind::Domain => "domain",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// Looks up when the site's certificate or domain registration expires.
+pub async fn lookup(client: &reqwest::Client, site: &Site, kind: ExpiryKind) -> Option<i64> {
+    let url = Url::parse(&site.url).ok()?;
+    let lookup = async {
+        match kind {
+            ExpiryKind::Certificate => certificate(&url).await,
+            ExpiryKind::Domain => domain(client, &url).await,
+        }
+    };
+    tokio::time::timeout(LOOKUP_TIMEOUT, lookup)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Reads `not_after` from the leaf certificate. The certificate isn't
+/// verified since an expired one is exactly what we want to report.
+async fn certificate(url: &Url) -> Option<i64> {
+    if url.scheme() != "https" {
+        return None;
+    }
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(AnyCertificate))
+        .with_no_client_auth();
+    let stream = TcpStream::connect((host, port)).await.ok()?;
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(ServerName::try_from(host).ok()?, stream)
+        .await
+        .ok()?;
+    let leaf = stream.get_ref().1.peer_certificates()?.first()?;
+    let (_, certificate) = x509_parser::parse_x509_certificate(&leaf.0).ok()?;
+    Some(certificate.validity().not_after.timestamp())
+}
+
+struct AnyCertificate;
+
+impl ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[derive(Deserialize)]
+struct Rdap {
+    #[serde(default)]
+    events: Vec<RdapEvent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RdapEvent {
+    event_action: String,
+    event_date: String,
+}
+
+/// Asks RDAP for the registration's expiration event.
+async fn domain(client: &reqwest::Client, url: &Url) -> Option<i64> {
+    let domain = registered_domain(url)?;
+    let rdap = client
+        .get(format!("{}/{}", RDAP_URL, domain))
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .json::<Rdap>()
+        .await
+        .ok()?;
+    let event = rdap
+        .events
+        .iter()
+        .find(|event| event.event_action == "expiration")?;
+    let expires_at = OffsetDateTime::parse(&event.event_date, &Rfc3339).ok()?;
+    Some(expires_at.unix_timestamp())
+}
+
+/// The last two labels of the host. This is wrong for suffixes like
+/// `co.uk` but those lookups just fail and the site gets no domain alerts.
+fn registered_domain(url: &Url) -> Option<String> {
+    let host = url.domain()?;
+    let labels = host.rsplit('.').take(2).collect::<Vec<_>>();
+    if labels.len() < 2 {
+        return None;
+    }
+    Some(format!("{}.{}", labels[1], labels[0]))
+}
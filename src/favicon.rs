@@ -0,0 +1,97 @@
+use crate::{Database, Site};
+use reqwest::Url;
+use std::time::Duration;
+
+/// Icons are fetched again once they are older than this, sites rarely
+/// change theirs.
+pub const REFRESH_SECS: i64 = 7 * 24 * 60 * 60;
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Anything bigger isn't a favicon worth keeping in the database.
+pub const MAX_BYTES: usize = 100 * 1024;
+
+/// A site's favicon as it was served.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Icon {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Fetches `/favicon.ico` from the site's origin. Responses that aren't
+/// images, are svg or are too big count as no icon.
+pub async fn fetch(client: &reqwest::Client, site: &Site) -> Option<Icon> {
+    let url = Url::parse(&site.url).ok()?.join("/favicon.ico").ok()?;
+    let response = client
+        .get(url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?
+        .to_string();
+    // svg can carry script, the placeholder is the only svg served
+    if !content_type.starts_with("image/")
+        || content_type.starts_with("image/svg")
+        || response
+            .content_length()
+            .map_or(false, |length| length as usize > MAX_BYTES)
+    {
+        return None;
+    }
+    let data = read(response).await?;
+    if data.is_empty() {
+        return None;
+    }
+    Some(Icon { content_type, data })
+}
+
+/// Reads the body a chunk at a time, giving up past `MAX_BYTES` so a body
+/// without a content length can't grow without bound.
+async fn read(mut response: reqwest::Response) -> Option<Vec<u8>> {
+    let mut data = vec![];
+    while let Some(chunk) = response.chunk().await.ok()? {
+        data.extend_from_slice(&chunk);
+        if data.len() > MAX_BYTES {
+            return None;
+        }
+    }
+    Some(data)
+}
+
+/// Fetches and stores the site's icon, remembering a miss so it isn't
+/// tried again until the next refresh.
+pub async fn refresh_site(db: &Database, client: &reqwest::Client, site: &Site) {
+    let icon = fetch(client, site).await;
+    if let Err(err) = db.upsert_site_icon(site.id, icon.as_ref()).await {
+        tracing::warn!("could not store icon for site {}: {}", site.id, err);
+    }
+}
+
+/// Refreshes icons that were never fetched or are stale.
+pub async fn refresh(db: &Database, client: &reqwest::Client) {
+    let before = Database::now() - REFRESH_SECS;
+    let sites = db.sites_due_icon_fetch(before).await.unwrap_or_default();
+    for site in sites {
+        refresh_site(db, client, &site).await;
+    }
+}
+
+/// Stands in for sites without an icon, the first letter of the name on
+/// grey so rows still line up.
+pub fn placeholder(name: &str) -> String {
+    let letter = name
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .find(|c| c.is_ascii_alphanumeric())
+        .unwrap_or('?')
+        .to_ascii_uppercase();
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 16 16"><rect width="16" height="16" rx="3" fill="#9f9f9f"/><text x="8" y="12" fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="10">{letter}</text></svg>"##
+    )
+}
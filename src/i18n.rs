@@ -0,0 +1,121 @@
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// A language the app's strings are translated to, kept per user.
+/// Strings missing from a locale fall back to English.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::De];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+        }
+    }
+
+    /// The language's own name, so it can be found in a list by someone
+    /// who can't read the current one.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::De => "Deutsch",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Locale> {
+        Self::ALL.into_iter().find(|locale| locale.as_str() == name)
+    }
+
+    /// Picks the first language in an `Accept-Language` header that has
+    /// a translation, for people who aren't logged in.
+    pub fn negotiate(accept_language: &str) -> Locale {
+        accept_language
+            .split(',')
+            .filter_map(|range| range.split(';').next())
+            .filter_map(|range| range.trim().split('-').next())
+            .find_map(|language| Locale::parse(&language.to_ascii_lowercase()))
+            .unwrap_or_default()
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Locale::En => include_str!("../locales/en.ftl"),
+            Locale::De => include_str!("../locales/de.ftl"),
+        }
+    }
+
+    fn bundle(&self) -> FluentBundle<FluentResource> {
+        let language = self
+            .as_str()
+            .parse::<LanguageIdentifier>()
+            .expect("locale names are language identifiers");
+        let resource = FluentResource::try_new(self.source().to_string())
+            .unwrap_or_else(|(_, errors)| panic!("{} has errors: {:?}", self.as_str(), errors));
+        let mut bundle = FluentBundle::new_concurrent(vec![language]);
+        // the isolation marks end up as stray characters in plain text
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(resource)
+            .unwrap_or_else(|errors| panic!("{} has errors: {:?}", self.as_str(), errors));
+        bundle
+    }
+}
+
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+fn bundles() -> &'static [FluentBundle<FluentResource>] {
+    BUNDLES.get_or_init(|| Locale::ALL.iter().map(Locale::bundle).collect())
+}
+
+fn format(locale: Locale, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let index = Locale::ALL.iter().position(|other| *other == locale)?;
+    let bundle = &bundles()[index];
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = vec![];
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        tracing::warn!(
+            "could not format {} in {}: {:?}",
+            id,
+            locale.as_str(),
+            errors
+        );
+    }
+    Some(text.into_owned())
+}
+
+fn translate(locale: Locale, id: &str, args: Option<&FluentArgs>) -> String {
+    format(locale, id, args)
+        .or_else(|| format(Locale::En, id, args))
+        .unwrap_or_else(|| {
+            tracing::warn!("no string named {}", id);
+            id.to_string()
+        })
+}
+
+/// The string named `id` in `locale`.
+pub fn t(locale: Locale, id: &str) -> String {
+    translate(locale, id, None)
+}
+
+/// Like [`t`] for strings with variables, `{ $count }` in the string is
+/// filled in from `("count", sites.len().into())`.
+pub fn t_with<'a>(
+    locale: Locale,
+    id: &str,
+    args: impl IntoIterator<Item = (&'a str, FluentValue<'a>)>,
+) -> String {
+    let args = args.into_iter().collect::<FluentArgs>();
+    translate(locale, id, Some(&args))
+}
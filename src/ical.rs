@@ -0,0 +1,49 @@
+use crate::MaintenanceWindow;
+use chrono::{TimeZone, Utc};
+
+/// Unix seconds as an iCalendar UTC date time, like `20230729T120000Z`.
+fn date_time(secs: i64) -> String {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .map(|at| at.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default()
+}
+
+/// Backslashes, semicolons, commas and newlines are escaped in text values.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// A calendar with one event per maintenance window. `host` makes the
+/// event uids unique to this instance so calendars can update them.
+pub fn maintenance_calendar(
+    name: &str,
+    host: &str,
+    windows: &[MaintenanceWindow],
+    now: i64,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//updown//maintenance//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(&format!("{} maintenance", name))),
+    ];
+    for window in windows {
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:maintenance-{}@{}", window.id, host),
+            format!("DTSTAMP:{}", date_time(now)),
+            format!("DTSTART:{}", date_time(window.starts_at)),
+            format!("DTEND:{}", date_time(window.ends_at)),
+            format!("SUMMARY:{}", escape(&window.title)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+    // lines end in CRLF, the last one too
+    lines.iter().map(|line| format!("{}\r\n", line)).collect()
+}
@@ -0,0 +1,233 @@
+use crate::{AppError, Database, Site};
+use serde::{Deserialize, Serialize};
+
+/// Uptime Kuma monitor types that check a url, the rest (ping, port, dns
+/// and so on) have nothing to import.
+pub const KUMA_HTTP_TYPES: [&str; 3] = ["http", "keyword", "json-query"];
+
+/// One site read from the file, before it's validated.
+#[derive(Debug, Default, Deserialize)]
+pub struct Row {
+    pub url: String,
+    pub name: Option<String>,
+    pub interval_secs: Option<i64>,
+    pub failure_threshold: Option<i64>,
+    /// Set for rows that came from an Uptime Kuma backup.
+    #[serde(skip)]
+    pub monitor_type: Option<String>,
+}
+
+impl Row {
+    fn label(&self) -> String {
+        match self.url.is_empty() {
+            true => self.name.clone().unwrap_or_default(),
+            false => self.url.clone(),
+        }
+    }
+
+    fn site(&self, user_id: i64) -> Result<Site, AppError> {
+        if let Some(monitor_type) = &self.monitor_type {
+            if !KUMA_HTTP_TYPES.contains(&monitor_type.as_str()) {
+                return Err(AppError::UrlScheme);
+            }
+        }
+        let mut site = Site::default();
+        site.user_id = user_id;
+        site.url = Site::normalize_url(&self.url)?;
+        site.name = self.name.clone().filter(|name| !name.trim().is_empty());
+        // rounded up to the next interval updown supports
+        site.interval_secs = Site::INTERVALS
+            .into_iter()
+            .find(|interval| *interval >= self.interval_secs.unwrap_or_default())
+            .unwrap_or(Site::INTERVALS[Site::INTERVALS.len() - 1]);
+        site.failure_threshold = self
+            .failure_threshold
+            .unwrap_or(1)
+            .clamp(1, Site::MAX_FAILURE_THRESHOLD);
+        Ok(site)
+    }
+}
+
+#[derive(Deserialize)]
+struct KumaMonitor {
+    #[serde(rename = "type")]
+    monitor_type: String,
+    name: Option<String>,
+    url: Option<String>,
+    interval: Option<i64>,
+    maxretries: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Json {
+    Kuma {
+        #[serde(rename = "monitorList")]
+        monitor_list: Vec<KumaMonitor>,
+    },
+    /// What `GET /api/export` returns.
+    Export {
+        sites: Vec<Row>,
+    },
+    Rows(Vec<Row>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rejected {
+    pub row: String,
+    pub error: AppError,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub imported: Vec<Site>,
+    /// Urls that were already monitored or came up twice in the file.
+    pub duplicates: Vec<String>,
+    pub rejected: Vec<Rejected>,
+    /// Urls left out because the user reached their site limit.
+    pub over_limit: Vec<String>,
+}
+
+impl Report {
+    pub fn summary(&self) -> String {
+        let summary = format!(
+            "Imported {} sites, skipped {} duplicates and {} that couldn't be monitored",
+            self.imported.len(),
+            self.duplicates.len(),
+            self.rejected.len()
+        );
+        match self.over_limit.len() {
+            0 => summary,
+            n => format!("{}, {} more would go past your site limit", summary, n),
+        }
+    }
+}
+
+/// Reads a csv file with a url and an optional name per line, an Uptime
+/// Kuma backup, or a json export, and returns the rows in it.
+pub fn parse(input: &str) -> Result<Vec<Row>, AppError> {
+    let input = input.trim();
+    if !input.starts_with(&['{', '['][..]) {
+        return Ok(parse_csv(input));
+    }
+    let rows = match serde_json::from_str(input).map_err(|_| AppError::ImportFormat)? {
+        Json::Kuma { monitor_list } => monitor_list
+            .into_iter()
+            .map(|monitor| Row {
+                url: monitor.url.unwrap_or_default(),
+                name: monitor.name,
+                interval_secs: monitor.interval,
+                // kuma retries after the first failure
+                failure_threshold: monitor.maxretries.map(|retries| retries + 1),
+                monitor_type: Some(monitor.monitor_type),
+            })
+            .collect(),
+        Json::Export { sites } => sites,
+        Json::Rows(rows) => rows,
+    };
+    Ok(rows)
+}
+
+/// The first line is taken as a header when one of its columns is `url`,
+/// otherwise the columns are url, name, interval_secs and
+/// failure_threshold.
+fn parse_csv(input: &str) -> Vec<Row> {
+    let mut lines = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(fields)
+        .peekable();
+    let mut columns = vec!["url", "name", "interval_secs", "failure_threshold"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    if let Some(header) = lines.peek() {
+        if header
+            .iter()
+            .any(|column| column.eq_ignore_ascii_case("url"))
+        {
+            columns = header.iter().map(|column| column.to_lowercase()).collect();
+            lines.next();
+        }
+    }
+    lines
+        .map(|fields| {
+            let field = |name: &str| {
+                columns
+                    .iter()
+                    .position(|column| column == name)
+                    .and_then(|i| fields.get(i))
+                    .filter(|field| !field.is_empty())
+                    .cloned()
+            };
+            Row {
+                url: field("url").unwrap_or_default(),
+                name: field("name"),
+                interval_secs: field("interval_secs").and_then(|secs| secs.parse().ok()),
+                failure_threshold: field("failure_threshold")
+                    .and_then(|threshold| threshold.parse().ok()),
+                monitor_type: None,
+            }
+        })
+        .collect()
+}
+
+/// Splits a csv line, with `"` quoting fields that hold commas and `""`
+/// for a quote inside them.
+fn fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// Validates the rows, drops the urls the user already monitors or that
+/// come up twice, and adds the rest in one transaction, up to the user's
+/// site limit.
+pub async fn run(db: &Database, user_id: i64, input: &str) -> Result<Report, AppError> {
+    let mut report = Report::default();
+    let mut sites: Vec<Site> = vec![];
+    for row in parse(input)? {
+        match row.site(user_id) {
+            Ok(site) if sites.iter().any(|other| other.url == site.url) => {
+                report.duplicates.push(site.url)
+            }
+            Ok(site) => sites.push(site),
+            Err(error) => report.rejected.push(Rejected {
+                row: row.label(),
+                error,
+            }),
+        }
+    }
+    let (imported, over_limit) = db.insert_sites(&sites).await?;
+    report.imported = imported;
+    report.over_limit = over_limit.into_iter().map(|site| site.url).collect();
+    report.duplicates.extend(
+        sites
+            .into_iter()
+            .filter(|site| !report.over_limit.contains(&site.url))
+            .filter(|site| {
+                !report
+                    .imported
+                    .iter()
+                    .any(|imported| imported.url == site.url)
+            })
+            .map(|site| site.url),
+    );
+    Ok(report)
+}
@@ -0,0 +1,115 @@
+use crate::{
+    checker::Check,
+    notifications::{format_duration, SiteEvent, SiteState},
+    Database, Incident, Site,
+};
+
+/// How far back checks are looked at to decide whether a site is flapping.
+pub const FLAP_WINDOW_SECS: i64 = 15 * 60;
+/// How many changes between up and down within the window count as
+/// flapping.
+pub const FLAP_THRESHOLD: usize = 4;
+
+/// Where a site stands, derived from its open incident.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Up,
+    Down,
+    /// Bouncing between up and down. The incident stays open and alerts are
+    /// held until the site keeps one state for a whole window.
+    Flapping,
+}
+
+impl Phase {
+    pub fn of(incident: Option<&Incident>) -> Self {
+        match incident {
+            None => Phase::Up,
+            Some(incident) if incident.flapping => Phase::Flapping,
+            Some(_) => Phase::Down,
+        }
+    }
+
+    /// The phase after a check, given how many times the site changed
+    /// between up and down within the flap window.
+    pub fn next(self, up: bool, flips: usize) -> Self {
+        match (self, up, flips) {
+            (Phase::Flapping, _, 1..) => Phase::Flapping,
+            (Phase::Up, true, _) => Phase::Up,
+            (Phase::Down, false, _) => Phase::Down,
+            (_, _, flips) if flips >= FLAP_THRESHOLD => Phase::Flapping,
+            (_, true, _) => Phase::Up,
+            (_, false, _) => Phase::Down,
+        }
+    }
+}
+
+/// What happened to a site's incident after a check.
+#[derive(Debug, Default)]
+pub struct Tracked {
+    pub event: Option<SiteEvent>,
+    pub opened: bool,
+    pub resolved: bool,
+}
+
+/// Moves the site's incident along after a check, opening, resolving or
+/// marking it as flapping, and returns the event to notify about if any.
+pub async fn track(db: &Database, site: &Site, check: &Check) -> Result<Tracked, sqlx::Error> {
+    let open = db.open_incident_by_site(site.id).await?;
+    let up = check.response().is_up();
+    let states = db
+        .check_states(site.id, Database::now() - FLAP_WINDOW_SECS)
+        .await?;
+    let flips = states.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let from = Phase::of(open.as_ref());
+    let to = from.next(up, flips);
+    let mut tracked = Tracked::default();
+    // a site stays up until it fails as many times in a row as it allows
+    if (from, to) == (Phase::Up, Phase::Down)
+        && site.failure_threshold > 1
+        && !db.failed_in_a_row(site.id, site.failure_threshold).await?
+    {
+        return Ok(tracked);
+    }
+    let mut event = SiteEvent::new(site, check);
+    match (from, to) {
+        (Phase::Up, Phase::Up) => return Ok(tracked),
+        (Phase::Down, Phase::Down) | (Phase::Flapping, Phase::Flapping) => {
+            if !up {
+                db.update_incident_status_code(site.id, check.status_code)
+                    .await?;
+            }
+            return Ok(tracked);
+        }
+        (Phase::Up, Phase::Down) | (Phase::Up, Phase::Flapping) => {
+            let flapping = to == Phase::Flapping;
+            event.incident = Some(
+                db.open_incident(site.id, check.status_code, flapping)
+                    .await?,
+            );
+            tracked.opened = true;
+        }
+        (Phase::Down, Phase::Flapping) | (Phase::Flapping, Phase::Down) => {
+            let flapping = to == Phase::Flapping;
+            event.incident = db.set_incident_flapping(site.id, flapping).await?;
+        }
+        (Phase::Down, Phase::Up) | (Phase::Flapping, Phase::Up) => {
+            event.incident = db.resolve_incident(site.id).await?;
+            tracked.resolved = true;
+        }
+    }
+    event.detail = match (from, to) {
+        (_, Phase::Flapping) => {
+            event.state = SiteState::Down;
+            Some(format!(
+                "is flapping, it changed between up and down {} times in the last {}",
+                flips,
+                format_duration(FLAP_WINDOW_SECS)
+            ))
+        }
+        (Phase::Flapping, Phase::Up) => Some("stopped flapping and is back up".to_string()),
+        (Phase::Flapping, Phase::Down) => Some("stopped flapping and stays down".to_string()),
+        _ => None,
+    };
+    tracked.event = Some(event);
+    Ok(tracked)
+}
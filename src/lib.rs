@@ -1,47 +1,152 @@
 use anyhow::Result;
+use chrono::{TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use digest::Frequency;
+use expiry::ExpiryKind;
 use models::Response;
 use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "postgres")]
+use sqlx::postgres::PgPoolOptions;
+#[cfg(not(feature = "postgres"))]
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{
-    migrate::MigrateError,
-    sqlite::{
-        SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteQueryResult,
-        SqliteSynchronous,
-    },
-    FromRow, SqlitePool,
+    migrate::{MigrateError, Migrator},
+    Executor, FromRow,
 };
 use std::{
+    borrow::Cow,
     fmt::Display,
+    net::IpAddr,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use timestamp::Timestamp;
+use webhooks::Event;
+
+pub mod backup;
+pub mod badge;
+pub mod checker;
+pub mod demo;
+pub mod digest;
+pub mod expiry;
+pub mod favicon;
+pub mod i18n;
+pub mod ical;
+pub mod import;
+pub mod incident;
+pub mod notifications;
+pub mod rate_limit;
+pub mod rules;
+pub mod subscribers;
+pub mod template;
+pub mod timestamp;
+pub mod token;
+pub mod webhooks;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Serializes as `{"type": "UrlEmpty"}`. Sources stay out of the json but
+/// show up in logs through [`AppError::chain`].
+#[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 pub enum AppError {
-    Migrate,
-    DatabaseInsert,
+    Migrate {
+        #[serde(skip)]
+        source: MigrateError,
+    },
+    DatabaseInsert {
+        #[serde(skip)]
+        source: sqlx::Error,
+    },
     Login,
     JsonParse,
-    DatabaseSelect,
+    DatabaseSelect {
+        #[serde(skip)]
+        source: sqlx::Error,
+    },
     UrlEmpty,
-    Rollback,
+    UrlInvalid,
+    UrlScheme,
+    /// No source when there was no migration to roll back.
+    Rollback {
+        #[serde(skip)]
+        source: Option<sqlx::Error>,
+    },
+    UnsupportedChannel,
+    ChannelConfig,
+    Notify,
+    TokenInvalid,
+    TokenExpired,
+    TokenUsed,
+    QuietHours,
+    Template,
+    SiteSettings,
+    DuplicateSite,
+    TagName,
+    ImportFormat,
+    TooManyAttempts,
+    Email,
+    Passkey,
+    CrossOrigin,
+    Role,
+    Organization,
+    LastOwner,
+    SiteLimit,
+    ApiToken,
+    Scope,
+    QueryParams,
+    RateLimited,
+    BatchSize,
+    Webhook,
+    Domain,
+    DuplicateDomain,
+    StatusIncident,
+    MaintenanceWindow,
+    AccountSettings,
+}
+
+impl AppError {
+    /// The error followed by everything that caused it, e.g.
+    /// `DatabaseInsert: error returned from database: UNIQUE constraint failed`.
+    pub fn chain(&self) -> String {
+        let mut chain = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(err) = source {
+            chain.push_str(&format!(": {}", err));
+            source = err.source();
+        }
+        chain
+    }
 }
 
 impl From<MigrateError> for AppError {
-    fn from(_value: MigrateError) -> Self {
-        AppError::Migrate
+    fn from(source: MigrateError) -> Self {
+        AppError::Migrate { source }
     }
 }
 
 impl Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{:?}", self))
+        match self {
+            AppError::Migrate { .. } => f.write_str("Migrate"),
+            AppError::DatabaseInsert { .. } => f.write_str("DatabaseInsert"),
+            AppError::DatabaseSelect { .. } => f.write_str("DatabaseSelect"),
+            AppError::Rollback { .. } => f.write_str("Rollback"),
+            _ => f.write_fmt(format_args!("{:?}", self)),
+        }
     }
 }
 
 impl std::error::Error for AppError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        match self {
+            AppError::Migrate { source } => Some(source),
+            AppError::DatabaseInsert { source } | AppError::DatabaseSelect { source } => {
+                Some(source)
+            }
+            AppError::Rollback {
+                source: Some(source),
+            } => Some(source),
+            _ => None,
+        }
     }
 
     fn cause(&self) -> Option<&dyn std::error::Error> {
@@ -53,206 +158,4784 @@ impl std::error::Error for AppError {
 pub struct User {
     pub id: i64,
     pub login_code: String,
-    pub created_at: i64,
-    pub updated_at: i64,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    pub digest: Frequency,
+    pub digest_sent_at: Option<i64>,
+    /// IANA name like `Europe/Berlin`, used to place quiet hours.
+    pub timezone: String,
+    /// Minutes after local midnight. Quiet hours may wrap past midnight.
+    pub quiet_start: Option<i64>,
+    pub quiet_end: Option<i64>,
+    /// Where a login link goes when the login code is lost.
+    pub email: Option<String>,
+    /// Seconds, set once the link mailed to `email` was followed. Only
+    /// verified addresses get recovery links.
+    pub email_verified_at: Option<i64>,
+    /// Can open `/admin`, set with `cargo run admin <login code>`.
+    pub is_admin: bool,
+    /// Overrides the instance's site limit for this user, set with
+    /// `cargo run max-sites <login code> <count>`.
+    pub max_sites: Option<i64>,
+    /// What the account goes by in the app, it has none until it's set.
+    pub name: Option<String>,
+    /// How often new sites are checked, one of [`Site::INTERVALS`].
+    pub default_interval_secs: i64,
+    /// The language the app is shown in.
+    pub locale: i18n::Locale,
+}
+
+impl User {
+    pub const MAX_NAME: usize = 64;
+
+    /// Trims the name, an empty one clears it.
+    pub fn normalize_name(name: &str) -> Result<Option<String>, AppError> {
+        let name = name.trim();
+        match name.chars().count() {
+            0 => Ok(None),
+            count if count > Self::MAX_NAME => Err(AppError::AccountSettings),
+            _ => Ok(Some(name.to_string())),
+        }
+    }
+
+    /// Whether `now` falls inside the user's quiet hours.
+    pub fn is_quiet(&self, now: i64) -> bool {
+        let (Some(start), Some(end)) = (self.quiet_start, self.quiet_end) else {
+            return false;
+        };
+        let timezone = self.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+        let Some(local) = Utc.timestamp_opt(now, 0).single() else {
+            return false;
+        };
+        let local = local.with_timezone(&timezone);
+        let minute = (local.hour() * 60 + local.minute()) as i64;
+        match start <= end {
+            true => start <= minute && minute < end,
+            false => minute >= start || minute < end,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
 pub struct Login {
     pub id: i64,
     pub user_id: i64,
-    pub created_at: i64,
+    pub created_at: Timestamp,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// What a member may do in an organization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Role {
+    /// Everything a member can, plus managing members and the organization.
+    Owner,
+    #[default]
+    Member,
+    /// Sees the sites and incidents but can't change anything.
+    Viewer,
+}
+
+impl Role {
+    pub fn can_edit(&self) -> bool {
+        *self != Role::Viewer
+    }
 }
 
+/// A team sharing one set of sites and channels. They belong to the
+/// organization's own account, a user nobody logs in as, so everything
+/// that works per user works per organization too.
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
-pub struct Site {
+pub struct Organization {
     pub id: i64,
+    pub name: String,
+    /// The account holding the organization's sites and channels.
     pub user_id: i64,
-    pub url: String,
-    pub name: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
 }
 
-pub mod models {
-    use serde::{Deserialize, Serialize};
-    use sqlx::FromRow;
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Membership {
+    pub id: i64,
+    pub organization_id: i64,
+    pub user_id: i64,
+    pub role: Role,
+    pub created_at: i64,
+}
 
-    #[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
-    pub struct Response {
-        pub id: i64,
-        pub status_code: i64,
-        pub site_id: i64,
-        pub created_at: i64,
-        pub updated_at: i64,
+/// A pending invite into an organization, handed out as a signed link.
+/// It's deleted once someone joins with it.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Invitation {
+    pub id: i64,
+    pub organization_id: i64,
+    pub role: Role,
+    pub created_by: i64,
+    pub expires_at: i64,
+    pub created_at: i64,
+}
+
+impl Invitation {
+    pub const TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+    /// The claims for the invite link, the token carries the invitation
+    /// id and the organization and role are looked up when it's opened.
+    pub fn claims(&self) -> token::Claims {
+        token::Claims::new(
+            token::Action::Invite,
+            self.created_by,
+            self.id,
+            self.expires_at,
+        )
     }
 }
 
-#[derive(Debug)]
-pub struct Database {
-    connection: SqlitePool,
+/// Someone who asked for email about incidents on a share page. Nothing
+/// is sent until they follow the link in the confirmation email.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Subscriber {
+    pub id: i64,
+    pub share_link_id: i64,
+    pub site_id: i64,
+    pub email: String,
+    pub confirmed_at: Option<i64>,
+    pub created_at: i64,
 }
 
-impl Database {
-    pub async fn new(filename: String) -> Self {
-        Self {
-            connection: Self::pool(&filename).await,
-        }
-    }
+/// A link that shows one site's status to anyone who has it, until it's
+/// revoked by deleting the row.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct ShareLink {
+    pub id: i64,
+    pub site_id: i64,
+    pub user_id: i64,
+    /// Seconds, `None` for a link that works until it's revoked.
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+    /// A hostname like `status.example.com` that serves the share page.
+    pub domain: Option<String>,
+}
 
-    pub async fn migrate(&self) -> Result<(), AppError> {
-        let result = sqlx::migrate!().run(&self.connection).await;
-        match result {
-            Ok(_) => Ok(()),
-            Err(err) => panic!("{}", err),
-        }
+impl ShareLink {
+    /// Lowercases the hostname and checks it looks like one, with at
+    /// least two labels of letters, digits and dashes.
+    pub fn normalize_domain(domain: &str) -> Option<String> {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        let labels = domain.split('.').collect::<Vec<_>>();
+        let valid = domain.len() <= 253
+            && labels.len() >= 2
+            && labels.iter().all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        valid.then_some(domain)
     }
 
-    pub async fn rollback(&self) -> Result<SqliteQueryResult, AppError> {
-        let migrations = sqlx::migrate!()
-            .migrations
-            .iter()
-            .filter(|m| m.migration_type.is_down_migration());
-        if let Some(migration) = migrations.last() {
-            if migration.migration_type.is_down_migration() {
-                let version = migration.version;
-                match sqlx::query(&migration.sql)
-                    .execute(&self.connection)
-                    .await
-                    .map_err(|_| AppError::Rollback)
-                {
-                    Ok(_) => sqlx::query("delete from _sqlx_migrations where version = ?")
-                        .bind(version)
-                        .execute(&self.connection)
-                        .await
-                        .map_err(|_| AppError::Rollback),
-                    Err(_) => Err(AppError::Rollback),
-                }
-            } else {
-                Err(AppError::Rollback)
-            }
-        } else {
-            Err(AppError::Rollback)
-        }
+    pub fn claims(&self) -> token::Claims {
+        token::Claims::new(
+            token::Action::Share,
+            self.user_id,
+            self.id,
+            self.expires_at.unwrap_or(i64::MAX),
+        )
     }
+}
 
-    fn connection_options(filename: &str) -> SqliteConnectOptions {
-        let options: SqliteConnectOptions = filename.parse().unwrap();
-        options
-            .create_if_missing(true)
-            .journal_mode(SqliteJournalMode::Wal)
-            .synchronous(SqliteSynchronous::Normal)
-            .busy_timeout(Duration::from_secs(30))
+/// Totals for the admin dashboard.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct InstanceStats {
+    /// People, not counting organization accounts.
+    pub users: i64,
+    pub sites: i64,
+    pub disabled_sites: i64,
+    pub checks_last_hour: i64,
+    /// Sites with an open incident.
+    pub failing_sites: i64,
+    /// Alerts held back by quiet hours.
+    pub queue_depth: i64,
+}
+
+impl InstanceStats {
+    pub fn checks_per_minute(&self) -> f64 {
+        self.checks_last_hour as f64 / 60.0
     }
+}
 
-    async fn pool(filename: &str) -> SqlitePool {
-        SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect_with(Self::connection_options(filename))
-            .await
-            .unwrap()
+/// A passkey that can log in instead of the login code.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Passkey {
+    pub id: i64,
+    pub user_id: i64,
+    /// Base64url, as the browser sends it back when logging in.
+    pub credential_id: String,
+    /// The webauthn-rs credential as json. Its sign counter changes with
+    /// every login.
+    #[serde(skip)]
+    pub passkey: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+}
+
+/// What an api token can do, reading covers GET requests.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Write,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 2] = [Scope::Read, Scope::Write];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+        }
     }
+}
 
-    fn now() -> f64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("unable to get epoch in insert_user")
-            .as_secs_f64()
+/// A bearer token for `/api/v1`. Only its hash is stored, the token is
+/// shown once when it's made.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct ApiToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    /// Space separated, like `read write`.
+    pub scopes: String,
+    pub last_used_at: Option<i64>,
+    pub created_at: i64,
+    /// The person who made it. An organization's tokens stop working once
+    /// they leave it, tokens from before this was kept have none.
+    pub created_by: Option<i64>,
+}
+
+impl ApiToken {
+    pub const PREFIX: &'static str = "updown_";
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes
+            .split_whitespace()
+            .any(|name| name == scope.as_str())
     }
+}
 
-    pub async fn insert_user(&self) -> Result<User, AppError> {
-        let login_code = nanoid!();
-        let now = Self::now();
-        sqlx::query_as!(
-            User,
-            "insert into users (login_code, created_at, updated_at) values (?, ?, ?) returning *",
-            login_code,
-            now,
-            now
-        )
-        .fetch_one(&self.connection)
-        .await
-        .map_err(|_| AppError::DatabaseInsert)
+/// An account level subscription to [`Event`]s, posted to `url` and
+/// signed with `secret`.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Webhook {
+    pub id: i64,
+    pub user_id: i64,
+    pub url: String,
+    /// Space separated, like `site.created check.failed`.
+    pub events: String,
+    #[serde(skip)]
+    pub secret: String,
+    pub created_at: i64,
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: Event) -> bool {
+        self.events
+            .split_whitespace()
+            .any(|name| name == event.as_str())
     }
+}
 
-    pub async fn user_by_id(&self, id: i64) -> Result<User, sqlx::Error> {
-        sqlx::query_as!(User, "select * from users where id = ?", id)
-            .fetch_one(&self.connection)
-            .await
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    /// Missing when the request never got a response.
+    pub status_code: Option<i64>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: i64,
+}
+
+/// Failed logins from one ip and for one login code within
+/// [`LoginFailures::WINDOW_SECS`].
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct LoginFailures {
+    pub ip_failures: i64,
+    pub code_failures: i64,
+    pub last_failed_at: Option<i64>,
+}
+
+impl LoginFailures {
+    pub const WINDOW_SECS: i64 = 24 * 60 * 60;
+    /// Failures allowed before the lockout kicks in.
+    pub const FREE_ATTEMPTS: i64 = 5;
+    /// The first lockout, doubled with every failure after that.
+    pub const BASE_LOCKOUT_SECS: i64 = 2;
+    pub const MAX_LOCKOUT_SECS: i64 = 60 * 60;
+
+    /// Seconds until the next attempt is let through, `None` when it can
+    /// go ahead now.
+    pub fn retry_after(&self, now: i64) -> Option<i64> {
+        let failures = self.ip_failures.max(self.code_failures);
+        if failures < Self::FREE_ATTEMPTS {
+            return None;
+        }
+        let exponent = (failures - Self::FREE_ATTEMPTS).min(20) as u32;
+        let lockout = (Self::BASE_LOCKOUT_SECS * 2_i64.pow(exponent)).min(Self::MAX_LOCKOUT_SECS);
+        let retry_after = self.last_failed_at? + lockout - now;
+        (retry_after > 0).then_some(retry_after)
     }
+}
 
-    pub async fn user_by_login_code(&self, login_code: String) -> Result<User, sqlx::Error> {
-        sqlx::query_as!(
-            User,
-            "select * from users where login_code = ? limit 1",
-            login_code
-        )
-        .fetch_one(&self.connection)
-        .await
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Site {
+    pub id: i64,
+    pub user_id: i64,
+    pub url: String,
+    pub name: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    /// Alerts for critical sites go out even during quiet hours.
+    pub critical: bool,
+    /// Checks until then count as maintenance and don't open incidents.
+    pub maintenance_until: Option<i64>,
+    /// How often the site is checked, one of [`Site::INTERVALS`].
+    pub interval_secs: i64,
+    /// How many failed checks in a row it takes to open an incident.
+    pub failure_threshold: i64,
+    /// Set by an admin to stop checking an abusive site.
+    pub disabled_at: Option<i64>,
+    /// Set by the owner to stop checking the site for a while.
+    pub paused_at: Option<i64>,
+}
+
+impl Site {
+    /// The watcher wakes up every five minutes so intervals are multiples
+    /// of that.
+    pub const INTERVALS: [i64; 5] = [300, 600, 900, 1800, 3600];
+    pub const MAX_FAILURE_THRESHOLD: i64 = 10;
+
+    /// The name if the site has one, the url otherwise.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.url)
     }
 
-    pub async fn insert_login(&self, new_login: Login) -> Result<Login, sqlx::Error> {
-        let now = Self::now();
-        sqlx::query_as!(
-            Login,
-            "insert into logins (user_id, created_at) values (?, ?) returning *",
-            new_login.user_id,
-            now
-        )
-        .fetch_one(&self.connection)
-        .await
+    pub fn in_maintenance(&self, now: i64) -> bool {
+        self.maintenance_until.map_or(false, |until| until > now)
     }
 
-    pub async fn insert_site(&self, site: Site) -> Result<Site, sqlx::Error> {
-        let now = Self::now();
-        sqlx::query_as!(
-            Site,
-            "insert into sites (url, user_id, created_at, updated_at) values (?, ?, ?, ?) returning *",
-            site.url,
-            site.user_id,
-            now,
-            now,
-        )
-        .fetch_one(&self.connection)
-        .await
+    /// Cleans up a url typed in by a user: adds `https://` when there's no
+    /// scheme, only allows http and https, wants a host with a dot in it
+    /// (or localhost or an ip) and drops trailing slashes so
+    /// `example.com/` and `https://example.com` end up the same.
+    pub fn normalize_url(input: &str) -> Result<String, AppError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(AppError::UrlEmpty);
+        }
+        let input = match input.contains("://") {
+            true => input.to_string(),
+            false => format!("https://{}", input),
+        };
+        let url = reqwest::Url::parse(&input).map_err(|_| AppError::UrlInvalid)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::UrlScheme);
+        }
+        let host = url.host_str().ok_or(AppError::UrlInvalid)?;
+        let ip = host.trim_matches(&['[', ']'][..]).parse::<IpAddr>().is_ok();
+        if !(ip || host.contains('.') || host == "localhost") {
+            return Err(AppError::UrlInvalid);
+        }
+        Ok(url.as_str().trim_end_matches('/').to_string())
     }
 
-    pub async fn sites_by_user_id(&self, user_id: i64) -> Result<Vec<Site>, sqlx::Error> {
-        sqlx::query_as!(Site, "select * from sites where user_id = ?", user_id,)
-            .fetch_all(&self.connection)
-            .await
+    pub fn validate(&self) -> Result<(), AppError> {
+        Self::normalize_url(&self.url)?;
+        if !Self::INTERVALS.contains(&self.interval_secs)
+            || !(1..=Self::MAX_FAILURE_THRESHOLD).contains(&self.failure_threshold)
+        {
+            return Err(AppError::SiteSettings);
+        }
+        Ok(())
     }
+}
 
-    pub async fn sites(&self) -> Result<Vec<Site>, sqlx::Error> {
-        sqlx::query_as!(Site, "select * from sites")
-            .fetch_all(&self.connection)
-            .await
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct NotificationChannel {
+    pub id: i64,
+    pub user_id: i64,
+    pub kind: String,
+    pub config: String,
+    pub verified: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Minimum seconds between two notifications about the same site.
+    pub cooldown_secs: i64,
+    /// Seconds between "still down" reminders while an incident is open,
+    /// 0 turns reminders off.
+    pub renotify_secs: i64,
+    /// Replaces the default alert text, see `template::VARIABLES`.
+    pub template: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct SiteTransfer {
+    pub id: i64,
+    pub site_id: i64,
+    pub from_user_id: i64,
+    pub to_user_id: Option<i64>,
+    pub token: String,
+    pub accepted_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Incident {
+    pub id: i64,
+    pub site_id: i64,
+    pub status_code: i64,
+    pub started_at: i64,
+    pub resolved_at: Option<i64>,
+    /// The status code of the most recent failing check.
+    pub last_status_code: Option<i64>,
+    pub acknowledged_at: Option<i64>,
+    pub acknowledged_by: Option<i64>,
+    /// Set while the site bounces between up and down.
+    pub flapping: bool,
+}
+
+impl Incident {
+    pub fn duration(&self) -> Option<i64> {
+        Some(self.resolved_at? - self.started_at)
     }
+}
 
-    pub async fn upsert_response(&self, response: Response) -> Result<Response, sqlx::Error> {
-        let now = Self::now();
-        sqlx::query_as!(Response, r#"insert into responses (status_code, site_id, created_at, updated_at) values (?, ?, ?, ?) on conflict (status_code, site_id) do update set updated_at = ? returning *"#, response.status_code, response.site_id, now, now, now).fetch_one(&self.connection).await
+/// An incident as the incidents view lists it, with what made the check
+/// that opened it fail.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct PastIncident {
+    pub id: i64,
+    pub site_id: i64,
+    pub status_code: i64,
+    pub started_at: i64,
+    pub resolved_at: Option<i64>,
+    pub last_status_code: Option<i64>,
+    pub flapping: bool,
+    /// Gone once the opening check is rolled up.
+    pub failure: Option<checker::FailureKind>,
+}
+
+impl PastIncident {
+    pub fn duration(&self) -> Option<i64> {
+        Some(self.resolved_at? - self.started_at)
     }
+}
 
-    pub async fn latest_response_by_site(&self, site_id: i64) -> Result<Response, sqlx::Error> {
-        sqlx::query_as!(
-            Response,
-            "select * from responses where site_id = ? order by updated_at desc limit 1",
-            site_id
-        )
-        .fetch_one(&self.connection)
-        .await
+/// Planned work on a site. Once it starts the watcher puts the site in
+/// maintenance until it ends, like setting `maintenance_until` by hand.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct MaintenanceWindow {
+    pub id: i64,
+    pub site_id: i64,
+    pub title: String,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    pub created_at: i64,
+}
+
+impl MaintenanceWindow {
+    pub const MAX_TITLE_LEN: usize = 200;
+}
+
+/// Where a published incident is at, in the order they usually go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum IncidentStatus {
+    #[default]
+    Investigating,
+    Identified,
+    Monitoring,
+    Resolved,
+}
+
+impl IncidentStatus {
+    pub const ALL: [IncidentStatus; 4] = [
+        IncidentStatus::Investigating,
+        IncidentStatus::Identified,
+        IncidentStatus::Monitoring,
+        IncidentStatus::Resolved,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            IncidentStatus::Investigating => "Investigating",
+            IncidentStatus::Identified => "Identified",
+            IncidentStatus::Monitoring => "Monitoring",
+            IncidentStatus::Resolved => "Resolved",
+        }
     }
+}
 
-    pub fn new_login(user_id: i64) -> Login {
-        let mut login = Login::default();
-        login.user_id = user_id;
-        return login;
+/// An incident written up by hand for the share page, apart from the ones
+/// checks open and resolve.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct StatusIncident {
+    pub id: i64,
+    pub site_id: i64,
+    pub title: String,
+    /// The status of the latest update.
+    pub status: IncidentStatus,
+    pub created_at: i64,
+    pub resolved_at: Option<i64>,
+}
+
+impl StatusIncident {
+    pub const MAX_TITLE_LEN: usize = 200;
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct IncidentUpdate {
+    pub id: i64,
+    pub status_incident_id: i64,
+    pub site_id: i64,
+    pub status: IncidentStatus,
+    pub message: String,
+    pub created_at: i64,
+}
+
+impl IncidentUpdate {
+    pub const MAX_MESSAGE_LEN: usize = 5000;
+}
+
+/// A published incident with its updates, newest first.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct IncidentTimeline {
+    #[serde(flatten)]
+    pub incident: StatusIncident,
+    pub updates: Vec<IncidentUpdate>,
+}
+
+/// Alert when the site's latency percentile over the window goes above the
+/// threshold, e.g. p95 over the last 15 minutes above 2000 ms.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct LatencyRule {
+    pub id: i64,
+    pub user_id: i64,
+    pub site_id: i64,
+    pub percentile: i64,
+    pub window_secs: i64,
+    pub threshold_ms: i64,
+    pub triggered_at: Option<i64>,
+    pub created_at: i64,
+}
+
+/// When a site's certificate or domain expires, refreshed once a day.
+/// `days` is the site's override of the kind's default alert threshold.
+#[derive(Serialize, Deserialize, Clone, PartialEq, FromRow, Debug)]
+pub struct Expiry {
+    pub site_id: i64,
+    pub kind: ExpiryKind,
+    pub expires_at: Option<i64>,
+    pub checked_at: i64,
+    pub notified_at: Option<i64>,
+    pub days: Option<i64>,
+}
+
+impl Expiry {
+    pub fn days(&self) -> i64 {
+        self.days.unwrap_or(self.kind.default_days())
     }
+}
 
-    pub async fn login_count(&self, user_id: i64) -> Result<i32, sqlx::Error> {
-        sqlx::query_scalar!("select count(id) from logins where user_id = ?", user_id)
-            .fetch_one(&self.connection)
-            .await
+/// A site's favicon, `data` is null when the last fetch found none.
+#[derive(Clone, PartialEq, FromRow, Debug)]
+pub struct SiteIcon {
+    pub site_id: i64,
+    pub content_type: Option<String>,
+    pub data: Option<Vec<u8>>,
+    pub fetched_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, FromRow, Debug)]
+pub struct ExpiryRule {
+    pub id: i64,
+    pub user_id: i64,
+    pub site_id: i64,
+    pub kind: ExpiryKind,
+    pub days: i64,
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Tag {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub created_at: i64,
+}
+
+impl Tag {
+    pub const MAX_NAME_LEN: usize = 32;
+
+    /// Tags are trimmed and lowercased so `Prod` and `prod ` are the same
+    /// tag.
+    pub fn normalize_name(name: &str) -> Result<String, AppError> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() || name.chars().count() > Self::MAX_NAME_LEN {
+            return Err(AppError::TagName);
+        }
+        Ok(name)
+    }
+}
+
+/// A notification held back by quiet hours, stored as json.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct QueuedNotification {
+    pub id: i64,
+    pub channel_id: i64,
+    pub user_id: i64,
+    pub notification: String,
+    pub created_at: i64,
+}
+
+/// Check counts for one site over a window. Maintenance checks are left
+/// out of the percentage.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct Uptime {
+    pub window_secs: i64,
+    pub up: i64,
+    pub down: i64,
+    pub maintenance: i64,
+}
+
+impl Uptime {
+    pub fn percentage(&self) -> Option<f64> {
+        match self.up + self.down {
+            0 => None,
+            total => Some(100.0 * self.up as f64 / total as f64),
+        }
+    }
+}
+
+/// How finely checks are grouped into [`CheckBucket`]s.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Resolution::Hour => "hour",
+            Resolution::Day => "day",
+        }
+    }
+
+    pub fn secs(&self) -> i64 {
+        match self {
+            Resolution::Hour => 3600,
+            Resolution::Day => 86_400,
+        }
+    }
+
+    /// The rollup table and its time column.
+    fn rollup(&self) -> (&'static str, &'static str) {
+        match self {
+            Resolution::Hour => ("checks_hourly", "hour"),
+            Resolution::Day => ("checks_daily", "day"),
+        }
+    }
+}
+
+/// Checks of one hour or day, from raw checks and rollups alike.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct CheckBucket {
+    /// Start of the hour or day.
+    pub at: i64,
+    pub checks: i64,
+    pub up: i64,
+    pub maintenance: i64,
+    pub latency_avg_ms: i64,
+    pub latency_min_ms: i64,
+    pub latency_max_ms: i64,
+}
+
+/// One change in a batch, sites are validated before they get here.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SiteOp {
+    Create(Site),
+    /// The whole site after the changes, found by its id.
+    Update(Site),
+    Delete(i64),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteOpStatus {
+    Created,
+    /// The url was already monitored, nothing changed.
+    Exists,
+    Updated,
+    Deleted,
+    /// The site to delete was already gone.
+    Missing,
+}
+
+impl SiteOpStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SiteOpStatus::Created => "created",
+            SiteOpStatus::Exists => "exists",
+            SiteOpStatus::Updated => "updated",
+            SiteOpStatus::Deleted => "deleted",
+            SiteOpStatus::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SiteOpResult {
+    pub status: SiteOpStatus,
+    pub site: Option<Site>,
+}
+
+/// Which operation of a batch failed, and why.
+#[derive(Debug)]
+pub struct BatchError {
+    pub index: usize,
+    pub error: AppError,
+}
+
+/// A site's numbers over a digest period.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct SiteSummary {
+    pub site_id: i64,
+    pub url: String,
+    pub name: Option<String>,
+    pub checks: i64,
+    pub failures: i64,
+    pub latency_ms: Option<i64>,
+}
+
+impl SiteSummary {
+    pub fn uptime(&self) -> Option<f64> {
+        match self.checks {
+            0 => None,
+            checks => Some(100.0 * (checks - self.failures) as f64 / checks as f64),
+        }
+    }
+}
+
+/// What the watcher did in one tick.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+pub struct WatchCycle {
+    pub id: i64,
+    pub sites_checked: i64,
+    pub failures: i64,
+    pub new_incidents: i64,
+    pub duration_ms: i64,
+    /// How late the tick started compared to when it was scheduled.
+    pub lag_ms: i64,
+    pub created_at: i64,
+}
+
+/// One page of rows, newest first. Pass `next_cursor` back to get the
+/// page after it, it's `None` on the last page.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<i64>,
+}
+
+impl<T> Page<T> {
+    pub const DEFAULT_LIMIT: i64 = 50;
+    pub const MAX_LIMIT: i64 = 200;
+
+    /// Takes up to `limit + 1` rows and uses the extra one to tell whether
+    /// there is another page.
+    fn new(mut items: Vec<T>, limit: i64, id: impl Fn(&T) -> i64) -> Self {
+        let more = items.len() as i64 > limit;
+        items.truncate(limit as usize);
+        let next_cursor = match more {
+            true => items.last().map(id),
+            false => None,
+        };
+        Self { items, next_cursor }
+    }
+
+    /// Clamps a requested page size between 1 and [`Page::MAX_LIMIT`].
+    pub fn limit(limit: Option<i64>) -> i64 {
+        limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .clamp(1, Self::MAX_LIMIT)
+    }
+}
+
+/// Everything stored about a user, for taking their data elsewhere.
+/// Checks that were already rolled up aren't in it.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct Export {
+    pub user: User,
+    pub logins: Vec<Login>,
+    pub sites: Vec<Site>,
+    pub checks: Vec<models::Check>,
+    pub incidents: Vec<Incident>,
+    pub channels: Vec<NotificationChannel>,
+}
+
+/// Everything the liveview dashboard needs for one user, loaded at once.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct Dashboard {
+    pub user: User,
+    pub login_count: i32,
+    pub sites: Vec<DashboardSite>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct DashboardSite {
+    pub site: Site,
+    pub latest_response: Option<Response>,
+    /// Percentage of the last 30 days the site wasn't in an incident.
+    pub uptime: Option<f64>,
+    pub open_incident: Option<Incident>,
+    /// Tag names, sorted.
+    pub tags: Vec<String>,
+    /// How long the latest check took, only known for checks that came in
+    /// while the dashboard was open.
+    pub latency_ms: Option<i64>,
+}
+
+/// The orders the monitors view can list sites in.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteSort {
+    /// Newest first.
+    #[default]
+    Recent,
+    /// Sites in an incident first.
+    Status,
+    /// Slowest latest check first.
+    Latency,
+}
+
+impl SiteSort {
+    pub const ALL: [SiteSort; 3] = [SiteSort::Recent, SiteSort::Status, SiteSort::Latency];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SiteSort::Recent => "Recently added",
+            SiteSort::Status => "Status",
+            SiteSort::Latency => "Latency",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|sort| sort.label().eq_ignore_ascii_case(value))
+    }
+
+    fn order_by(&self) -> &'static str {
+        match self {
+            SiteSort::Recent => "sites.created_at desc, sites.id desc",
+            SiteSort::Status => {
+                "case when open_incident.id is null then 1 else 0 end, sites.created_at desc, sites.id desc"
+            }
+            SiteSort::Latency => {
+                "coalesce((select checks.latency_ms from checks where checks.site_id = sites.id order by checks.created_at desc limit 1), -1) desc, sites.id desc"
+            }
+        }
+    }
+}
+
+/// What the monitors view searches for, the default matches every site.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct SiteQuery {
+    /// Part of the url or name, any case.
+    pub search: String,
+    pub sort: SiteSort,
+    /// Only sites in an incident.
+    pub down_only: bool,
+    pub tag: Option<String>,
+}
+
+impl SiteQuery {
+    /// The search as a `like` pattern, escaping the wildcards in it.
+    fn pattern(&self) -> String {
+        let search = self
+            .search
+            .trim()
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        format!("%{}%", search)
+    }
+}
+
+#[derive(FromRow)]
+struct DashboardRow {
+    user_id: i64,
+    login_code: String,
+    user_created_at: Timestamp,
+    user_updated_at: Timestamp,
+    user_digest: Frequency,
+    user_digest_sent_at: Option<i64>,
+    user_timezone: String,
+    user_quiet_start: Option<i64>,
+    user_quiet_end: Option<i64>,
+    user_email: Option<String>,
+    user_email_verified_at: Option<i64>,
+    user_is_admin: bool,
+    user_max_sites: Option<i64>,
+    user_name: Option<String>,
+    user_default_interval_secs: i64,
+    user_locale: i18n::Locale,
+    login_count: i64,
+    site_id: Option<i64>,
+    site_url: Option<String>,
+    site_name: Option<String>,
+    site_created_at: Option<Timestamp>,
+    site_updated_at: Option<Timestamp>,
+    site_critical: Option<bool>,
+    site_maintenance_until: Option<i64>,
+    site_interval_secs: Option<i64>,
+    site_failure_threshold: Option<i64>,
+    site_disabled_at: Option<i64>,
+    site_paused_at: Option<i64>,
+    response_id: Option<i64>,
+    status_code: Option<i64>,
+    response_created_at: Option<Timestamp>,
+    response_updated_at: Option<Timestamp>,
+    downtime: Option<i64>,
+    incident_id: Option<i64>,
+    incident_status_code: Option<i64>,
+    incident_last_status_code: Option<i64>,
+    incident_started_at: Option<i64>,
+    incident_acknowledged_at: Option<i64>,
+    incident_acknowledged_by: Option<i64>,
+    incident_flapping: Option<bool>,
+}
+
+pub mod models {
+    use crate::{checker::FailureKind, timestamp::Timestamp};
+    use serde::{Deserialize, Serialize};
+    use sqlx::FromRow;
+
+    #[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+    pub struct Response {
+        pub id: i64,
+        pub status_code: i64,
+        pub site_id: i64,
+        pub created_at: Timestamp,
+        pub updated_at: Timestamp,
+    }
+
+    /// One row per check the watcher runs, unlike `Response` which only
+    /// keeps the latest time each status code was seen.
+    #[derive(Serialize, Deserialize, Default, Clone, PartialEq, FromRow, Debug)]
+    pub struct Check {
+        pub id: i64,
+        pub site_id: i64,
+        pub status_code: i64,
+        pub latency_ms: i64,
+        pub failure: Option<FailureKind>,
+        pub ip: Option<String>,
+        pub created_at: i64,
+        pub maintenance: bool,
+    }
+
+    impl Response {
+        pub fn is_up(&self) -> bool {
+            self.status_code >= 200 && self.status_code < 300
+        }
+    }
+}
+
+/// Pool size and sqlite pragmas. The defaults suit a small instance, the
+/// cache and mmap sizes are left to sqlite unless set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseOptions {
+    /// Size of the read pool, writes always use one connection.
+    pub max_connections: u32,
+    pub busy_timeout: Duration,
+    /// Page cache per connection in KiB.
+    pub cache_size_kib: Option<i64>,
+    /// Bytes of the database file to memory map.
+    pub mmap_size: Option<i64>,
+    /// Keeps temp tables and indices in memory instead of on disk.
+    pub temp_store_memory: bool,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            busy_timeout: Duration::from_secs(30),
+            cache_size_kib: None,
+            mmap_size: None,
+            temp_store_memory: false,
+        }
+    }
+}
+
+/// The database behind [`Database`], sqlite unless built with the
+/// `postgres` feature.
+#[cfg(not(feature = "postgres"))]
+pub type Backend = sqlx::Sqlite;
+#[cfg(feature = "postgres")]
+pub type Backend = sqlx::Postgres;
+
+pub type Pool = sqlx::Pool<Backend>;
+pub type QueryResult = <Backend as sqlx::Database>::QueryResult;
+
+/// Queries are written once with sqlite's `?` and `?1` placeholders, which
+/// become postgres' `$1` when built with the `postgres` feature.
+#[cfg(not(feature = "postgres"))]
+fn sql(query: &str) -> Cow<'_, str> {
+    Cow::Borrowed(query)
+}
+
+#[cfg(feature = "postgres")]
+fn sql(query: &str) -> Cow<'_, str> {
+    let mut sql = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    let mut next = 0;
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                quoted = !quoted;
+                sql.push(c);
+            }
+            '?' if !quoted => {
+                sql.push('$');
+                match chars.peek().map_or(false, char::is_ascii_digit) {
+                    true => {
+                        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                            sql.push(digit);
+                        }
+                    }
+                    false => {
+                        next += 1;
+                        sql.push_str(&next.to_string());
+                    }
+                }
+            }
+            _ => sql.push(c),
+        }
+    }
+    Cow::Owned(sql)
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod postgres_tests {
+    use super::sql;
+
+    #[test]
+    fn numbers_plain_placeholders_in_order() {
+        assert_eq!(
+            sql("select * from sites where user_id = ? and id = ?"),
+            "select * from sites where user_id = $1 and id = $2"
+        );
+    }
+
+    #[test]
+    fn keeps_numbered_placeholders() {
+        assert_eq!(
+            sql("update users set name = ?2 where id = ?1 and ?2 is not null"),
+            "update users set name = $2 where id = $1 and $2 is not null"
+        );
+    }
+
+    #[test]
+    fn leaves_question_marks_in_strings_alone() {
+        assert_eq!(
+            sql("select * from sites where name = 'why?' and id = ?"),
+            "select * from sites where name = 'why?' and id = $1"
+        );
+    }
+}
+
+/// Reads go through a pool of connections while writes are funneled
+/// through a single one, which under WAL keeps the watcher's writes from
+/// running into `SQLITE_BUSY`. Postgres takes concurrent writes so both
+/// pools get `max_connections` there.
+#[derive(Debug)]
+pub struct Database {
+    reader: Pool,
+    writer: Pool,
+    /// Most sites a user can monitor unless `users.max_sites` says
+    /// otherwise, no limit when unset.
+    max_sites: Option<i64>,
+}
+
+impl Database {
+    pub async fn new(filename: String) -> Self {
+        Self::new_with_options(filename, DatabaseOptions::default()).await
+    }
+
+    pub async fn new_with_options(filename: String, options: DatabaseOptions) -> Self {
+        let writers = match cfg!(feature = "postgres") {
+            true => options.max_connections,
+            false => 1,
+        };
+        // the writer goes first so it can create the database file
+        let writer = Self::pool(&filename, &options, writers).await;
+        let reader = Self::pool(&filename, &options, options.max_connections).await;
+        Self {
+            reader,
+            writer,
+            max_sites: None,
+        }
+    }
+
+    pub fn max_sites(mut self, max_sites: Option<i64>) -> Self {
+        self.max_sites = max_sites;
+        self
+    }
+
+    /// Postgres has its own migrations, squashed from the sqlite ones.
+    fn migrator() -> Migrator {
+        #[cfg(not(feature = "postgres"))]
+        return sqlx::migrate!();
+        #[cfg(feature = "postgres")]
+        return sqlx::migrate!("./migrations-postgres");
+    }
+
+    pub async fn migrate(&self) -> Result<(), AppError> {
+        Self::migrator().run(&self.writer).await?;
+        Ok(())
+    }
+
+    pub async fn rollback(&self) -> Result<QueryResult, AppError> {
+        let migrator = Self::migrator();
+        let migrations = migrator
+            .migrations
+            .iter()
+            .filter(|m| m.migration_type.is_down_migration());
+        if let Some(migration) = migrations.last() {
+            if migration.migration_type.is_down_migration() {
+                let version = migration.version;
+                let rollback = |source| AppError::Rollback {
+                    source: Some(source),
+                };
+                // unprepared so postgres takes several statements at once
+                self.writer
+                    .execute(&*migration.sql)
+                    .await
+                    .map_err(rollback)?;
+                sqlx::query(&sql("delete from _sqlx_migrations where version = ?"))
+                    .bind(version)
+                    .execute(&self.writer)
+                    .await
+                    .map_err(rollback)
+            } else {
+                Err(AppError::Rollback { source: None })
+            }
+        } else {
+            Err(AppError::Rollback { source: None })
+        }
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    fn connection_options(filename: &str, options: &DatabaseOptions) -> SqliteConnectOptions {
+        let mut connect_options: SqliteConnectOptions = filename.parse().unwrap();
+        connect_options = connect_options
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(options.busy_timeout);
+        if let Some(kib) = options.cache_size_kib {
+            // negative sizes are in KiB rather than pages
+            connect_options = connect_options.pragma("cache_size", format!("-{}", kib));
+        }
+        if let Some(bytes) = options.mmap_size {
+            connect_options = connect_options.pragma("mmap_size", bytes.to_string());
+        }
+        if options.temp_store_memory {
+            connect_options = connect_options.pragma("temp_store", "memory");
+        }
+        connect_options
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    async fn pool(filename: &str, options: &DatabaseOptions, max_connections: u32) -> Pool {
+        SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(Self::connection_options(filename, options))
+            .await
+            .unwrap()
+    }
+
+    /// Only the pool size and busy timeout apply to postgres, the timeout
+    /// being how long to wait for a free connection.
+    #[cfg(feature = "postgres")]
+    async fn pool(url: &str, options: &DatabaseOptions, max_connections: u32) -> Pool {
+        PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(options.busy_timeout)
+            .connect(url)
+            .await
+            .unwrap()
+    }
+
+    /// Writes a copy of the live database to `path` with `vacuum into`,
+    /// which reads in one transaction so the watcher can keep writing
+    /// meanwhile. Fails when `path` already exists.
+    #[cfg(not(feature = "postgres"))]
+    pub async fn backup(&self, path: &str) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query("vacuum into ?")
+            .bind(path)
+            .execute(&self.reader)
+            .await
+    }
+
+    #[cfg(feature = "postgres")]
+    pub async fn backup(&self, _path: &str) -> Result<QueryResult, sqlx::Error> {
+        Err(sqlx::Error::Configuration(
+            "postgres databases are backed up with pg_dump".into(),
+        ))
+    }
+
+    /// How often the watcher wakes up to check the sites that are due.
+    pub const WATCH_INTERVAL_SECS: i64 = 300;
+    /// Deliveries kept per webhook.
+    pub const WEBHOOK_DELIVERIES: i64 = 100;
+    /// Most incidents the incidents view lists at once.
+    pub const PAST_INCIDENTS: i64 = 200;
+
+    /// Seconds since the epoch, used by the check, incident and alerting
+    /// tables. Users, logins, sites and responses use [`Timestamp`] instead.
+    pub fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("unable to get epoch in insert_user")
+            .as_secs() as i64
+    }
+
+    pub async fn insert_user(&self) -> Result<User, AppError> {
+        let login_code = nanoid!();
+        let now = Timestamp::now();
+        sqlx::query_as::<_, User>(&sql(
+            "insert into users (login_code, created_at, updated_at) values (?, ?, ?) returning *",
+        ))
+        .bind(login_code)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+        .map_err(|source| AppError::DatabaseInsert { source })
+    }
+
+    /// Creates the user, their first login and their first site together,
+    /// so a failure part way doesn't leave a user without a site. The site
+    /// counts against the limit like any other, past it nothing is created.
+    pub async fn signup(&self, url: String, new_login: Login) -> Result<(User, Site), AppError> {
+        let now = Timestamp::now();
+        let signup: Result<Option<(User, Site)>, sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            let user = sqlx::query_as::<_, User>(
+                &sql("insert into users (login_code, created_at, updated_at) values (?, ?, ?) returning *"),
+            )
+            .bind(nanoid!())
+            .bind(now)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?;
+            sqlx::query(
+                &sql("insert into logins (user_id, ip_address, user_agent, created_at) values (?, ?, ?, ?)"),
+            )
+            .bind(user.id)
+            .bind(new_login.ip_address)
+            .bind(new_login.user_agent)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            let mut site = Site::default();
+            site.url = url;
+            site.user_id = user.id;
+            let Some(site) = Self::insert_site_in(&mut tx, &site, self.max_sites).await? else {
+                return Ok(None);
+            };
+            tx.commit().await?;
+            Ok(Some((user, site)))
+        }
+        .await;
+        signup.map_err(Self::site_error)?.ok_or(AppError::SiteLimit)
+    }
+
+    pub async fn user_by_id(&self, id: i64) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(&sql("select * from users where id = ?"))
+            .bind(id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    pub async fn user_by_login_code(&self, login_code: String) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(&sql(
+            "select * from users where login_code = ? and id not in (select user_id from organizations) limit 1",
+        ))
+            .bind(login_code)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    /// Accounts that verified this email, usually one. An address isn't
+    /// unique so someone can't claim another person's email to block them.
+    pub async fn users_by_email(&self, email: &str) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(&sql(
+            "select * from users where lower(email) = lower(?) and email_verified_at is not null",
+        ))
+        .bind(email)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Sets or clears the recovery email. A new address starts out
+    /// unverified.
+    pub async fn update_user_email(
+        &self,
+        user_id: i64,
+        email: Option<&str>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set email = ?, email_verified_at = null, updated_at = ? where id = ?",
+        ))
+        .bind(email)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn verify_user_email(&self, user_id: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set email_verified_at = ?, updated_at = ? where id = ? and email is not null",
+        ))
+        .bind(Self::now())
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn insert_login(&self, new_login: Login) -> Result<Login, sqlx::Error> {
+        let now = Timestamp::now();
+        sqlx::query_as::<_, Login>(
+            &sql("insert into logins (user_id, ip_address, user_agent, created_at) values (?, ?, ?, ?) returning *"),
+        )
+        .bind(new_login.user_id)
+        .bind(new_login.ip_address)
+        .bind(new_login.user_agent)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// The user's last `limit` logins, newest first, signup included.
+    pub async fn recent_logins(&self, user_id: i64, limit: i64) -> Result<Vec<Login>, sqlx::Error> {
+        sqlx::query_as::<_, Login>(&sql(
+            "select * from logins where user_id = ? order by id desc limit ?",
+        ))
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn passkeys_by_user_id(&self, user_id: i64) -> Result<Vec<Passkey>, sqlx::Error> {
+        sqlx::query_as::<_, Passkey>(&sql("select * from passkeys where user_id = ? order by id"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn passkey_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> Result<Passkey, sqlx::Error> {
+        sqlx::query_as::<_, Passkey>(&sql("select * from passkeys where credential_id = ?"))
+            .bind(credential_id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    pub async fn insert_passkey(
+        &self,
+        user_id: i64,
+        credential_id: &str,
+        passkey: &str,
+    ) -> Result<Passkey, sqlx::Error> {
+        sqlx::query_as::<_, Passkey>(&sql(
+            "insert into passkeys (user_id, credential_id, passkey, created_at) values (?, ?, ?, ?) returning *",
+        ))
+        .bind(user_id)
+        .bind(credential_id)
+        .bind(passkey)
+        .bind(Self::now())
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Stores the credential after a login, its counter moved on.
+    pub async fn update_passkey(&self, id: i64, passkey: &str) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update passkeys set passkey = ?, last_used_at = ? where id = ?",
+        ))
+        .bind(passkey)
+        .bind(Self::now())
+        .bind(id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn delete_passkey(&self, user_id: i64, id: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("delete from passkeys where id = ? and user_id = ?"))
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.writer)
+            .await
+    }
+
+    /// Returns the row and the token, which can't be read back later.
+    pub async fn insert_api_token(
+        &self,
+        user_id: i64,
+        created_by: i64,
+        name: &str,
+        scopes: &[Scope],
+    ) -> Result<(ApiToken, String), sqlx::Error> {
+        let token = format!("{}{}", ApiToken::PREFIX, nanoid!(32));
+        let scopes = scopes
+            .iter()
+            .map(Scope::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let api_token = sqlx::query_as::<_, ApiToken>(&sql(
+            "insert into api_tokens (user_id, name, token_hash, scopes, created_at, created_by) values (?, ?, ?, ?, ?, ?) returning *",
+        ))
+        .bind(user_id)
+        .bind(name)
+        .bind(token::hash(&token))
+        .bind(scopes)
+        .bind(Self::now())
+        .bind(created_by)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok((api_token, token))
+    }
+
+    pub async fn api_tokens_by_user_id(&self, user_id: i64) -> Result<Vec<ApiToken>, sqlx::Error> {
+        sqlx::query_as::<_, ApiToken>(&sql(
+            "select * from api_tokens where user_id = ? order by id",
+        ))
+        .bind(user_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Looks the token up without marking it used.
+    pub async fn api_token_by_token(&self, token: &str) -> Result<ApiToken, sqlx::Error> {
+        sqlx::query_as::<_, ApiToken>(&sql("select * from api_tokens where token_hash = ?"))
+            .bind(token::hash(token))
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    /// Looks the token up and records that it was used.
+    pub async fn use_api_token(&self, token: &str) -> Result<ApiToken, sqlx::Error> {
+        sqlx::query_as::<_, ApiToken>(&sql(
+            "update api_tokens set last_used_at = ? where token_hash = ? returning *",
+        ))
+        .bind(Self::now())
+        .bind(token::hash(token))
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn delete_api_token(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("delete from api_tokens where id = ? and user_id = ?"))
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.writer)
+            .await
+    }
+
+    /// Returns the webhook and its signing secret, which can't be read back
+    /// through the api later.
+    pub async fn insert_webhook(
+        &self,
+        user_id: i64,
+        url: &str,
+        events: &[Event],
+    ) -> Result<(Webhook, String), sqlx::Error> {
+        let secret = nanoid!(32);
+        let events = events
+            .iter()
+            .map(Event::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let webhook = sqlx::query_as::<_, Webhook>(&sql(
+            "insert into webhooks (user_id, url, events, secret, created_at) values (?, ?, ?, ?, ?) returning *",
+        ))
+        .bind(user_id)
+        .bind(url)
+        .bind(events)
+        .bind(&secret)
+        .bind(Self::now())
+        .fetch_one(&self.writer)
+        .await?;
+        Ok((webhook, secret))
+    }
+
+    pub async fn webhooks_by_user_id(&self, user_id: i64) -> Result<Vec<Webhook>, sqlx::Error> {
+        sqlx::query_as::<_, Webhook>(&sql("select * from webhooks where user_id = ? order by id"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn webhook_by_id(&self, user_id: i64, id: i64) -> Result<Webhook, sqlx::Error> {
+        sqlx::query_as::<_, Webhook>(&sql("select * from webhooks where id = ? and user_id = ?"))
+            .bind(id)
+            .bind(user_id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    /// Deletes the webhook with its delivery log.
+    pub async fn delete_webhook(&self, user_id: i64, id: i64) -> Result<Webhook, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        let webhook = sqlx::query_as::<_, Webhook>(&sql(
+            "select * from webhooks where id = ? and user_id = ?",
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&sql("delete from webhook_deliveries where webhook_id = ?"))
+            .bind(webhook.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&sql("delete from webhooks where id = ?"))
+            .bind(webhook.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(webhook)
+    }
+
+    /// Logs a delivery, keeping the last [`Self::WEBHOOK_DELIVERIES`] per
+    /// webhook.
+    pub async fn insert_webhook_delivery(
+        &self,
+        webhook_id: i64,
+        event: Event,
+        status_code: Option<i64>,
+        error: Option<&str>,
+        duration_ms: i64,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(&sql(
+            "insert into webhook_deliveries (webhook_id, event, status_code, error, duration_ms, created_at) values (?, ?, ?, ?, ?, ?) returning *",
+        ))
+        .bind(webhook_id)
+        .bind(event.as_str())
+        .bind(status_code)
+        .bind(error)
+        .bind(duration_ms)
+        .bind(Self::now())
+        .fetch_one(&self.writer)
+        .await?;
+        sqlx::query(&sql(
+            "delete from webhook_deliveries where webhook_id = ?1 and id not in (select id from webhook_deliveries where webhook_id = ?1 order by id desc limit ?2)",
+        ))
+        .bind(webhook_id)
+        .bind(Self::WEBHOOK_DELIVERIES)
+        .execute(&self.writer)
+        .await?;
+        Ok(delivery)
+    }
+
+    /// Newest first.
+    pub async fn webhook_deliveries(
+        &self,
+        webhook_id: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        sqlx::query_as::<_, WebhookDelivery>(&sql(
+            "select * from webhook_deliveries where webhook_id = ? order by id desc",
+        ))
+        .bind(webhook_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Whether the user logged in before from this ip and user agent.
+    pub async fn login_seen(&self, login: &Login) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            &sql("select exists(select 1 from logins where user_id = ? and ip_address is not distinct from ? and user_agent is not distinct from ?)"),
+        )
+        .bind(login.user_id)
+        .bind(&login.ip_address)
+        .bind(&login.user_agent)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    /// Failed logins since `since` from the ip and for the code, counted
+    /// separately so neither spreading guesses over ips nor over codes gets
+    /// around the lockout.
+    pub async fn login_failures(
+        &self,
+        ip_address: Option<&str>,
+        login_code: &str,
+        since: i64,
+    ) -> Result<LoginFailures, sqlx::Error> {
+        sqlx::query_as::<_, LoginFailures>(&sql(
+            r#"
+            select
+                (select count(*) from login_attempts where ip_address = ?1 and created_at > ?3) as ip_failures,
+                (select count(*) from login_attempts where login_code = ?2 and created_at > ?3) as code_failures,
+                (
+                    select max(created_at) from login_attempts
+                    where (ip_address = ?1 or login_code = ?2) and created_at > ?3
+                ) as last_failed_at
+            "#,
+        ))
+        .bind(ip_address)
+        .bind(login_code)
+        .bind(since)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    /// Records a failed login. Attempts older than the window are cleaned
+    /// up on the way.
+    pub async fn insert_login_attempt(
+        &self,
+        ip_address: Option<&str>,
+        login_code: &str,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query(&sql("delete from login_attempts where created_at < ?"))
+            .bind(now - LoginFailures::WINDOW_SECS)
+            .execute(&self.writer)
+            .await?;
+        sqlx::query(&sql(
+            "insert into login_attempts (ip_address, login_code, created_at) values (?, ?, ?)",
+        ))
+        .bind(ip_address)
+        .bind(login_code)
+        .bind(now)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Forgets the failures for the code after a login with it goes
+    /// through. Failures from the ip stay, otherwise logging into an
+    /// account of one's own between guesses would lift the lockout.
+    pub async fn clear_login_attempts(&self, login_code: &str) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("delete from login_attempts where login_code = ?"))
+            .bind(login_code)
+            .execute(&self.writer)
+            .await
+    }
+
+    /// Sqlite's extended result code for a unique constraint failure.
+    #[cfg(not(feature = "postgres"))]
+    const UNIQUE_VIOLATION: &'static str = "2067";
+    #[cfg(feature = "postgres")]
+    const UNIQUE_VIOLATION: &'static str = "23505";
+
+    /// Turns a unique constraint failure on sites into
+    /// [`AppError::DuplicateSite`].
+    fn site_error(source: sqlx::Error) -> AppError {
+        match &source {
+            sqlx::Error::Database(err) if err.code().as_deref() == Some(Self::UNIQUE_VIOLATION) => {
+                AppError::DuplicateSite
+            }
+            sqlx::Error::RowNotFound => AppError::DatabaseSelect { source },
+            _ => AppError::DatabaseInsert { source },
+        }
+    }
+
+    /// Fails with `SiteLimit` once the user monitors as many sites as
+    /// their limit allows, the count is checked in the insert itself. A site
+    /// left at 0 gets the account's default interval and a threshold of 1.
+    pub async fn insert_site(&self, site: Site) -> Result<Site, AppError> {
+        let inserted: Result<Option<Site>, sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            let site = Self::insert_site_in(&mut tx, &site, self.max_sites).await?;
+            tx.commit().await?;
+            Ok(site)
+        }
+        .await;
+        inserted
+            .map_err(Self::site_error)?
+            .ok_or(AppError::SiteLimit)
+    }
+
+    /// `None` when the user is at their site limit.
+    async fn insert_site_in(
+        tx: &mut sqlx::Transaction<'_, Backend>,
+        site: &Site,
+        max_sites: Option<i64>,
+    ) -> Result<Option<Site>, sqlx::Error> {
+        let now = Timestamp::now();
+        sqlx::query_as::<_, Site>(&sql(r#"
+            insert into sites (url, name, user_id, interval_secs, failure_threshold, created_at, updated_at)
+            select ?1, ?2, ?3,
+                coalesce(?7, (select default_interval_secs from users where id = ?3)),
+                coalesce(?8, 1),
+                ?4, ?4
+            where (select count(*) from sites where user_id = ?3)
+                < coalesce((select max_sites from users where id = ?3), ?5, ?6)
+            returning *
+            "#))
+        .bind(&site.url)
+        .bind(&site.name)
+        .bind(site.user_id)
+        .bind(now)
+        .bind(max_sites)
+        .bind(i64::MAX)
+        .bind(Some(site.interval_secs).filter(|secs| *secs > 0))
+        .bind(Some(site.failure_threshold).filter(|threshold| *threshold > 0))
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// How many more sites the user can add, `None` without a limit.
+    async fn sites_left(
+        tx: &mut sqlx::Transaction<'_, Backend>,
+        user_id: i64,
+        max_sites: Option<i64>,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let (user_max_sites, count) = sqlx::query_as::<_, (Option<i64>, i64)>(&sql(
+            "select (select max_sites from users where id = ?1), (select count(*) from sites where user_id = ?1)",
+        ))
+        .bind(user_id)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(user_max_sites
+            .or(max_sites)
+            .map(|max_sites| (max_sites - count).max(0)))
+    }
+
+    /// Adds the sites in one transaction and returns the ones that were
+    /// added, urls the user already monitors are skipped. Sites past the
+    /// user's limit aren't added and come back as the second list.
+    pub async fn insert_sites(&self, sites: &[Site]) -> Result<(Vec<Site>, Vec<Site>), AppError> {
+        let now = Timestamp::now();
+        let inserted: Result<(Vec<Site>, Vec<Site>), sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            let mut inserted = vec![];
+            let mut over_limit = vec![];
+            let mut left = match sites.first() {
+                Some(site) => Self::sites_left(&mut tx, site.user_id, self.max_sites).await?,
+                None => None,
+            };
+            for site in sites {
+                if left == Some(0) {
+                    over_limit.push(site.clone());
+                    continue;
+                }
+                let site = sqlx::query_as::<_, Site>(&sql(
+                    "insert into sites (url, name, user_id, interval_secs, failure_threshold, created_at, updated_at) values (?, ?, ?, ?, ?, ?, ?) on conflict (user_id, url) do nothing returning *",
+                ))
+                .bind(&site.url)
+                .bind(&site.name)
+                .bind(site.user_id)
+                .bind(site.interval_secs)
+                .bind(site.failure_threshold)
+                .bind(now)
+                .bind(now)
+                .fetch_optional(&mut *tx)
+                .await?;
+                if site.is_some() {
+                    left = left.map(|left| left - 1);
+                }
+                inserted.extend(site);
+            }
+            tx.commit().await?;
+            Ok((inserted, over_limit))
+        }
+        .await;
+        inserted.map_err(|source| AppError::DatabaseInsert { source })
+    }
+
+    pub async fn sites_by_user_id(&self, user_id: i64) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(&sql("select * from sites where user_id = ?"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn sites(&self) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(&sql("select * from sites"))
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    /// The user's sites, newest first, with ids below `cursor`.
+    pub async fn sites_page(
+        &self,
+        user_id: i64,
+        cursor: Option<i64>,
+        limit: i64,
+    ) -> Result<Page<Site>, sqlx::Error> {
+        let sites = sqlx::query_as::<_, Site>(
+            &sql("select * from sites where user_id = ? and id < coalesce(?, 9223372036854775807) order by id desc limit ?"),
+        )
+        .bind(user_id)
+        .bind(cursor)
+        .bind(limit + 1)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(Page::new(sites, limit, |site| site.id))
+    }
+
+    /// The site's checks, newest first, with ids below `cursor`. Checks
+    /// that were rolled up are gone from here.
+    pub async fn checks_page(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        cursor: Option<i64>,
+        limit: i64,
+    ) -> Result<Page<models::Check>, sqlx::Error> {
+        let checks = sqlx::query_as::<_, models::Check>(
+            &sql("select checks.* from checks join sites on sites.id = checks.site_id where checks.site_id = ? and sites.user_id = ? and checks.id < coalesce(?, 9223372036854775807) order by checks.id desc limit ?"),
+        )
+        .bind(site_id)
+        .bind(user_id)
+        .bind(cursor)
+        .bind(limit + 1)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(Page::new(checks, limit, |check| check.id))
+    }
+
+    /// The newest check id, where pages waiting for new checks start.
+    pub async fn last_check_id(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(&sql("select coalesce(max(id), 0) from checks"))
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    /// The checks of the user's sites with ids above `after`, oldest first.
+    pub async fn checks_after(
+        &self,
+        user_id: i64,
+        after: i64,
+    ) -> Result<Vec<models::Check>, sqlx::Error> {
+        sqlx::query_as::<_, models::Check>(
+            &sql("select checks.* from checks join sites on sites.id = checks.site_id where sites.user_id = ? and checks.id > ? order by checks.id"),
+        )
+        .bind(user_id)
+        .bind(after)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// The last `per_site` checks of each site in one statement, rather
+    /// than a query per site, oldest first.
+    pub async fn recent_checks_for_sites(
+        &self,
+        site_ids: &[i64],
+        per_site: i64,
+    ) -> Result<Vec<models::Check>, sqlx::Error> {
+        if site_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let placeholders = vec!["?"; site_ids.len()].join(", ");
+        let statement = format!(
+            r#"
+            select * from (
+                select
+                    checks.*,
+                    row_number() over (
+                        partition by checks.site_id
+                        order by checks.created_at desc, checks.id desc
+                    ) as position
+                from checks
+                where checks.site_id in ({})
+            ) as recent
+            where position <= ?
+            order by site_id, created_at, id
+            "#,
+            placeholders
+        );
+        let statement = sql(&statement);
+        let mut query = sqlx::query_as::<_, models::Check>(&statement);
+        for site_id in site_ids {
+            query = query.bind(site_id);
+        }
+        query.bind(per_site).fetch_all(&self.reader).await
+    }
+
+    /// Sites whose last check is at least their interval old, give or take
+    /// half a watch cycle so a slow cycle doesn't push a site back a whole
+    /// interval.
+    pub async fn sites_due_check(&self, now: i64) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(
+            &sql("select * from sites where disabled_at is null and paused_at is null and coalesce((select max(created_at) from checks where site_id = sites.id), 0) <= ? - interval_secs"),
+        )
+        .bind(now + Self::WATCH_INTERVAL_SECS / 2)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn update_site(&self, site: Site) -> Result<Site, AppError> {
+        sqlx::query_as::<_, Site>(
+            &sql("update sites set url = ?, name = ?, interval_secs = ?, failure_threshold = ?, updated_at = ? where id = ? and user_id = ? returning *"),
+        )
+        .bind(site.url)
+        .bind(site.name)
+        .bind(site.interval_secs)
+        .bind(site.failure_threshold)
+        .bind(Timestamp::now())
+        .bind(site.id)
+        .bind(site.user_id)
+        .fetch_one(&self.writer)
+        .await
+        .map_err(Self::site_error)
+    }
+
+    /// Whether the site's last `count` checks all failed.
+    pub async fn failed_in_a_row(&self, site_id: i64, count: i64) -> Result<bool, sqlx::Error> {
+        let failures = sqlx::query_scalar::<_, i64>(
+            &sql("select count(*) from (select status_code from checks where site_id = ? and not maintenance order by id desc limit ?) as recent where status_code not between 200 and 299"),
+        )
+        .bind(site_id)
+        .bind(count)
+        .fetch_one(&self.reader)
+        .await?;
+        Ok(failures >= count)
+    }
+
+    pub async fn upsert_response(&self, response: Response) -> Result<Response, sqlx::Error> {
+        let now = Timestamp::now();
+        sqlx::query_as::<_, Response>(
+            &sql("insert into responses (status_code, site_id, created_at, updated_at) values (?, ?, ?, ?) on conflict (status_code, site_id) do update set updated_at = excluded.updated_at returning *"),
+        )
+        .bind(response.status_code)
+        .bind(response.site_id)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// The response a site most recently returned, served by the
+    /// `(site_id, updated_at)` index. Responses are upserted per status code,
+    /// so a code the site returns again only bumps `updated_at` and ordering
+    /// by `created_at` would pick the wrong one.
+    pub async fn latest_response_by_site(&self, site_id: i64) -> Result<Response, sqlx::Error> {
+        sqlx::query_as::<_, Response>(&sql(
+            "select * from responses where site_id = ? order by updated_at desc limit 1",
+        ))
+        .bind(site_id)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    /// A login row for the user, ready for `insert_login`.
+    pub fn new_login(user_id: i64) -> Login {
+        let mut login = Login::default();
+        login.user_id = user_id;
+        login
+    }
+
+    /// How many times the user has logged in, signup included.
+    pub async fn login_count(&self, user_id: i64) -> Result<i32, sqlx::Error> {
+        let count =
+            sqlx::query_scalar::<_, i64>(&sql("select count(id) from logins where user_id = ?"))
+                .bind(user_id)
+                .fetch_one(&self.reader)
+                .await?;
+        Ok(count as i32)
+    }
+
+    pub async fn insert_channel(
+        &self,
+        channel: NotificationChannel,
+    ) -> Result<NotificationChannel, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, NotificationChannel>(
+            &sql("insert into notification_channels (user_id, kind, config, cooldown_secs, renotify_secs, template, created_at, updated_at) values (?, ?, ?, ?, ?, ?, ?, ?) returning *"),
+        )
+        .bind(channel.user_id)
+        .bind(channel.kind)
+        .bind(channel.config)
+        .bind(channel.cooldown_secs)
+        .bind(channel.renotify_secs)
+        .bind(channel.template)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn channels_by_user_id(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<NotificationChannel>, sqlx::Error> {
+        sqlx::query_as::<_, NotificationChannel>(&sql(
+            "select * from notification_channels where user_id = ? order by created_at",
+        ))
+        .bind(user_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn channel_by_id(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<NotificationChannel, sqlx::Error> {
+        sqlx::query_as::<_, NotificationChannel>(&sql(
+            "select * from notification_channels where user_id = ? and id = ?",
+        ))
+        .bind(user_id)
+        .bind(id)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    pub async fn update_channel(
+        &self,
+        channel: NotificationChannel,
+    ) -> Result<NotificationChannel, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, NotificationChannel>(
+            &sql("update notification_channels set kind = ?, config = ?, cooldown_secs = ?, renotify_secs = ?, template = ?, verified = false, updated_at = ? where user_id = ? and id = ? returning *"),
+        )
+        .bind(channel.kind)
+        .bind(channel.config)
+        .bind(channel.cooldown_secs)
+        .bind(channel.renotify_secs)
+        .bind(channel.template)
+        .bind(now)
+        .bind(channel.user_id)
+        .bind(channel.id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn verify_channel(&self, id: i64) -> Result<QueryResult, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query(&sql("update notification_channels set verified = true, updated_at = ? where id = ? and not verified"))
+            .bind(now)
+            .bind(id)
+            .execute(&self.writer)
+            .await
+    }
+
+    pub async fn delete_channel(&self, user_id: i64, id: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("delete from notification_routes where channel_id = (select id from notification_channels where user_id = ? and id = ?)"))
+            .bind(user_id)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        sqlx::query(&sql(
+            "delete from queued_notifications where user_id = ? and channel_id = ?",
+        ))
+        .bind(user_id)
+        .bind(id)
+        .execute(&self.writer)
+        .await?;
+        sqlx::query(&sql(
+            "delete from notification_channels where user_id = ? and id = ?",
+        ))
+        .bind(user_id)
+        .bind(id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Every table that points at a site, children first, so a site can be
+    /// deleted without tripping its foreign keys.
+    const SITE_TABLES: [&'static str; 18] = [
+        "notification_routes",
+        "site_icons",
+        "maintenance_windows",
+        "subscribers",
+        "incident_updates",
+        "status_incidents",
+        "share_links",
+        "site_tags",
+        "notifications_sent",
+        "site_transfers",
+        "latency_rules",
+        "expiry_rules",
+        "expiries",
+        "incidents",
+        "checks",
+        "checks_hourly",
+        "checks_daily",
+        "responses",
+    ];
+
+    /// Deletes the site and everything recorded about it in one transaction.
+    pub async fn delete_site(&self, user_id: i64, site_id: i64) -> Result<Site, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        let site = Self::delete_site_in(&mut tx, user_id, site_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        tx.commit().await?;
+        Ok(site)
+    }
+
+    async fn delete_site_in(
+        tx: &mut sqlx::Transaction<'_, Backend>,
+        user_id: i64,
+        site_id: i64,
+    ) -> Result<Option<Site>, sqlx::Error> {
+        let site =
+            sqlx::query_as::<_, Site>(&sql("select * from sites where id = ? and user_id = ?"))
+                .bind(site_id)
+                .bind(user_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+        let Some(site) = site else {
+            return Ok(None);
+        };
+        for table in Self::SITE_TABLES {
+            sqlx::query(&sql(&format!("delete from {} where site_id = ?", table)))
+                .bind(site.id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        sqlx::query(&sql("delete from sites where id = ?"))
+            .bind(site.id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(Some(site))
+    }
+
+    /// Applies the operations in order in one transaction. The first one
+    /// that fails rolls all of them back and is returned with its index.
+    /// Creating a url the user already monitors and deleting a site that's
+    /// gone aren't failures, so a batch can be sent again safely.
+    pub async fn apply_site_ops(
+        &self,
+        user_id: i64,
+        ops: Vec<SiteOp>,
+    ) -> Result<Vec<SiteOpResult>, BatchError> {
+        let failed = |index: usize| move |error: AppError| BatchError { index, error };
+        let insert_error = |source: sqlx::Error| AppError::DatabaseInsert { source };
+        let mut tx = self
+            .writer
+            .begin()
+            .await
+            .map_err(insert_error)
+            .map_err(failed(0))?;
+        let mut left = Self::sites_left(&mut tx, user_id, self.max_sites)
+            .await
+            .map_err(insert_error)
+            .map_err(failed(0))?;
+        let now = Timestamp::now();
+        let mut results = vec![];
+        for (index, op) in ops.into_iter().enumerate() {
+            let result = match op {
+                SiteOp::Create(site) => {
+                    let existing = sqlx::query_as::<_, Site>(&sql(
+                        "select * from sites where user_id = ? and url = ?",
+                    ))
+                    .bind(user_id)
+                    .bind(&site.url)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(insert_error)
+                    .map_err(failed(index))?;
+                    match existing {
+                        Some(site) => SiteOpResult {
+                            status: SiteOpStatus::Exists,
+                            site: Some(site),
+                        },
+                        None if left == Some(0) => {
+                            return Err(failed(index)(AppError::SiteLimit));
+                        }
+                        None => {
+                            let site = sqlx::query_as::<_, Site>(&sql(
+                                "insert into sites (url, name, user_id, interval_secs, failure_threshold, created_at, updated_at) values (?, ?, ?, ?, ?, ?, ?) returning *",
+                            ))
+                            .bind(site.url)
+                            .bind(site.name)
+                            .bind(user_id)
+                            .bind(site.interval_secs)
+                            .bind(site.failure_threshold)
+                            .bind(now)
+                            .bind(now)
+                            .fetch_one(&mut *tx)
+                            .await
+                            .map_err(Self::site_error)
+                            .map_err(failed(index))?;
+                            left = left.map(|left| left - 1);
+                            SiteOpResult {
+                                status: SiteOpStatus::Created,
+                                site: Some(site),
+                            }
+                        }
+                    }
+                }
+                SiteOp::Update(site) => {
+                    let site = sqlx::query_as::<_, Site>(&sql(
+                        "update sites set url = ?, name = ?, interval_secs = ?, failure_threshold = ?, updated_at = ? where id = ? and user_id = ? returning *",
+                    ))
+                    .bind(site.url)
+                    .bind(site.name)
+                    .bind(site.interval_secs)
+                    .bind(site.failure_threshold)
+                    .bind(now)
+                    .bind(site.id)
+                    .bind(user_id)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(Self::site_error)
+                    .map_err(failed(index))?;
+                    SiteOpResult {
+                        status: SiteOpStatus::Updated,
+                        site: Some(site),
+                    }
+                }
+                SiteOp::Delete(site_id) => {
+                    let site = Self::delete_site_in(&mut tx, user_id, site_id)
+                        .await
+                        .map_err(insert_error)
+                        .map_err(failed(index))?;
+                    if site.is_some() {
+                        left = left.map(|left| left + 1);
+                    }
+                    SiteOpResult {
+                        status: match site {
+                            Some(_) => SiteOpStatus::Deleted,
+                            None => SiteOpStatus::Missing,
+                        },
+                        site,
+                    }
+                }
+            };
+            results.push(result);
+        }
+        let last = results.len().saturating_sub(1);
+        tx.commit()
+            .await
+            .map_err(insert_error)
+            .map_err(failed(last))?;
+        Ok(results)
+    }
+
+    /// Pauses or resumes the user's sites together, the ones they don't own
+    /// are left out.
+    pub async fn pause_sites(
+        &self,
+        user_id: i64,
+        site_ids: &[i64],
+        paused: bool,
+    ) -> Result<Vec<Site>, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        let paused_at = paused.then(Self::now);
+        let now = Timestamp::now();
+        let mut sites = vec![];
+        for site_id in site_ids {
+            let site = sqlx::query_as::<_, Site>(&sql(
+                "update sites set paused_at = ?, updated_at = ? where id = ? and user_id = ? returning *",
+            ))
+            .bind(paused_at)
+            .bind(now)
+            .bind(site_id)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            sites.extend(site);
+        }
+        tx.commit().await?;
+        Ok(sites)
+    }
+
+    /// Deletes the user's sites together with everything that belongs to
+    /// them, all or none.
+    pub async fn delete_sites(
+        &self,
+        user_id: i64,
+        site_ids: &[i64],
+    ) -> Result<Vec<Site>, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        let mut sites = vec![];
+        for site_id in site_ids {
+            sites.extend(Self::delete_site_in(&mut tx, user_id, *site_id).await?);
+        }
+        tx.commit().await?;
+        Ok(sites)
+    }
+
+    /// Adds the tag to each of the user's sites, creating it when it's new.
+    pub async fn tag_sites(
+        &self,
+        user_id: i64,
+        site_ids: &[i64],
+        name: &str,
+    ) -> Result<Tag, AppError> {
+        let name = Tag::normalize_name(name)?;
+        let result: Result<Tag, sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            let tag = Self::upsert_tag(&mut tx, user_id, &name).await?;
+            for site_id in site_ids {
+                sqlx::query(
+                    &sql("insert into site_tags (site_id, tag_id) select id, ? from sites where id = ? and user_id = ? on conflict do nothing"),
+                )
+                .bind(tag.id)
+                .bind(site_id)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(tag)
+        }
+        .await;
+        result.map_err(|source| AppError::DatabaseInsert { source })
+    }
+
+    /// Takes the tag off each of the user's sites, the tag itself stays.
+    /// Returns the tag's normalized name.
+    pub async fn untag_sites(
+        &self,
+        user_id: i64,
+        site_ids: &[i64],
+        name: &str,
+    ) -> Result<String, AppError> {
+        let name = Tag::normalize_name(name)?;
+        let result: Result<(), sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            for site_id in site_ids {
+                sqlx::query(
+                    &sql("delete from site_tags where site_id = (select id from sites where id = ? and user_id = ?) and tag_id = (select id from tags where user_id = ? and name = ?)"),
+                )
+                .bind(site_id)
+                .bind(user_id)
+                .bind(user_id)
+                .bind(&name)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await
+        }
+        .await;
+        result.map_err(|source| AppError::DatabaseInsert { source })?;
+        Ok(name)
+    }
+
+    /// Gathers the user's data in one transaction so checks and incidents
+    /// line up with the sites they belong to.
+    pub async fn export_user(&self, user_id: i64) -> Result<Export, sqlx::Error> {
+        let mut tx = self.reader.begin().await?;
+        let user = sqlx::query_as::<_, User>(&sql("select * from users where id = ?"))
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let logins =
+            sqlx::query_as::<_, Login>(&sql("select * from logins where user_id = ? order by id"))
+                .bind(user_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        let sites =
+            sqlx::query_as::<_, Site>(&sql("select * from sites where user_id = ? order by id"))
+                .bind(user_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        let checks = sqlx::query_as::<_, models::Check>(&sql(
+            "select checks.* from checks join sites on sites.id = checks.site_id where sites.user_id = ? order by checks.id",
+        ))
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let incidents = sqlx::query_as::<_, Incident>(&sql(
+            "select incidents.* from incidents join sites on sites.id = incidents.site_id where sites.user_id = ? order by incidents.id",
+        ))
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let channels = sqlx::query_as::<_, NotificationChannel>(&sql(
+            "select * from notification_channels where user_id = ? order by id",
+        ))
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Export {
+            user,
+            logins,
+            sites,
+            checks,
+            incidents,
+            channels,
+        })
+    }
+
+    /// Deletes the user with their sites, everything recorded about those,
+    /// their channels and logins in one transaction. The last owner of an
+    /// organization can't leave it without anyone to manage it.
+    pub async fn delete_user(&self, user_id: i64) -> Result<User, AppError> {
+        let deleted: Result<Option<User>, sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            let last_owner = sqlx::query_scalar::<_, bool>(&sql(r#"
+                select exists(
+                    select 1 from memberships
+                    where memberships.user_id = ?1 and memberships.role = 'owner'
+                    and not exists (
+                        select 1 from memberships owners
+                        where owners.organization_id = memberships.organization_id
+                        and owners.role = 'owner' and owners.user_id != ?1
+                    )
+                )
+                "#))
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if last_owner {
+                return Ok(None);
+            }
+            let user = Self::delete_user_in(&mut tx, user_id).await?;
+            tx.commit().await?;
+            Ok(Some(user))
+        }
+        .await;
+        deleted
+            .map_err(|source| AppError::DatabaseInsert { source })?
+            .ok_or(AppError::LastOwner)
+    }
+
+    async fn delete_user_in(
+        tx: &mut sqlx::Transaction<'_, Backend>,
+        user_id: i64,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as::<_, User>(&sql("select * from users where id = ?"))
+            .bind(user_id)
+            .fetch_one(&mut **tx)
+            .await?;
+        for table in Self::SITE_TABLES {
+            sqlx::query(&sql(&format!(
+                "delete from {} where site_id in (select id from sites where user_id = ?)",
+                table
+            )))
+            .bind(user.id)
+            .execute(&mut **tx)
+            .await?;
+        }
+        for table in ["notification_routes", "notifications_sent"] {
+            sqlx::query(&sql(&format!(
+                "delete from {} where channel_id in (select id from notification_channels where user_id = ?)",
+                table
+            )))
+            .bind(user.id)
+            .execute(&mut **tx)
+            .await?;
+        }
+        // sites they gave away keep their incidents and transfer history
+        sqlx::query(&sql(
+            "update incidents set acknowledged_by = null where acknowledged_by = ?",
+        ))
+        .bind(user.id)
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query(&sql("delete from invitations where created_by = ?"))
+            .bind(user.id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(&sql(
+            "delete from site_transfers where from_user_id = ? or to_user_id = ?",
+        ))
+        .bind(user.id)
+        .bind(user.id)
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query(&sql(
+            "delete from webhook_deliveries where webhook_id in (select id from webhooks where user_id = ?)",
+        ))
+        .bind(user.id)
+        .execute(&mut **tx)
+        .await?;
+        for table in [
+            "queued_notifications",
+            "latency_rules",
+            "expiry_rules",
+            "tags",
+            "sites",
+            "notification_channels",
+            "logins",
+            "passkeys",
+            "api_tokens",
+            "webhooks",
+            "memberships",
+        ] {
+            sqlx::query(&sql(&format!("delete from {} where user_id = ?", table)))
+                .bind(user.id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        sqlx::query(&sql("delete from api_tokens where created_by = ?"))
+            .bind(user.id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(&sql("delete from users where id = ?"))
+            .bind(user.id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(user)
+    }
+
+    /// Creates the organization with its account and makes the user its
+    /// owner.
+    pub async fn insert_organization(
+        &self,
+        owner_id: i64,
+        name: &str,
+    ) -> Result<Organization, sqlx::Error> {
+        let now = Self::now();
+        let mut tx = self.writer.begin().await?;
+        let account = sqlx::query_as::<_, User>(&sql(
+            "insert into users (login_code, created_at, updated_at) values (?, ?, ?) returning *",
+        ))
+        .bind(nanoid!())
+        .bind(Timestamp::now())
+        .bind(Timestamp::now())
+        .fetch_one(&mut *tx)
+        .await?;
+        let organization = sqlx::query_as::<_, Organization>(&sql(
+            "insert into organizations (name, user_id, created_at, updated_at) values (?, ?, ?, ?) returning *",
+        ))
+        .bind(name)
+        .bind(account.id)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&sql(
+            "insert into memberships (organization_id, user_id, role, created_at) values (?, ?, ?, ?)",
+        ))
+        .bind(organization.id)
+        .bind(owner_id)
+        .bind(Role::Owner)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(organization)
+    }
+
+    pub async fn organization_by_id(&self, id: i64) -> Result<Organization, sqlx::Error> {
+        sqlx::query_as::<_, Organization>(&sql("select * from organizations where id = ?"))
+            .bind(id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    /// The organization the account belongs to, if it's one's account.
+    pub async fn organization_by_user_id(&self, user_id: i64) -> Result<Organization, sqlx::Error> {
+        sqlx::query_as::<_, Organization>(&sql("select * from organizations where user_id = ?"))
+            .bind(user_id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    pub async fn organizations_by_user_id(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<Organization>, sqlx::Error> {
+        sqlx::query_as::<_, Organization>(&sql(r#"
+            select organizations.*
+            from organizations
+            join memberships on memberships.organization_id = organizations.id
+            where memberships.user_id = ?
+            order by organizations.name
+            "#))
+        .bind(user_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn membership(
+        &self,
+        organization_id: i64,
+        user_id: i64,
+    ) -> Result<Membership, sqlx::Error> {
+        sqlx::query_as::<_, Membership>(&sql(
+            "select * from memberships where organization_id = ? and user_id = ?",
+        ))
+        .bind(organization_id)
+        .bind(user_id)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    pub async fn memberships_by_organization_id(
+        &self,
+        organization_id: i64,
+    ) -> Result<Vec<Membership>, sqlx::Error> {
+        sqlx::query_as::<_, Membership>(&sql(
+            "select * from memberships where organization_id = ? order by id",
+        ))
+        .bind(organization_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Changes a member's role. The last owner can't be demoted so an
+    /// organization always has someone to manage it.
+    pub async fn update_membership_role(
+        &self,
+        organization_id: i64,
+        user_id: i64,
+        role: Role,
+    ) -> Result<Membership, sqlx::Error> {
+        sqlx::query_as::<_, Membership>(&sql(r#"
+            update memberships set role = ?1
+            where organization_id = ?2 and user_id = ?3
+            and (?1 = 'owner' or (
+                select count(*) from memberships owners
+                where owners.organization_id = ?2 and owners.role = 'owner' and owners.user_id != ?3
+            ) > 0)
+            returning *
+            "#))
+        .bind(role)
+        .bind(organization_id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Removes a member, unless they're the last owner, and revokes the api
+    /// tokens they made for the organization.
+    pub async fn delete_membership(
+        &self,
+        organization_id: i64,
+        user_id: i64,
+    ) -> Result<Membership, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        let membership = sqlx::query_as::<_, Membership>(&sql(r#"
+            delete from memberships
+            where organization_id = ?1 and user_id = ?2
+            and (role != 'owner' or (
+                select count(*) from memberships owners
+                where owners.organization_id = ?1 and owners.role = 'owner' and owners.user_id != ?2
+            ) > 0)
+            returning *
+            "#))
+        .bind(organization_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&sql(
+            "delete from api_tokens where created_by = ? and user_id = (select user_id from organizations where id = ?)",
+        ))
+        .bind(user_id)
+        .bind(organization_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(membership)
+    }
+
+    /// Deletes the organization, its memberships and its account with
+    /// every site and channel in it.
+    pub async fn delete_organization(&self, id: i64) -> Result<Organization, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        for table in ["memberships", "invitations"] {
+            sqlx::query(&sql(&format!(
+                "delete from {} where organization_id = ?",
+                table
+            )))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let organization = sqlx::query_as::<_, Organization>(&sql(
+            "delete from organizations where id = ? returning *",
+        ))
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        Self::delete_user_in(&mut tx, organization.user_id).await?;
+        tx.commit().await?;
+        Ok(organization)
+    }
+
+    pub async fn insert_invitation(
+        &self,
+        organization_id: i64,
+        role: Role,
+        created_by: i64,
+    ) -> Result<Invitation, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, Invitation>(&sql(
+            "insert into invitations (organization_id, role, created_by, expires_at, created_at) values (?, ?, ?, ?, ?) returning *",
+        ))
+        .bind(organization_id)
+        .bind(role)
+        .bind(created_by)
+        .bind(now + Invitation::TTL_SECS)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Invitations that can still be used.
+    pub async fn invitations_by_organization_id(
+        &self,
+        organization_id: i64,
+    ) -> Result<Vec<Invitation>, sqlx::Error> {
+        sqlx::query_as::<_, Invitation>(&sql(
+            "select * from invitations where organization_id = ? and expires_at > ? order by id desc",
+        ))
+        .bind(organization_id)
+        .bind(Self::now())
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn invitation_by_id(&self, id: i64) -> Result<Invitation, sqlx::Error> {
+        sqlx::query_as::<_, Invitation>(&sql("select * from invitations where id = ?"))
+            .bind(id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    pub async fn delete_invitation(
+        &self,
+        organization_id: i64,
+        id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "delete from invitations where id = ? and organization_id = ?",
+        ))
+        .bind(id)
+        .bind(organization_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Uses up the invitation and adds the user to its organization. Someone
+    /// who is already a member keeps their role.
+    pub async fn accept_invitation(
+        &self,
+        id: i64,
+        user_id: i64,
+    ) -> Result<Membership, sqlx::Error> {
+        let now = Self::now();
+        let mut tx = self.writer.begin().await?;
+        let invitation = sqlx::query_as::<_, Invitation>(&sql(
+            "delete from invitations where id = ? and expires_at > ? returning *",
+        ))
+        .bind(id)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&sql(
+            "insert into memberships (organization_id, user_id, role, created_at) values (?, ?, ?, ?) on conflict (organization_id, user_id) do nothing",
+        ))
+        .bind(invitation.organization_id)
+        .bind(user_id)
+        .bind(invitation.role)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        let membership = sqlx::query_as::<_, Membership>(&sql(
+            "select * from memberships where organization_id = ? and user_id = ?",
+        ))
+        .bind(invitation.organization_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(membership)
+    }
+
+    pub async fn insert_site_transfer(
+        &self,
+        user_id: i64,
+        site_id: i64,
+    ) -> Result<SiteTransfer, sqlx::Error> {
+        let token = nanoid!();
+        let now = Self::now();
+        sqlx::query_as::<_, SiteTransfer>(
+            &sql("insert into site_transfers (site_id, from_user_id, token, created_at) select id, user_id, ?, ? from sites where id = ? and user_id = ? returning *"),
+        )
+        .bind(token)
+        .bind(now)
+        .bind(site_id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Hands the site over to `user_id`. Responses stay keyed by site id so
+    /// the whole history moves along with it.
+    pub async fn accept_site_transfer(
+        &self,
+        user_id: i64,
+        token: String,
+    ) -> Result<Site, sqlx::Error> {
+        let now = Self::now();
+        let mut tx = self.writer.begin().await?;
+        let transfer = sqlx::query_as::<_, SiteTransfer>(&sql(
+            "select * from site_transfers where token = ? and accepted_at is null",
+        ))
+        .bind(token)
+        .fetch_one(&mut *tx)
+        .await?;
+        let site = sqlx::query_as::<_, Site>(&sql(
+            "update sites set user_id = ?, updated_at = ? where id = ? and user_id = ? returning *",
+        ))
+        .bind(user_id)
+        .bind(Timestamp::now())
+        .bind(transfer.site_id)
+        .bind(transfer.from_user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&sql(
+            "update site_transfers set to_user_id = ?, accepted_at = ? where id = ?",
+        ))
+        .bind(user_id)
+        .bind(now)
+        .bind(transfer.id)
+        .execute(&mut *tx)
+        .await?;
+        // the old owner's share links shouldn't outlive their ownership
+        sqlx::query(&sql("delete from share_links where site_id = ?"))
+            .bind(site.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(site)
+    }
+
+    pub async fn site_transfers_by_site_id(
+        &self,
+        site_id: i64,
+    ) -> Result<Vec<SiteTransfer>, sqlx::Error> {
+        sqlx::query_as::<_, SiteTransfer>(&sql(
+            "select * from site_transfers where site_id = ? order by created_at desc",
+        ))
+        .bind(site_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn open_incident(
+        &self,
+        site_id: i64,
+        status_code: i64,
+        flapping: bool,
+    ) -> Result<Incident, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, Incident>(
+            &sql("insert into incidents (site_id, status_code, last_status_code, started_at, flapping) values (?, ?, ?, ?, ?) returning *"),
+        )
+        .bind(site_id)
+        .bind(status_code)
+        .bind(status_code)
+        .bind(now)
+        .bind(flapping)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Acknowledges one of the user's incidents, the first acknowledgement
+    /// wins.
+    pub async fn acknowledge_incident(
+        &self,
+        user_id: i64,
+        incident_id: i64,
+    ) -> Result<Incident, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, Incident>(
+            &sql("update incidents set acknowledged_at = ?, acknowledged_by = ? where id = ? and acknowledged_at is null and site_id in (select id from sites where user_id = ?) returning *"),
+        )
+        .bind(now)
+        .bind(user_id)
+        .bind(incident_id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Keeps the open incident's last failing status code current while the
+    /// site stays down.
+    pub async fn update_incident_status_code(
+        &self,
+        site_id: i64,
+        status_code: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update incidents set last_status_code = ? where site_id = ? and resolved_at is null",
+        ))
+        .bind(status_code)
+        .bind(site_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn open_incident_by_site(
+        &self,
+        site_id: i64,
+    ) -> Result<Option<Incident>, sqlx::Error> {
+        sqlx::query_as::<_, Incident>(
+            &sql("select * from incidents where site_id = ? and resolved_at is null order by started_at desc limit 1"),
+        )
+        .bind(site_id)
+        .fetch_optional(&self.reader)
+        .await
+    }
+
+    pub async fn set_incident_flapping(
+        &self,
+        site_id: i64,
+        flapping: bool,
+    ) -> Result<Option<Incident>, sqlx::Error> {
+        sqlx::query_as::<_, Incident>(
+            &sql("update incidents set flapping = ? where site_id = ? and resolved_at is null returning *"),
+        )
+        .bind(flapping)
+        .bind(site_id)
+        .fetch_optional(&self.writer)
+        .await
+    }
+
+    /// Whether each check since `since` was up, oldest first.
+    pub async fn check_states(&self, site_id: i64, since: i64) -> Result<Vec<bool>, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            &sql("select status_code between 200 and 299 from checks where site_id = ? and created_at >= ? and not maintenance order by id"),
+        )
+        .bind(site_id)
+        .bind(since)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn resolve_incident(&self, site_id: i64) -> Result<Option<Incident>, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, Incident>(
+            &sql("update incidents set resolved_at = ? where site_id = ? and resolved_at is null returning *"),
+        )
+        .bind(now)
+        .bind(site_id)
+        .fetch_optional(&self.writer)
+        .await
+    }
+
+    fn check_incident_update(message: &str) -> Result<(), AppError> {
+        match message.trim().is_empty() || message.len() > IncidentUpdate::MAX_MESSAGE_LEN {
+            true => Err(AppError::StatusIncident),
+            false => Ok(()),
+        }
+    }
+
+    /// Publishes an incident on the site's share page with its first
+    /// update.
+    pub async fn insert_status_incident(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        title: &str,
+        status: IncidentStatus,
+        message: &str,
+    ) -> Result<IncidentTimeline, AppError> {
+        let title = title.trim();
+        if title.is_empty() || title.len() > StatusIncident::MAX_TITLE_LEN {
+            return Err(AppError::StatusIncident);
+        }
+        Self::check_incident_update(message)?;
+        let now = Self::now();
+        let resolved_at = (status == IncidentStatus::Resolved).then_some(now);
+        let insert = |source| AppError::DatabaseInsert { source };
+        let mut tx = self.writer.begin().await.map_err(insert)?;
+        let incident = sqlx::query_as::<_, StatusIncident>(&sql(
+            "insert into status_incidents (site_id, title, status, created_at, resolved_at) select id, ?, ?, ?, ? from sites where id = ? and user_id = ? returning *",
+        ))
+        .bind(title)
+        .bind(status)
+        .bind(now)
+        .bind(resolved_at)
+        .bind(site_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|source| AppError::DatabaseSelect { source })?;
+        let update = sqlx::query_as::<_, IncidentUpdate>(&sql(
+            "insert into incident_updates (status_incident_id, site_id, status, message, created_at) values (?, ?, ?, ?, ?) returning *",
+        ))
+        .bind(incident.id)
+        .bind(incident.site_id)
+        .bind(status)
+        .bind(message.trim())
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(insert)?;
+        tx.commit().await.map_err(insert)?;
+        Ok(IncidentTimeline {
+            incident,
+            updates: vec![update],
+        })
+    }
+
+    /// Adds an update and moves the incident to its status. Resolving sets
+    /// `resolved_at`, any other status opens it again. The timeline only
+    /// has the new update.
+    pub async fn insert_incident_update(
+        &self,
+        user_id: i64,
+        status_incident_id: i64,
+        status: IncidentStatus,
+        message: &str,
+    ) -> Result<IncidentTimeline, AppError> {
+        Self::check_incident_update(message)?;
+        let now = Self::now();
+        let insert = |source| AppError::DatabaseInsert { source };
+        let mut tx = self.writer.begin().await.map_err(insert)?;
+        let incident = sqlx::query_as::<_, StatusIncident>(&sql(
+            "update status_incidents set status = ?, resolved_at = case when ? then coalesce(resolved_at, ?) end where id = ? and site_id in (select id from sites where user_id = ?) returning *",
+        ))
+        .bind(status)
+        .bind(status == IncidentStatus::Resolved)
+        .bind(now)
+        .bind(status_incident_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|source| AppError::DatabaseSelect { source })?;
+        let update = sqlx::query_as::<_, IncidentUpdate>(&sql(
+            "insert into incident_updates (status_incident_id, site_id, status, message, created_at) values (?, ?, ?, ?, ?) returning *",
+        ))
+        .bind(incident.id)
+        .bind(incident.site_id)
+        .bind(status)
+        .bind(message.trim())
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(insert)?;
+        tx.commit().await.map_err(insert)?;
+        Ok(IncidentTimeline {
+            incident,
+            updates: vec![update],
+        })
+    }
+
+    /// The site's latest published incidents with their updates, newest
+    /// first.
+    pub async fn incident_timelines(
+        &self,
+        site_id: i64,
+        limit: i64,
+    ) -> Result<Vec<IncidentTimeline>, sqlx::Error> {
+        let incidents = sqlx::query_as::<_, StatusIncident>(&sql(
+            "select * from status_incidents where site_id = ? order by id desc limit ?",
+        ))
+        .bind(site_id)
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await?;
+        let oldest = incidents.last().map(|incident| incident.id).unwrap_or(0);
+        let updates = sqlx::query_as::<_, IncidentUpdate>(&sql(
+            "select * from incident_updates where site_id = ? and status_incident_id >= ? order by id desc",
+        ))
+        .bind(site_id)
+        .bind(oldest)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(incidents
+            .into_iter()
+            .map(|incident| IncidentTimeline {
+                updates: updates
+                    .iter()
+                    .filter(|update| update.status_incident_id == incident.id)
+                    .cloned()
+                    .collect(),
+                incident,
+            })
+            .collect())
+    }
+
+    /// The site's latest published incident that isn't resolved.
+    pub async fn open_status_incident(
+        &self,
+        site_id: i64,
+    ) -> Result<Option<StatusIncident>, sqlx::Error> {
+        sqlx::query_as::<_, StatusIncident>(&sql(
+            "select * from status_incidents where site_id = ? and resolved_at is null order by id desc limit 1",
+        ))
+        .bind(site_id)
+        .fetch_optional(&self.reader)
+        .await
+    }
+
+    /// Deletes the published incident with its updates.
+    pub async fn delete_status_incident(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<StatusIncident, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        let incident = sqlx::query_as::<_, StatusIncident>(&sql(
+            "select * from status_incidents where id = ? and site_id in (select id from sites where user_id = ?)",
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&sql(
+            "delete from incident_updates where status_incident_id = ?",
+        ))
+        .bind(incident.id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&sql("delete from status_incidents where id = ?"))
+            .bind(incident.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(incident)
+    }
+
+    /// Loads the user, their login count, their sites, each site's latest
+    /// response and 30 day uptime in a single query.
+    pub async fn dashboard(&self, user_id: i64) -> Result<Dashboard, sqlx::Error> {
+        const WINDOW: i64 = 30 * 24 * 60 * 60;
+        let now = Self::now();
+        let rows = sqlx::query_as::<_, DashboardRow>(&sql(r#"
+            select
+                users.id as user_id,
+                users.login_code,
+                users.created_at as user_created_at,
+                users.updated_at as user_updated_at,
+                users.digest as user_digest,
+                users.digest_sent_at as user_digest_sent_at,
+                users.timezone as user_timezone,
+                users.quiet_start as user_quiet_start,
+                users.quiet_end as user_quiet_end,
+                users.email as user_email,
+                users.email_verified_at as user_email_verified_at,
+                users.is_admin as user_is_admin,
+                users.max_sites as user_max_sites,
+                users.name as user_name,
+                users.default_interval_secs as user_default_interval_secs,
+                users.locale as user_locale,
+                (select count(id) from logins where logins.user_id = users.id) as login_count,
+                sites.id as site_id,
+                sites.url as site_url,
+                sites.name as site_name,
+                sites.created_at as site_created_at,
+                sites.updated_at as site_updated_at,
+                sites.critical as site_critical,
+                sites.maintenance_until as site_maintenance_until,
+                sites.interval_secs as site_interval_secs,
+                sites.failure_threshold as site_failure_threshold,
+                sites.disabled_at as site_disabled_at,
+                sites.paused_at as site_paused_at,
+                latest.id as response_id,
+                latest.status_code,
+                latest.created_at as response_created_at,
+                latest.updated_at as response_updated_at,
+                (
+                    select cast(sum(
+                        case when resolved_at < ?1 then resolved_at else ?1 end
+                        - case when started_at > ?1 - ?2 then started_at else ?1 - ?2 end
+                    ) as bigint)
+                    from incidents
+                    where incidents.site_id = sites.id and coalesce(resolved_at, ?1) > ?1 - ?2
+                ) as downtime,
+                open_incident.id as incident_id,
+                open_incident.status_code as incident_status_code,
+                open_incident.last_status_code as incident_last_status_code,
+                open_incident.started_at as incident_started_at,
+                open_incident.acknowledged_at as incident_acknowledged_at,
+                open_incident.acknowledged_by as incident_acknowledged_by,
+                open_incident.flapping as incident_flapping
+            from users
+            left join sites on sites.user_id = users.id
+            left join responses latest on latest.id = (
+                select id from responses where site_id = sites.id order by updated_at desc limit 1
+            )
+            left join incidents open_incident on open_incident.id = (
+                select id from incidents
+                where site_id = sites.id and resolved_at is null
+                order by started_at desc limit 1
+            )
+            where users.id = ?3
+            order by sites.created_at desc
+            "#))
+        .bind(now)
+        .bind(WINDOW)
+        .bind(user_id)
+        .fetch_all(&self.reader)
+        .await?;
+        let Some(first) = rows.first() else {
+            return Err(sqlx::Error::RowNotFound);
+        };
+        let mut dashboard = Dashboard {
+            user: User {
+                id: first.user_id,
+                login_code: first.login_code.clone(),
+                created_at: first.user_created_at,
+                updated_at: first.user_updated_at,
+                digest: first.user_digest,
+                digest_sent_at: first.user_digest_sent_at,
+                timezone: first.user_timezone.clone(),
+                quiet_start: first.user_quiet_start,
+                quiet_end: first.user_quiet_end,
+                email: first.user_email.clone(),
+                email_verified_at: first.user_email_verified_at,
+                is_admin: first.user_is_admin,
+                max_sites: first.user_max_sites,
+                name: first.user_name.clone(),
+                default_interval_secs: first.user_default_interval_secs,
+                locale: first.user_locale,
+            },
+            login_count: first.login_count as i32,
+            sites: vec![],
+        };
+        for row in rows {
+            let Some(site_id) = row.site_id else {
+                continue;
+            };
+            let site = Site {
+                id: site_id,
+                user_id: row.user_id,
+                url: row.site_url.unwrap_or_default(),
+                name: row.site_name,
+                created_at: row.site_created_at.unwrap_or_default(),
+                updated_at: row.site_updated_at.unwrap_or_default(),
+                critical: row.site_critical.unwrap_or_default(),
+                maintenance_until: row.site_maintenance_until,
+                interval_secs: row.site_interval_secs.unwrap_or_default(),
+                failure_threshold: row.site_failure_threshold.unwrap_or_default(),
+                disabled_at: row.site_disabled_at,
+                paused_at: row.site_paused_at,
+            };
+            let latest_response = row.response_id.map(|id| Response {
+                id,
+                site_id,
+                status_code: row.status_code.unwrap_or_default(),
+                created_at: row.response_created_at.unwrap_or_default(),
+                updated_at: row.response_updated_at.unwrap_or_default(),
+            });
+            let uptime = latest_response.as_ref().map(|_| {
+                let downtime = row.downtime.unwrap_or_default().min(WINDOW);
+                100.0 * (WINDOW - downtime) as f64 / WINDOW as f64
+            });
+            let open_incident = row.incident_id.map(|id| Incident {
+                id,
+                site_id,
+                status_code: row.incident_status_code.unwrap_or_default(),
+                started_at: row.incident_started_at.unwrap_or_default(),
+                resolved_at: None,
+                last_status_code: row.incident_last_status_code,
+                acknowledged_at: row.incident_acknowledged_at,
+                acknowledged_by: row.incident_acknowledged_by,
+                flapping: row.incident_flapping.unwrap_or_default(),
+            });
+            dashboard.sites.push(DashboardSite {
+                site,
+                latest_response,
+                uptime,
+                open_incident,
+                tags: vec![],
+                latency_ms: None,
+            });
+        }
+        let site_tags = self.site_tags_by_user_id(user_id).await?;
+        for site in dashboard.sites.iter_mut() {
+            site.tags = site_tags
+                .iter()
+                .filter(|(site_id, _)| *site_id == site.site.id)
+                .map(|(_, name)| name.clone())
+                .collect();
+        }
+        Ok(dashboard)
+    }
+
+    pub async fn tags_by_user_id(&self, user_id: i64) -> Result<Vec<Tag>, sqlx::Error> {
+        sqlx::query_as::<_, Tag>(&sql("select * from tags where user_id = ? order by name"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    /// Every (site id, tag name) pair for the user's sites, sorted by name.
+    pub async fn site_tags_by_user_id(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            &sql("select site_tags.site_id, tags.name from site_tags join tags on tags.id = site_tags.tag_id where tags.user_id = ? order by tags.name"),
+        )
+        .bind(user_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// The ids of the first `limit` of the user's sites that match the
+    /// query, in its order.
+    pub async fn matching_site_ids(
+        &self,
+        user_id: i64,
+        query: &SiteQuery,
+        limit: i64,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let statement = format!(
+            r#"
+            select sites.id
+            from sites
+            left join incidents open_incident on open_incident.id = (
+                select id from incidents
+                where site_id = sites.id and resolved_at is null
+                order by started_at desc limit 1
+            )
+            where sites.user_id = ?1
+                and (lower(sites.url) like ?2 escape '\' or lower(coalesce(sites.name, '')) like ?2 escape '\')
+                and (not ?3 or open_incident.id is not null)
+                and (?4 is null or sites.id in (
+                    select site_tags.site_id from site_tags
+                    join tags on tags.id = site_tags.tag_id
+                    where tags.user_id = ?1 and tags.name = ?4
+                ))
+            order by {}
+            limit ?5
+            "#,
+            query.sort.order_by()
+        );
+        sqlx::query_scalar::<_, i64>(&sql(&statement))
+            .bind(user_id)
+            .bind(query.pattern())
+            .bind(query.down_only)
+            .bind(query.tag.as_deref())
+            .bind(limit)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    /// Tags the site, creating the tag the first time its name is used.
+    pub async fn tag_site(&self, user_id: i64, site_id: i64, name: &str) -> Result<Tag, AppError> {
+        let name = Tag::normalize_name(name)?;
+        let tag: Result<Tag, sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            let tag = Self::upsert_tag(&mut tx, user_id, &name).await?;
+            sqlx::query(
+                &sql("insert into site_tags (site_id, tag_id) select id, ? from sites where id = ? and user_id = ? on conflict do nothing"),
+            )
+            .bind(tag.id)
+            .bind(site_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(tag)
+        }
+        .await;
+        let tag = tag.map_err(|source| AppError::DatabaseInsert { source })?;
+        Ok(tag)
+    }
+
+    pub async fn untag_site(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        tag_id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(
+            &sql("delete from site_tags where site_id = ? and tag_id = (select id from tags where id = ? and user_id = ?)"),
+        )
+        .bind(site_id)
+        .bind(tag_id)
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Replaces all of the site's tags with `names`.
+    pub async fn set_site_tags(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        names: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let mut names = names
+            .iter()
+            .map(|name| Tag::normalize_name(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        names.sort();
+        names.dedup();
+        let result: Result<(), sqlx::Error> = async {
+            let mut tx = self.writer.begin().await?;
+            sqlx::query(
+                &sql("delete from site_tags where site_id = (select id from sites where id = ? and user_id = ?)"),
+            )
+            .bind(site_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+            for name in &names {
+                let tag = Self::upsert_tag(&mut tx, user_id, name).await?;
+                sqlx::query(
+                    &sql("insert into site_tags (site_id, tag_id) select id, ? from sites where id = ? and user_id = ?"),
+                )
+                .bind(tag.id)
+                .bind(site_id)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await
+        }
+        .await;
+        result.map_err(|source| AppError::DatabaseInsert { source })?;
+        Ok(names)
+    }
+
+    async fn upsert_tag(
+        tx: &mut sqlx::Transaction<'_, Backend>,
+        user_id: i64,
+        name: &str,
+    ) -> Result<Tag, sqlx::Error> {
+        sqlx::query_as::<_, Tag>(
+            &sql("insert into tags (user_id, name, created_at) values (?, ?, ?) on conflict (user_id, name) do update set name = excluded.name returning *"),
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(Self::now())
+        .fetch_one(&mut **tx)
+        .await
+    }
+
+    /// The user's sites with the tag, newest first.
+    pub async fn sites_by_tag(&self, user_id: i64, name: &str) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(
+            &sql("select sites.* from sites join site_tags on site_tags.site_id = sites.id join tags on tags.id = site_tags.tag_id where sites.user_id = ? and tags.name = ? order by sites.id desc"),
+        )
+        .bind(user_id)
+        .bind(name.trim().to_lowercase())
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Marks a verified token as used. Fails with `TokenUsed` the second
+    /// time the same token comes in. Expired nonces are cleaned up on the way.
+    pub async fn consume_token(&self, claims: &token::Claims) -> Result<(), AppError> {
+        let now = Self::now();
+        _ = sqlx::query(&sql("delete from used_tokens where expires_at < ?"))
+            .bind(now)
+            .execute(&self.writer)
+            .await;
+        sqlx::query(&sql(
+            "insert into used_tokens (nonce, expires_at, created_at) values (?, ?, ?)",
+        ))
+        .bind(&claims.nonce)
+        .bind(claims.expires_at)
+        .bind(now)
+        .execute(&self.writer)
+        .await
+        .map(|_| ())
+        .map_err(|_| AppError::TokenUsed)
+    }
+
+    pub async fn route_channel(
+        &self,
+        user_id: i64,
+        channel_id: i64,
+        site_id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(
+            &sql("insert into notification_routes (channel_id, site_id) select notification_channels.id, sites.id from notification_channels, sites where notification_channels.id = ? and sites.id = ? and notification_channels.user_id = ? and sites.user_id = ? on conflict do nothing"),
+        )
+        .bind(channel_id)
+        .bind(site_id)
+        .bind(user_id)
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn unroute_channel(
+        &self,
+        user_id: i64,
+        channel_id: i64,
+        site_id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(
+            &sql("delete from notification_routes where channel_id = (select id from notification_channels where id = ? and user_id = ?) and site_id = ?"),
+        )
+        .bind(channel_id)
+        .bind(user_id)
+        .bind(site_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn routed_site_ids(&self, channel_id: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(&sql(
+            "select site_id from notification_routes where channel_id = ?",
+        ))
+        .bind(channel_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn insert_check(&self, check: &checker::Check) -> Result<models::Check, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, models::Check>(
+            &sql("insert into checks (site_id, status_code, latency_ms, failure, ip, maintenance, created_at) values (?, ?, ?, ?, ?, ?, ?) returning *"),
+        )
+        .bind(check.site_id)
+        .bind(check.status_code)
+        .bind(check.latency_ms)
+        .bind(check.failure)
+        .bind(check.ip.map(|ip| ip.to_string()))
+        .bind(check.maintenance)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Checks and incidents from another time, like the demo's made up
+    /// history, in one transaction.
+    pub async fn insert_history(
+        &self,
+        checks: &[(checker::Check, i64)],
+        incidents: &[Incident],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        for (check, created_at) in checks {
+            sqlx::query(
+                &sql("insert into checks (site_id, status_code, latency_ms, failure, ip, maintenance, created_at) values (?, ?, ?, ?, ?, ?, ?)"),
+            )
+            .bind(check.site_id)
+            .bind(check.status_code)
+            .bind(check.latency_ms)
+            .bind(check.failure)
+            .bind(check.ip.map(|ip| ip.to_string()))
+            .bind(check.maintenance)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for incident in incidents {
+            sqlx::query(
+                &sql("insert into incidents (site_id, status_code, last_status_code, started_at, resolved_at, flapping) values (?, ?, ?, ?, ?, ?)"),
+            )
+            .bind(incident.site_id)
+            .bind(incident.status_code)
+            .bind(incident.last_status_code)
+            .bind(incident.started_at)
+            .bind(incident.resolved_at)
+            .bind(incident.flapping)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    pub async fn insert_watch_cycle(&self, cycle: WatchCycle) -> Result<WatchCycle, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, WatchCycle>(
+            &sql("insert into watch_cycles (sites_checked, failures, new_incidents, duration_ms, lag_ms, created_at) values (?, ?, ?, ?, ?, ?) returning *"),
+        )
+        .bind(cycle.sites_checked)
+        .bind(cycle.failures)
+        .bind(cycle.new_incidents)
+        .bind(cycle.duration_ms)
+        .bind(cycle.lag_ms)
+        .bind(now)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn watch_cycles(&self, limit: i64) -> Result<Vec<WatchCycle>, sqlx::Error> {
+        sqlx::query_as::<_, WatchCycle>(&sql("select * from watch_cycles order by id desc limit ?"))
+            .bind(limit)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn site_by_id(&self, id: i64) -> Result<Site, sqlx::Error> {
+        sqlx::query_as::<_, Site>(&sql("select * from sites where id = ?"))
+            .bind(id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    pub async fn open_incidents(&self) -> Result<Vec<Incident>, sqlx::Error> {
+        sqlx::query_as::<_, Incident>(&sql("select * from incidents where resolved_at is null"))
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn insert_notification_sent(
+        &self,
+        channel_id: i64,
+        site_id: i64,
+        incident_id: Option<i64>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query(
+            &sql("insert into notifications_sent (channel_id, site_id, incident_id, created_at) values (?, ?, ?, ?)"),
+        )
+        .bind(channel_id)
+        .bind(site_id)
+        .bind(incident_id)
+        .bind(now)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Sites the channel has been notified about since `since`.
+    pub async fn notified_site_ids(
+        &self,
+        channel_id: i64,
+        since: i64,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            &sql("select distinct site_id from notifications_sent where channel_id = ? and created_at > ?"),
+        )
+        .bind(channel_id)
+        .bind(since)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn last_notified_at(
+        &self,
+        channel_id: i64,
+        site_id: i64,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, Option<i64>>(&sql(
+            "select max(created_at) from notifications_sent where channel_id = ? and site_id = ?",
+        ))
+        .bind(channel_id)
+        .bind(site_id)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    pub async fn insert_latency_rule(&self, rule: LatencyRule) -> Result<LatencyRule, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, LatencyRule>(
+            &sql("insert into latency_rules (user_id, site_id, percentile, window_secs, threshold_ms, created_at) select user_id, id, ?, ?, ?, ? from sites where id = ? and user_id = ? returning *"),
+        )
+        .bind(rule.percentile)
+        .bind(rule.window_secs)
+        .bind(rule.threshold_ms)
+        .bind(now)
+        .bind(rule.site_id)
+        .bind(rule.user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn latency_rules_by_user_id(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<LatencyRule>, sqlx::Error> {
+        sqlx::query_as::<_, LatencyRule>(&sql("select * from latency_rules where user_id = ?"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn latency_rules(&self) -> Result<Vec<LatencyRule>, sqlx::Error> {
+        sqlx::query_as::<_, LatencyRule>(&sql("select * from latency_rules"))
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn delete_latency_rule(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "delete from latency_rules where user_id = ? and id = ?",
+        ))
+        .bind(user_id)
+        .bind(id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn set_latency_rule_triggered(
+        &self,
+        id: i64,
+        triggered: bool,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let triggered_at = triggered.then(Self::now);
+        sqlx::query(&sql(
+            "update latency_rules set triggered_at = ? where id = ?",
+        ))
+        .bind(triggered_at)
+        .bind(id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn insert_share_link(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        expires_at: Option<i64>,
+    ) -> Result<ShareLink, sqlx::Error> {
+        sqlx::query_as::<_, ShareLink>(&sql(
+            "insert into share_links (site_id, user_id, expires_at, created_at) select id, user_id, ?, ? from sites where id = ? and user_id = ? returning *",
+        ))
+        .bind(expires_at)
+        .bind(Self::now())
+        .bind(site_id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn share_links_by_site_id(
+        &self,
+        user_id: i64,
+        site_id: i64,
+    ) -> Result<Vec<ShareLink>, sqlx::Error> {
+        sqlx::query_as::<_, ShareLink>(&sql(
+            "select * from share_links where user_id = ? and site_id = ? order by id desc",
+        ))
+        .bind(user_id)
+        .bind(site_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn share_link_by_id(&self, id: i64) -> Result<ShareLink, sqlx::Error> {
+        sqlx::query_as::<_, ShareLink>(&sql("select * from share_links where id = ?"))
+            .bind(id)
+            .fetch_one(&self.reader)
+            .await
+    }
+
+    /// Fails with `DuplicateDomain` when another share link has it.
+    pub async fn update_share_link_domain(
+        &self,
+        user_id: i64,
+        id: i64,
+        domain: Option<&str>,
+    ) -> Result<ShareLink, AppError> {
+        sqlx::query_as::<_, ShareLink>(&sql(
+            "update share_links set domain = ? where id = ? and user_id = ? returning *",
+        ))
+        .bind(domain)
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+        .map_err(|source| match &source {
+            sqlx::Error::Database(err) if err.code().as_deref() == Some(Self::UNIQUE_VIOLATION) => {
+                AppError::DuplicateDomain
+            }
+            sqlx::Error::RowNotFound => AppError::DatabaseSelect { source },
+            _ => AppError::DatabaseInsert { source },
+        })
+    }
+
+    /// The share link with this domain, unless it expired.
+    pub async fn share_link_by_domain(
+        &self,
+        domain: &str,
+        now: i64,
+    ) -> Result<ShareLink, sqlx::Error> {
+        sqlx::query_as::<_, ShareLink>(&sql(
+            "select * from share_links where domain = ? and (expires_at is null or expires_at > ?)",
+        ))
+        .bind(domain)
+        .bind(now)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    /// Every custom domain, for the certificates issued at startup.
+    pub async fn share_link_domains(&self) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>(&sql(
+            "select domain from share_links where domain is not null order by id",
+        ))
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Deletes the share link with its subscribers.
+    pub async fn delete_share_link(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        sqlx::query(&sql(
+            "delete from subscribers where share_link_id in (select id from share_links where id = ? and user_id = ?)",
+        ))
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query(&sql("delete from share_links where id = ? and user_id = ?"))
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Adds the email to the share link's subscribers, or returns the row
+    /// that's there so a lost confirmation can be sent again.
+    pub async fn insert_subscriber(
+        &self,
+        share_link: &ShareLink,
+        email: &str,
+    ) -> Result<Subscriber, sqlx::Error> {
+        sqlx::query(&sql(
+            "insert into subscribers (share_link_id, site_id, email, created_at) values (?, ?, ?, ?) on conflict (share_link_id, email) do nothing",
+        ))
+        .bind(share_link.id)
+        .bind(share_link.site_id)
+        .bind(email)
+        .bind(Self::now())
+        .execute(&self.writer)
+        .await?;
+        sqlx::query_as::<_, Subscriber>(&sql(
+            "select * from subscribers where share_link_id = ? and email = ?",
+        ))
+        .bind(share_link.id)
+        .bind(email)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn confirm_subscriber(&self, id: i64) -> Result<Subscriber, sqlx::Error> {
+        sqlx::query_as::<_, Subscriber>(&sql(
+            "update subscribers set confirmed_at = coalesce(confirmed_at, ?) where id = ? returning *",
+        ))
+        .bind(Self::now())
+        .bind(id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn delete_subscriber(&self, id: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("delete from subscribers where id = ?"))
+            .bind(id)
+            .execute(&self.writer)
+            .await
+    }
+
+    /// The confirmed subscribers of the site's share links that still
+    /// work, one per email.
+    pub async fn confirmed_subscribers(
+        &self,
+        site_id: i64,
+    ) -> Result<Vec<Subscriber>, sqlx::Error> {
+        sqlx::query_as::<_, Subscriber>(&sql(r#"
+            select * from subscribers where id in (
+                select min(subscribers.id) from subscribers
+                join share_links on share_links.id = subscribers.share_link_id
+                where subscribers.site_id = ?
+                    and subscribers.confirmed_at is not null
+                    and (share_links.expires_at is null or share_links.expires_at > ?)
+                group by subscribers.email
+            )
+            order by id
+            "#))
+        .bind(site_id)
+        .bind(Self::now())
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn instance_stats(&self) -> Result<InstanceStats, sqlx::Error> {
+        sqlx::query_as::<_, InstanceStats>(&sql(
+            r#"
+            select
+                (select count(*) from users where id not in (select user_id from organizations)) as users,
+                (select count(*) from sites) as sites,
+                (select count(*) from sites where disabled_at is not null) as disabled_sites,
+                (select count(*) from checks where created_at > ?) as checks_last_hour,
+                (select count(distinct site_id) from incidents where resolved_at is null) as failing_sites,
+                (select count(*) from queued_notifications) as queue_depth
+            "#,
+        ))
+        .bind(Self::now() - 60 * 60)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    /// Every user's sites whose url contains `search`, newest first, for
+    /// the admin dashboard.
+    pub async fn search_sites(&self, search: &str, limit: i64) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(&sql(
+            "select * from sites where lower(url) like lower(?) order by id desc limit ?",
+        ))
+        .bind(format!("%{}%", search))
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Stops or resumes checking a site, regardless of who owns it.
+    pub async fn set_site_disabled(
+        &self,
+        site_id: i64,
+        disabled: bool,
+    ) -> Result<Site, sqlx::Error> {
+        sqlx::query_as::<_, Site>(&sql(
+            "update sites set disabled_at = ?, updated_at = ? where id = ? returning *",
+        ))
+        .bind(disabled.then(Self::now))
+        .bind(Timestamp::now())
+        .bind(site_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn make_admin(&self, login_code: &str) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(&sql(
+            "update users set is_admin = ?, updated_at = ? where login_code = ? returning *",
+        ))
+        .bind(true)
+        .bind(Timestamp::now())
+        .bind(login_code)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Sets the user's site limit, `None` goes back to the instance's.
+    pub async fn set_max_sites(
+        &self,
+        login_code: &str,
+        max_sites: Option<i64>,
+    ) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(&sql(
+            "update users set max_sites = ?, updated_at = ? where login_code = ? returning *",
+        ))
+        .bind(max_sites)
+        .bind(Timestamp::now())
+        .bind(login_code)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// The site's most recent checks, newest first.
+    pub async fn recent_checks(
+        &self,
+        site_id: i64,
+        limit: i64,
+    ) -> Result<Vec<models::Check>, sqlx::Error> {
+        sqlx::query_as::<_, models::Check>(&sql(
+            "select * from checks where site_id = ? order by id desc limit ?",
+        ))
+        .bind(site_id)
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Latencies of the checks that got a response since `since`.
+    pub async fn latencies(&self, site_id: i64, since: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            &sql("select latency_ms from checks where site_id = ? and created_at > ? and status_code > 0 and not maintenance"),
+        )
+        .bind(site_id)
+        .bind(since)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Sites whose expiry of this kind was never looked up or is stale.
+    pub async fn sites_due_expiry_lookup(
+        &self,
+        kind: ExpiryKind,
+        before: i64,
+    ) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(
+            &sql("select sites.* from sites left join expiries on expiries.site_id = sites.id and expiries.kind = ? where expiries.checked_at is null or expiries.checked_at < ?"),
+        )
+        .bind(kind)
+        .bind(before)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Stores the lookup, clearing `notified_at` when the date moved so a
+    /// renewed certificate or domain alerts again before its next expiry.
+    pub async fn upsert_expiry(
+        &self,
+        site_id: i64,
+        kind: ExpiryKind,
+        expires_at: Option<i64>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query(
+            &sql("insert into expiries (site_id, kind, expires_at, checked_at) values (?, ?, ?, ?) on conflict (site_id, kind) do update set notified_at = case when expiries.expires_at is not distinct from excluded.expires_at then expiries.notified_at else null end, expires_at = excluded.expires_at, checked_at = excluded.checked_at"),
+        )
+        .bind(site_id)
+        .bind(kind)
+        .bind(expires_at)
+        .bind(now)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Sites whose icon was never fetched or is stale.
+    pub async fn sites_due_icon_fetch(&self, before: i64) -> Result<Vec<Site>, sqlx::Error> {
+        sqlx::query_as::<_, Site>(
+            &sql("select sites.* from sites left join site_icons on site_icons.site_id = sites.id where site_icons.fetched_at is null or site_icons.fetched_at < ?"),
+        )
+        .bind(before)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn upsert_site_icon(
+        &self,
+        site_id: i64,
+        icon: Option<&favicon::Icon>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(
+            &sql("insert into site_icons (site_id, content_type, data, fetched_at) values (?, ?, ?, ?) on conflict (site_id) do update set content_type = excluded.content_type, data = excluded.data, fetched_at = excluded.fetched_at"),
+        )
+        .bind(site_id)
+        .bind(icon.map(|icon| icon.content_type.as_str()))
+        .bind(icon.map(|icon| icon.data.as_slice()))
+        .bind(Self::now())
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn site_icon(&self, site_id: i64) -> Result<Option<SiteIcon>, sqlx::Error> {
+        sqlx::query_as::<_, SiteIcon>(&sql("select * from site_icons where site_id = ?"))
+            .bind(site_id)
+            .fetch_optional(&self.reader)
+            .await
+    }
+
+    pub async fn expiries(&self) -> Result<Vec<Expiry>, sqlx::Error> {
+        sqlx::query_as::<_, Expiry>(
+            &sql("select expiries.*, expiry_rules.days from expiries left join expiry_rules on expiry_rules.site_id = expiries.site_id and expiry_rules.kind = expiries.kind where expiries.expires_at is not null"),
+        )
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn set_expiry_notified(
+        &self,
+        site_id: i64,
+        kind: ExpiryKind,
+    ) -> Result<QueryResult, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query(&sql(
+            "update expiries set notified_at = ? where site_id = ? and kind = ?",
+        ))
+        .bind(now)
+        .bind(site_id)
+        .bind(kind)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn upsert_expiry_rule(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        kind: ExpiryKind,
+        days: i64,
+    ) -> Result<ExpiryRule, sqlx::Error> {
+        let now = Self::now();
+        sqlx::query_as::<_, ExpiryRule>(
+            &sql("insert into expiry_rules (user_id, site_id, kind, days, created_at) select user_id, id, ?, ?, ? from sites where id = ? and user_id = ? on conflict (site_id, kind) do update set days = excluded.days returning *"),
+        )
+        .bind(kind)
+        .bind(days)
+        .bind(now)
+        .bind(site_id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn expiry_rules_by_user_id(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<ExpiryRule>, sqlx::Error> {
+        sqlx::query_as::<_, ExpiryRule>(&sql("select * from expiry_rules where user_id = ?"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    pub async fn delete_expiry_rule(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "delete from expiry_rules where user_id = ? and id = ?",
+        ))
+        .bind(user_id)
+        .bind(id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn update_user_digest(
+        &self,
+        user_id: i64,
+        digest: Frequency,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set digest = ?, updated_at = ? where id = ?",
+        ))
+        .bind(digest)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Users with a digest turned on whose last one is a period old.
+    pub async fn users_due_digest(&self, now: i64) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            &sql("select * from users where (digest = 'daily' and coalesce(digest_sent_at, 0) < ?1 - 86400) or (digest = 'weekly' and coalesce(digest_sent_at, 0) < ?1 - 604800)"),
+        )
+        .bind(now)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn set_digest_sent(&self, user_id: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("update users set digest_sent_at = ? where id = ?"))
+            .bind(Self::now())
+            .bind(user_id)
+            .execute(&self.writer)
+            .await
+    }
+
+    pub async fn site_summaries(
+        &self,
+        user_id: i64,
+        since: i64,
+    ) -> Result<Vec<SiteSummary>, sqlx::Error> {
+        sqlx::query_as::<_, SiteSummary>(
+            &sql("select sites.id as site_id, sites.url, sites.name, count(checks.id) as checks, coalesce(sum(case when checks.status_code not between 200 and 299 then 1 else 0 end), 0) as failures, cast(avg(checks.latency_ms) as bigint) as latency_ms from sites left join checks on checks.site_id = sites.id and checks.created_at > ? where sites.user_id = ? group by sites.id"),
+        )
+        .bind(since)
+        .bind(user_id)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn incidents_since(
+        &self,
+        user_id: i64,
+        since: i64,
+    ) -> Result<Vec<Incident>, sqlx::Error> {
+        sqlx::query_as::<_, Incident>(
+            &sql("select incidents.* from incidents join sites on sites.id = incidents.site_id where sites.user_id = ? and incidents.started_at > ? order by incidents.started_at"),
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// The user's incidents that started between `from` and `to`, newest
+    /// first, for one site or all of them.
+    pub async fn past_incidents(
+        &self,
+        user_id: i64,
+        site_id: Option<i64>,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<PastIncident>, sqlx::Error> {
+        sqlx::query_as::<_, PastIncident>(&sql(r#"
+            select
+                incidents.id,
+                incidents.site_id,
+                incidents.status_code,
+                incidents.started_at,
+                incidents.resolved_at,
+                incidents.last_status_code,
+                incidents.flapping,
+                (
+                    select checks.failure
+                    from checks
+                    where checks.site_id = incidents.site_id
+                        and checks.created_at <= incidents.started_at
+                        and checks.failure is not null
+                    order by checks.created_at desc
+                    limit 1
+                ) as failure
+            from incidents
+            join sites on sites.id = incidents.site_id
+            where sites.user_id = ?1
+                and (?2 is null or incidents.site_id = ?2)
+                and incidents.started_at >= ?3
+                and incidents.started_at < ?4
+            order by incidents.started_at desc
+            limit ?5
+            "#))
+        .bind(user_id)
+        .bind(site_id)
+        .bind(from)
+        .bind(to)
+        .bind(Self::PAST_INCIDENTS)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn expiries_before(
+        &self,
+        user_id: i64,
+        kind: ExpiryKind,
+        before: i64,
+    ) -> Result<Vec<Expiry>, sqlx::Error> {
+        sqlx::query_as::<_, Expiry>(
+            &sql("select expiries.*, expiry_rules.days from expiries join sites on sites.id = expiries.site_id left join expiry_rules on expiry_rules.site_id = expiries.site_id and expiry_rules.kind = expiries.kind where sites.user_id = ? and expiries.kind = ? and expiries.expires_at < ? order by expiries.expires_at"),
+        )
+        .bind(user_id)
+        .bind(kind)
+        .bind(before)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn update_quiet_hours(
+        &self,
+        user_id: i64,
+        timezone: &str,
+        quiet_start: Option<i64>,
+        quiet_end: Option<i64>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(
+            &sql("update users set timezone = ?, quiet_start = ?, quiet_end = ?, updated_at = ? where id = ?"),
+        )
+        .bind(timezone)
+        .bind(quiet_start)
+        .bind(quiet_end)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn update_user_name(
+        &self,
+        user_id: i64,
+        name: Option<&str>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set name = ?, updated_at = ? where id = ?",
+        ))
+        .bind(name)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Moves quiet hours and digests to another timezone, the hours
+    /// themselves stay as they are.
+    pub async fn update_user_timezone(
+        &self,
+        user_id: i64,
+        timezone: &str,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set timezone = ?, updated_at = ? where id = ?",
+        ))
+        .bind(timezone)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Sites added from now on are checked this often, existing ones keep
+    /// their interval.
+    pub async fn update_default_interval(
+        &self,
+        user_id: i64,
+        interval_secs: i64,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set default_interval_secs = ?, updated_at = ? where id = ?",
+        ))
+        .bind(interval_secs)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn update_user_locale(
+        &self,
+        user_id: i64,
+        locale: i18n::Locale,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update users set locale = ?, updated_at = ? where id = ?",
+        ))
+        .bind(locale)
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    /// Swaps the login code for a new one, the old one stops working right
+    /// away. Sessions that are already logged in stay logged in.
+    pub async fn rotate_login_code(&self, user_id: i64) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(&sql(
+            "update users set login_code = ?, updated_at = ? where id = ? returning *",
+        ))
+        .bind(nanoid!())
+        .bind(Timestamp::now())
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    pub async fn set_site_critical(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        critical: bool,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update sites set critical = ?, updated_at = ? where id = ? and user_id = ?",
+        ))
+        .bind(critical)
+        .bind(Timestamp::now())
+        .bind(site_id)
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn critical_site_ids(&self, user_id: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(&sql("select id from sites where user_id = ? and critical"))
+            .bind(user_id)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    /// Holds a notification for a channel until the user's quiet hours end.
+    pub async fn queue_notification(
+        &self,
+        channel: &NotificationChannel,
+        notification: &str,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(
+            &sql("insert into queued_notifications (channel_id, user_id, notification, created_at) values (?, ?, ?, ?)"),
+        )
+        .bind(channel.id)
+        .bind(channel.user_id)
+        .bind(notification)
+        .bind(Self::now())
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn queued_notifications(&self) -> Result<Vec<QueuedNotification>, sqlx::Error> {
+        sqlx::query_as::<_, QueuedNotification>(&sql(
+            "select * from queued_notifications order by created_at",
+        ))
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn delete_queued_notification(&self, id: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql("delete from queued_notifications where id = ?"))
+            .bind(id)
+            .execute(&self.writer)
+            .await
+    }
+
+    /// The windows uptime is usually shown for, 24 hours to 90 days.
+    pub const UPTIME_WINDOWS: [i64; 4] = [86_400, 7 * 86_400, 30 * 86_400, 90 * 86_400];
+
+    /// Counts the site's up, down and maintenance checks over the last
+    /// `window_secs` in sql, reaching into the hourly rollups for checks
+    /// that were already rolled up.
+    pub async fn uptime_by_site(
+        &self,
+        site_id: i64,
+        window_secs: i64,
+    ) -> Result<Uptime, sqlx::Error> {
+        sqlx::query_as::<_, Uptime>(
+            &sql(r#"
+            select
+                ?1 as window_secs,
+                cast(coalesce(sum(up), 0) as bigint) as up,
+                cast(coalesce(sum(down), 0) as bigint) as down,
+                cast(coalesce(sum(maintenance), 0) as bigint) as maintenance
+            from (
+                select
+                    sum(case when not maintenance and status_code between 200 and 299 then 1 else 0 end) as up,
+                    sum(case when not maintenance and status_code not between 200 and 299 then 1 else 0 end) as down,
+                    sum(case when maintenance then 1 else 0 end) as maintenance
+                from checks
+                where site_id = ?2 and created_at > ?3
+                union all
+                select sum(up), sum(checks - up - maintenance), sum(maintenance)
+                from checks_hourly
+                where site_id = ?2 and hour > ?3
+            ) as counts
+            "#),
+        )
+        .bind(window_secs)
+        .bind(site_id)
+        .bind(Self::now() - window_secs)
+        .fetch_one(&self.reader)
+        .await
+    }
+
+    /// The site's checks from `from` up to `to`, oldest first. Pass the
+    /// last id back as `after` for the next batch.
+    pub async fn checks_between(
+        &self,
+        site_id: i64,
+        from: i64,
+        to: i64,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<models::Check>, sqlx::Error> {
+        sqlx::query_as::<_, models::Check>(&sql(
+            "select * from checks where site_id = ? and created_at >= ? and created_at < ? and id > ? order by id limit ?",
+        ))
+        .bind(site_id)
+        .bind(from)
+        .bind(to)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    /// Groups the site's checks from `from` up to `to` by hour or day,
+    /// taking older ones from the rollups since raw checks don't last.
+    pub async fn check_buckets(
+        &self,
+        site_id: i64,
+        from: i64,
+        to: i64,
+        resolution: Resolution,
+    ) -> Result<Vec<CheckBucket>, sqlx::Error> {
+        let secs = resolution.secs();
+        let (table, column) = resolution.rollup();
+        let query = format!(
+            r#"
+            select
+                bucket as at,
+                cast(sum(checks) as bigint) as checks,
+                cast(sum(up) as bigint) as up,
+                cast(sum(maintenance) as bigint) as maintenance,
+                cast(sum(latency_sum_ms) / sum(checks) as bigint) as latency_avg_ms,
+                cast(min(latency_min_ms) as bigint) as latency_min_ms,
+                cast(max(latency_max_ms) as bigint) as latency_max_ms
+            from (
+                select
+                    created_at / {secs} * {secs} as bucket,
+                    count(*) as checks,
+                    sum(case when not maintenance and status_code between 200 and 299 then 1 else 0 end) as up,
+                    sum(case when maintenance then 1 else 0 end) as maintenance,
+                    sum(latency_ms) as latency_sum_ms,
+                    min(latency_ms) as latency_min_ms,
+                    max(latency_ms) as latency_max_ms
+                from checks
+                where site_id = ?1 and created_at >= ?2 and created_at < ?3
+                group by created_at / {secs}
+                union all
+                select {column}, checks, up, maintenance, latency_sum_ms, latency_min_ms, latency_max_ms
+                from {table}
+                where site_id = ?1 and {column} >= ?2 / {secs} * {secs} and {column} < ?3
+            ) as buckets
+            group by bucket
+            order by bucket
+            "#
+        );
+        sqlx::query_as::<_, CheckBucket>(&sql(&query))
+            .bind(site_id)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.reader)
+            .await
+    }
+
+    /// Folds checks older than `before` into the hourly and daily rollups
+    /// and deletes them.
+    pub async fn roll_up_checks(&self, before: i64) -> Result<u64, sqlx::Error> {
+        let mut tx = self.writer.begin().await?;
+        for (table, column, secs) in [
+            ("checks_hourly", "hour", 3600),
+            ("checks_daily", "day", 86_400),
+        ] {
+            let rollup = format!(
+                r#"
+                insert into {table} (site_id, {column}, checks, up, maintenance, latency_sum_ms, latency_min_ms, latency_max_ms)
+                select
+                    site_id,
+                    created_at / {secs} * {secs},
+                    count(*),
+                    sum(case when not maintenance and status_code between 200 and 299 then 1 else 0 end),
+                    sum(case when maintenance then 1 else 0 end),
+                    sum(latency_ms),
+                    min(latency_ms),
+                    max(latency_ms)
+                from checks
+                where created_at < ?
+                group by site_id, created_at / {secs}
+                on conflict (site_id, {column}) do update set
+                    checks = {table}.checks + excluded.checks,
+                    up = {table}.up + excluded.up,
+                    maintenance = {table}.maintenance + excluded.maintenance,
+                    latency_sum_ms = {table}.latency_sum_ms + excluded.latency_sum_ms,
+                    latency_min_ms = case when {table}.latency_min_ms < excluded.latency_min_ms
+                        then {table}.latency_min_ms else excluded.latency_min_ms end,
+                    latency_max_ms = case when {table}.latency_max_ms > excluded.latency_max_ms
+                        then {table}.latency_max_ms else excluded.latency_max_ms end
+                "#
+            );
+            sqlx::query(&sql(&rollup))
+                .bind(before)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let deleted = sqlx::query(&sql("delete from checks where created_at < ?"))
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Fails with `MaintenanceWindow` when the title is empty or too long
+    /// or the window ends before it starts.
+    pub async fn insert_maintenance_window(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        title: &str,
+        starts_at: i64,
+        ends_at: i64,
+    ) -> Result<MaintenanceWindow, AppError> {
+        let title = title.trim();
+        if title.is_empty()
+            || title.len() > MaintenanceWindow::MAX_TITLE_LEN
+            || ends_at <= starts_at
+        {
+            return Err(AppError::MaintenanceWindow);
+        }
+        sqlx::query_as::<_, MaintenanceWindow>(&sql(
+            "insert into maintenance_windows (site_id, title, starts_at, ends_at, created_at) select id, ?, ?, ?, ? from sites where id = ? and user_id = ? returning *",
+        ))
+        .bind(title)
+        .bind(starts_at)
+        .bind(ends_at)
+        .bind(Self::now())
+        .bind(site_id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+        .map_err(|source| AppError::DatabaseSelect { source })
+    }
+
+    /// The site's windows that end after `since`, soonest first.
+    pub async fn maintenance_windows(
+        &self,
+        site_id: i64,
+        since: i64,
+    ) -> Result<Vec<MaintenanceWindow>, sqlx::Error> {
+        sqlx::query_as::<_, MaintenanceWindow>(&sql(
+            "select * from maintenance_windows where site_id = ? and ends_at > ? order by starts_at",
+        ))
+        .bind(site_id)
+        .bind(since)
+        .fetch_all(&self.reader)
+        .await
+    }
+
+    pub async fn delete_maintenance_window(
+        &self,
+        user_id: i64,
+        id: i64,
+    ) -> Result<MaintenanceWindow, sqlx::Error> {
+        sqlx::query_as::<_, MaintenanceWindow>(&sql(
+            "delete from maintenance_windows where id = ? and site_id in (select id from sites where user_id = ?) returning *",
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&self.writer)
+        .await
+    }
+
+    /// Puts sites in maintenance for the windows going on at `now`, a
+    /// longer `maintenance_until` set by hand is kept.
+    pub async fn start_maintenance_windows(&self, now: i64) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(r#"
+            update sites set maintenance_until = (
+                select max(ends_at) from maintenance_windows
+                where site_id = sites.id and starts_at <= ?1 and ends_at > ?1
+            )
+            where id in (
+                select site_id from maintenance_windows where starts_at <= ?1 and ends_at > ?1
+            )
+            and coalesce(maintenance_until, 0) < (
+                select max(ends_at) from maintenance_windows
+                where site_id = sites.id and starts_at <= ?1 and ends_at > ?1
+            )
+            "#))
+        .bind(now)
+        .execute(&self.writer)
+        .await
+    }
+
+    pub async fn set_site_maintenance(
+        &self,
+        user_id: i64,
+        site_id: i64,
+        until: Option<i64>,
+    ) -> Result<QueryResult, sqlx::Error> {
+        sqlx::query(&sql(
+            "update sites set maintenance_until = ?, updated_at = ? where id = ? and user_id = ?",
+        ))
+        .bind(until)
+        .bind(Timestamp::now())
+        .bind(site_id)
+        .bind(user_id)
+        .execute(&self.writer)
+        .await
+    }
+}
+
+#[cfg(all(test, not(feature = "postgres")))]
+mod tests {
+    use super::*;
+
+    /// A migrated database in a file of its own, the reader and writer
+    /// pools can't share an in-memory one.
+    async fn database() -> Database {
+        let path = std::env::temp_dir().join(format!("updown-{}.sqlite3", nanoid::nanoid!()));
+        let db = Database::new(format!("sqlite://{}", path.display())).await;
+        db.migrate().await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn logging_in_keeps_the_ip_lockout() {
+        let db = database().await;
+        let ip_address = Some("203.0.113.7");
+        for _ in 0..LoginFailures::FREE_ATTEMPTS {
+            db.insert_login_attempt(ip_address, "someone-elses-code")
+                .await
+                .unwrap();
+        }
+        db.clear_login_attempts("my-own-code").await.unwrap();
+        let now = Database::now();
+        let failures = db
+            .login_failures(
+                ip_address,
+                "someone-elses-code",
+                now - LoginFailures::WINDOW_SECS,
+            )
+            .await
+            .unwrap();
+        assert_eq!(failures.ip_failures, LoginFailures::FREE_ATTEMPTS);
+        assert!(failures.retry_after(now).is_some());
+    }
+
+    #[tokio::test]
+    async fn the_last_owner_cant_delete_their_account() {
+        let db = database().await;
+        let owner = db.insert_user().await.unwrap();
+        let organization = db.insert_organization(owner.id, "Acme").await.unwrap();
+        assert!(matches!(
+            db.delete_user(owner.id).await,
+            Err(AppError::LastOwner)
+        ));
+        assert!(db.user_by_id(owner.id).await.is_ok());
+
+        let other = db.insert_user().await.unwrap();
+        let invitation = db
+            .insert_invitation(organization.id, Role::Owner, owner.id)
+            .await
+            .unwrap();
+        db.accept_invitation(invitation.id, other.id).await.unwrap();
+        db.delete_user(owner.id).await.unwrap();
+        assert!(db.user_by_id(owner.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn deleting_an_organization_deletes_its_account() {
+        let db = database().await;
+        let owner = db.insert_user().await.unwrap();
+        let organization = db.insert_organization(owner.id, "Acme").await.unwrap();
+        db.delete_organization(organization.id).await.unwrap();
+        assert!(db.user_by_id(organization.user_id).await.is_err());
+        assert!(db.user_by_id(owner.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn leaving_an_organization_revokes_your_api_tokens() {
+        let db = database().await;
+        let owner = db.insert_user().await.unwrap();
+        let organization = db.insert_organization(owner.id, "Acme").await.unwrap();
+        let member = db.insert_user().await.unwrap();
+        let invitation = db
+            .insert_invitation(organization.id, Role::Member, owner.id)
+            .await
+            .unwrap();
+        db.accept_invitation(invitation.id, member.id)
+            .await
+            .unwrap();
+        let (_, owners_token) = db
+            .insert_api_token(organization.user_id, owner.id, "owner", &Scope::ALL)
+            .await
+            .unwrap();
+        let (_, members_token) = db
+            .insert_api_token(organization.user_id, member.id, "member", &Scope::ALL)
+            .await
+            .unwrap();
+        db.delete_membership(organization.id, member.id)
+            .await
+            .unwrap();
+        assert!(db.api_token_by_token(&members_token).await.is_err());
+        assert!(db.api_token_by_token(&owners_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn signing_up_counts_against_the_site_limit() {
+        let db = database().await.max_sites(Some(0));
+        assert!(matches!(
+            db.signup("https://example.com".to_string(), Login::default())
+                .await,
+            Err(AppError::SiteLimit)
+        ));
+        assert!(db.sites().await.unwrap().is_empty());
     }
 }
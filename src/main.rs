@@ -1,34 +1,68 @@
 #![allow(non_snake_case)]
 
+mod api;
+
 use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::TimeZone;
 use dioxus::prelude::*;
 use dioxus_liveview::LiveViewPool;
+use qrcode::{render::svg, QrCode};
 use rust_embed::RustEmbed;
 use salvo::{
-    affix, handler,
+    affix, compression, handler,
     http::cookie::SameSite,
-    hyper::header::ORIGIN,
+    http::Method,
+    hyper::header::{
+        ACCEPT_ENCODING, ACCEPT_LANGUAGE, ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION,
+        CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_SECURITY_POLICY, CONTENT_TYPE, ETAG, HOST,
+        IF_NONE_MATCH, ORIGIN, REFERER, RETRY_AFTER, USER_AGENT, X_CONTENT_TYPE_OPTIONS,
+    },
     prelude::{StatusCode, StatusError, TcpListener},
     serve_static::static_embed,
     session::{CookieStore, SessionDepotExt, SessionHandler},
-    writer::{Json, Text},
+    writer::{Json, Redirect, Text},
     ws::WebSocketUpgrade,
-    Depot, Request, Response, Router, Server,
+    Depot, FlowCtrl, Handler, Request, Response, Router, Server,
 };
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
     sync::{Arc, OnceLock},
 };
-use updown::{AppError, Database, Login, Site, User};
+use updown::{
+    backup, badge, checker, demo,
+    digest::{self, Frequency},
+    expiry::ExpiryKind,
+    favicon,
+    i18n::{t, t_with, Locale},
+    ical, import, incident,
+    models::Check,
+    notifications::{self, email::Mailer, Dispatcher},
+    rate_limit::RateLimiter,
+    rules, subscribers, template,
+    timestamp::Timestamp,
+    token::{self, Action, Claims, Signer},
+    webhooks::{self, CheckPayload, Event},
+    AppError, Dashboard, DashboardSite, Database, DatabaseOptions, ExpiryRule, IncidentStatus,
+    IncidentTimeline, LatencyRule, Login, LoginFailures, MaintenanceWindow, NotificationChannel,
+    Page, Passkey, Resolution, Site, SiteIcon, SiteQuery, SiteSort, SiteTransfer, StatusIncident,
+    Tag, Uptime, User, WatchCycle,
+};
+use webauthn_rs::prelude as webauthn;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt().init();
     ENV.set(Env::new()).unwrap();
-    DB.set(Database::new(env().database_url.clone()).await)
-        .unwrap();
+    DB.set(
+        Database::new_with_options(env().database_url.clone(), env().database_options.clone())
+            .await
+            .max_sites(env().max_sites_per_user),
+    )
+    .unwrap();
     let args: Vec<String> = std::env::args().collect();
     let Some(arg) = args.get(1) else {
         db().migrate().await?;
@@ -45,6 +79,40 @@ async fn main() -> Result<()> {
         "watch" => {
             watch().await?;
         }
+        "backup" => {
+            let dir = args
+                .get(2)
+                .or(env().backup_dir.as_ref())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("backups"));
+            let path = backup::run(db(), &dir, env().backup_keep).await?;
+            println!("backed up to {}", path.display());
+        }
+        "signup" => {
+            let url = Site::normalize_url(args.get(2).map(String::as_str).unwrap_or_default())?;
+            let (user, site) = db().signup(url, Login::default()).await?;
+            println!(
+                "monitoring {} with login code {}",
+                site.url, user.login_code
+            );
+        }
+        "admin" => {
+            let login_code = args.get(2).map(String::as_str).unwrap_or_default();
+            let user = db().make_admin(login_code).await?;
+            println!("user {} is an admin now", user.id);
+        }
+        "max-sites" => {
+            let login_code = args.get(2).map(String::as_str).unwrap_or_default();
+            let max_sites = match args.get(3).map(String::as_str) {
+                Some("default") | None => None,
+                Some(max_sites) => Some(max_sites.parse::<i64>()?),
+            };
+            let user = db().set_max_sites(login_code, max_sites).await?;
+            match user.max_sites {
+                Some(max_sites) => println!("user {} can monitor {} sites", user.id, max_sites),
+                None => println!("user {} has the instance's site limit", user.id),
+            }
+        }
         _ => todo!(),
     };
     Ok(())
@@ -54,36 +122,268 @@ async fn server() -> Result<()> {
     // hot_reload_init!();
     let addr: SocketAddr = env().host.parse()?;
     println!("Listening on {}", addr);
+    #[cfg(feature = "acme")]
+    if let Some(cache_dir) = &env().acme_cache_dir {
+        return serve_acme(addr, cache_dir).await;
+    }
     Server::new(TcpListener::bind(addr)).serve(routes()).await;
     Ok(())
 }
 
+/// Serves https on `ACME_HOST` next to plain http on `HOST`, with
+/// certificates for the origin and every custom domain. The domains are
+/// read once, one added later gets its certificate after a restart.
+#[cfg(feature = "acme")]
+async fn serve_acme(addr: SocketAddr, cache_dir: &str) -> Result<()> {
+    use salvo::listener::{AcmeListener, Listener};
+    let mut listener = AcmeListener::builder().cache_path(cache_dir);
+    for domain in origin_host()
+        .into_iter()
+        .chain(db().share_link_domains().await?)
+    {
+        listener = listener.add_domain(domain);
+    }
+    let acme_addr: SocketAddr = env().acme_host.parse()?;
+    println!("Listening on {} with https", acme_addr);
+    let listener = listener.bind(acme_addr).await;
+    Server::new(listener.join(TcpListener::bind(addr)))
+        .serve(routes())
+        .await;
+    Ok(())
+}
+
 async fn watch() -> Result<()> {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+        Database::WATCH_INTERVAL_SECS as u64,
+    ));
+    if let Some(dir) = &env().backup_dir {
+        tokio::spawn(backups(PathBuf::from(dir)));
+    }
 
     loop {
-        interval.tick().await;
-        tokio::spawn(async {
-            _ = monitor().await;
+        let scheduled = interval.tick().await;
+        tokio::spawn(async move {
+            let lag = scheduled.elapsed();
+            if let Err(err) = monitor(lag).await {
+                tracing::error!("watch cycle failed: {}", err);
+            }
         });
     }
 }
 
-async fn monitor() -> Result<()> {
-    let sites = db().sites().await?;
+/// Snapshots the database every `backup_interval_hours`, starting right
+/// away.
+async fn backups(dir: PathBuf) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+        env().backup_interval_hours * 3600,
+    ));
+    loop {
+        interval.tick().await;
+        match backup::run(db(), &dir, env().backup_keep).await {
+            Ok(path) => tracing::info!("backed up to {}", path.display()),
+            Err(err) => tracing::error!("backup failed: {:#}", err),
+        }
+    }
+}
+
+async fn monitor(lag: std::time::Duration) -> Result<()> {
+    let started = std::time::Instant::now();
+    db().start_maintenance_windows(Database::now()).await?;
+    let sites = db().sites_due_check(Database::now()).await?;
+    let client = reqwest::Client::new();
+    let mut cycle = WatchCycle::default();
+    let mut events = vec![];
     for site in sites {
-        let response = response(&site).await?;
-        db().upsert_response(response).await?;
+        let check = checker::check(&client, &site).await;
+        db().upsert_response(check.response()).await?;
+        db().insert_check(&check).await?;
+        cycle.sites_checked += 1;
+        if !check.response().is_up() {
+            cycle.failures += 1;
+        }
+        if check.maintenance {
+            continue;
+        }
+        if !check.response().is_up() {
+            let mut payload = webhooks::Payload::new(Event::CheckFailed, site.clone());
+            payload.check = Some(CheckPayload::from(&check));
+            emit(&client, payload);
+        }
+        let tracked = incident::track(db(), &site, &check).await?;
+        if tracked.opened {
+            cycle.new_incidents += 1;
+        }
+        let incident = tracked
+            .event
+            .as_ref()
+            .and_then(|event| event.incident.clone());
+        for (happened, event) in [
+            (tracked.opened, Event::IncidentOpened),
+            (tracked.resolved, Event::IncidentResolved),
+        ] {
+            if happened {
+                let mut payload = webhooks::Payload::new(event, site.clone());
+                payload.incident = incident.clone();
+                emit(&client, payload);
+            }
+        }
+        let name = site.display_name();
+        if tracked.opened {
+            notify_subscribers(
+                site.id,
+                format!("{} is down", name),
+                format!("{} stopped responding, we're looking into it.", name),
+            );
+        }
+        if tracked.resolved {
+            notify_subscribers(
+                site.id,
+                format!("{} is back up", name),
+                format!("{} is responding again.", name),
+            );
+        }
+        events.extend(tracked.event);
+    }
+    let notifications = notifications::batch(events, notifications::BATCH_THRESHOLD);
+    let dispatcher = dispatcher();
+    dispatcher.dispatch(notifications).await;
+    dispatcher.flush().await;
+    dispatcher.remind().await;
+    rules::evaluate_latency_rules(db(), &dispatcher).await;
+    rules::refresh_expiries(db(), &client).await;
+    favicon::refresh(db(), &client).await;
+    rules::evaluate_expiry_rules(db(), &dispatcher).await;
+    digest::send_digests(db(), &dispatcher).await;
+    cycle.duration_ms = started.elapsed().as_millis() as i64;
+    cycle.lag_ms = lag.as_millis() as i64;
+    tracing::info!(
+        sites_checked = cycle.sites_checked,
+        failures = cycle.failures,
+        new_incidents = cycle.new_incidents,
+        duration_ms = cycle.duration_ms,
+        lag_ms = cycle.lag_ms,
+        "watch cycle finished"
+    );
+    db().insert_watch_cycle(cycle).await?;
+    let before = Database::now() - env().retention_days * 86_400;
+    let rolled_up = db().roll_up_checks(before).await?;
+    if rolled_up > 0 {
+        tracing::info!(rolled_up, "rolled up old checks");
     }
     Ok(())
 }
 
-async fn response<'a>(site: &'a Site) -> Result<updown::models::Response> {
-    let status_code: i64 = reqwest::get(&site.url).await?.status().as_u16() as i64;
-    let mut res = updown::models::Response::default();
-    res.status_code = status_code;
-    res.site_id = site.id;
-    Ok(res)
+/// Delivers to the site owner's webhooks in the background, so a slow
+/// endpoint doesn't hold up the watch cycle or the request.
+fn emit(client: &reqwest::Client, payload: webhooks::Payload) {
+    let client = client.clone();
+    tokio::spawn(async move { webhooks::deliver(db(), &client, &payload).await });
+}
+
+/// Tells webhooks subscribed to `site.created` about each new site and
+/// fetches their favicons in the background.
+fn emit_sites_created(sites: &[Site]) {
+    let client = reqwest::Client::new();
+    for site in sites {
+        emit(
+            &client,
+            webhooks::Payload::new(Event::SiteCreated, site.clone()),
+        );
+        let client = client.clone();
+        let site = site.clone();
+        tokio::spawn(async move { favicon::refresh_site(db(), &client, &site).await });
+    }
+}
+
+/// A dispatcher that signs links and sends email when SMTP is configured.
+fn dispatcher() -> Dispatcher<'static> {
+    let dispatcher =
+        Dispatcher::new(db(), env().origin.clone()).signer(Signer::new(&env().session_key));
+    match mailer() {
+        Some(mailer) => dispatcher.mailer(mailer),
+        None => dispatcher,
+    }
+}
+
+fn mailer() -> Option<Mailer> {
+    let (Some(url), Some(from)) = (&env().smtp_url, &env().mail_from) else {
+        return None;
+    };
+    match Mailer::new(url, from) {
+        Ok(mailer) => Some(mailer),
+        Err(err) => {
+            tracing::error!("could not set up smtp: {:?}", err);
+            None
+        }
+    }
+}
+
+/// How long the link in a recovery email logs in for.
+const RECOVERY_LINK_SECS: i64 = 15 * 60;
+const VERIFY_LINK_SECS: i64 = 24 * 60 * 60;
+
+/// Mails a link to the address that verifies it belongs to the user.
+async fn send_email_verification(user_id: i64, email: &str) -> Result<(), AppError> {
+    let mailer = mailer().ok_or(AppError::Notify)?;
+    let claims = Claims::new(
+        Action::VerifyEmail,
+        user_id,
+        token::fingerprint(email),
+        Database::now() + VERIFY_LINK_SECS,
+    );
+    let url = format!(
+        "{}/account/email/{}",
+        env().origin,
+        Signer::new(&env().session_key).sign(&claims)
+    );
+    let body = format!(
+        "Follow this link to use this address to recover your updown account:\n\n{}\n\nIgnore this email if you didn't ask for it.",
+        url
+    );
+    mailer.send(email, "Verify your recovery email", body).await
+}
+
+/// Mails a one time login link to every account that verified the email.
+/// Nothing is sent for an unknown address and the caller can't tell the
+/// difference, so addresses can't be probed.
+async fn send_recovery_links(email: &str) -> Result<(), AppError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    // asking again right away doesn't send another round of mail
+    if recovery_limiter()
+        .take(&email.trim().to_lowercase(), now)
+        .is_some()
+    {
+        return Ok(());
+    }
+    let users = db()
+        .users_by_email(email.trim())
+        .await
+        .map_err(|source| AppError::DatabaseSelect { source })?;
+    if users.is_empty() {
+        return Ok(());
+    }
+    let mailer = mailer().ok_or(AppError::Notify)?;
+    let signer = Signer::new(&env().session_key);
+    for user in users {
+        let claims = Claims::new(
+            Action::Recover,
+            user.id,
+            user.id,
+            Database::now() + RECOVERY_LINK_SECS,
+        );
+        let body = format!(
+            "Follow this link in the next {} to log in to updown:\n\n{}/recover/{}\n\nYour login code is shown in Account once you're in. Ignore this email if you didn't ask for it.",
+            notifications::format_duration(RECOVERY_LINK_SECS),
+            env().origin,
+            signer.sign(&claims)
+        );
+        let to = user.email.as_deref().unwrap_or(email);
+        mailer.send(to, "Log in to updown", body).await?;
+    }
+    Ok(())
 }
 
 #[derive(RustEmbed)]
@@ -92,14 +392,47 @@ struct Assets;
 
 static ENV: OnceLock<Env> = OnceLock::new();
 static DB: OnceLock<Database> = OnceLock::new();
+static RATE_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+static RECOVERY_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
 
 #[derive(Debug, Default)]
 struct Env {
     pub database_url: String,
+    pub database_options: DatabaseOptions,
     pub host: String,
     pub origin: String,
     pub ws_host: String,
     pub session_key: String,
+    /// When set, `/flaky` is down for the second half of every period of
+    /// this many seconds.
+    pub flaky_period: Option<i64>,
+    /// SMTP url and from address for email channels and digests.
+    pub smtp_url: Option<String>,
+    pub mail_from: Option<String>,
+    /// Days raw checks are kept before being rolled up.
+    pub retention_days: i64,
+    /// Where the watcher writes scheduled backups, none are made when unset.
+    pub backup_dir: Option<String>,
+    pub backup_interval_hours: u64,
+    /// How many backups are kept, older ones are deleted.
+    pub backup_keep: usize,
+    /// Most sites one user can monitor, users with their own `max_sites`
+    /// are not bound by it. No limit when unset.
+    pub max_sites_per_user: Option<i64>,
+    /// Requests per minute one ip or api token can make to the login,
+    /// signup, badge, status and api routes, 0 turns the limit off.
+    pub rate_limit_per_minute: u32,
+    /// Where certificates from let's encrypt are kept, https is only
+    /// served when this is set and the `acme` feature is on.
+    pub acme_cache_dir: Option<String>,
+    /// The address https is served on, the challenges need port 443.
+    pub acme_host: String,
+    /// Offers example sites with made up history to accounts without any,
+    /// for screenshots and trying things out.
+    pub demo: bool,
+    /// Proxies whose `X-Forwarded-For` is believed, requests from anywhere
+    /// else are placed by the address they came from.
+    pub trusted_proxies: Vec<IpAddr>,
 }
 
 impl Env {
@@ -111,19 +444,42 @@ impl Env {
         std::fs::read_to_string(".env").unwrap_or_default()
     }
 
+    /// Reads the DB_ pool and pragma settings, falling back to the defaults.
+    fn database_options(data: &HashMap<&str, &str>) -> DatabaseOptions {
+        let defaults = DatabaseOptions::default();
+        DatabaseOptions {
+            max_connections: data
+                .get("DB_MAX_CONNECTIONS")
+                .and_then(|max| max.parse().ok())
+                .filter(|max| *max > 0)
+                .unwrap_or(defaults.max_connections),
+            busy_timeout: data
+                .get("DB_BUSY_TIMEOUT_SECS")
+                .and_then(|secs| secs.parse().ok())
+                .map(std::time::Duration::from_secs)
+                .unwrap_or(defaults.busy_timeout),
+            cache_size_kib: data
+                .get("DB_CACHE_SIZE_KIB")
+                .and_then(|kib| kib.parse().ok()),
+            mmap_size: data
+                .get("DB_MMAP_SIZE")
+                .and_then(|bytes| bytes.parse().ok()),
+            temp_store_memory: data.get("DB_TEMP_STORE") == Some(&"memory"),
+        }
+    }
+
     fn parse(file: String) -> Self {
+        // values like SMTP_URL can have an = of their own
         let data = file
             .lines()
-            .flat_map(|line| line.split("="))
-            .collect::<Vec<_>>()
-            .chunks_exact(2)
-            .map(|x| (x[0], x[1]))
+            .filter_map(|line| line.split_once('='))
             .collect::<HashMap<_, _>>();
         Self {
             database_url: data
                 .get("DATABASE_URL")
                 .expect("DATABASE_URL is missing")
                 .to_string(),
+            database_options: Self::database_options(&data),
             host: data.get("HOST").expect("HOST is missing").to_string(),
             origin: data.get("ORIGIN").expect("ORIGIN is missing").to_string(),
             ws_host: data.get("WS_HOST").expect("WS_HOST is missing").to_string(),
@@ -131,6 +487,47 @@ impl Env {
                 .get("SESSION_KEY")
                 .expect("SESSION_KEY is missing")
                 .to_string(),
+            flaky_period: data
+                .get("FLAKY_PERIOD")
+                .and_then(|period| period.parse().ok())
+                .filter(|period| *period > 1),
+            smtp_url: data.get("SMTP_URL").map(|url| url.to_string()),
+            mail_from: data.get("MAIL_FROM").map(|from| from.to_string()),
+            retention_days: data
+                .get("RETENTION_DAYS")
+                .and_then(|days| days.parse().ok())
+                .unwrap_or(30),
+            backup_dir: data.get("BACKUP_DIR").map(|dir| dir.to_string()),
+            backup_interval_hours: data
+                .get("BACKUP_INTERVAL_HOURS")
+                .and_then(|hours| hours.parse().ok())
+                .filter(|hours| *hours > 0)
+                .unwrap_or(24),
+            backup_keep: data
+                .get("BACKUP_KEEP")
+                .and_then(|keep| keep.parse().ok())
+                .filter(|keep| *keep > 0)
+                .unwrap_or(7),
+            max_sites_per_user: data
+                .get("MAX_SITES_PER_USER")
+                .and_then(|max| max.parse().ok())
+                .filter(|max| *max >= 0),
+            rate_limit_per_minute: data
+                .get("RATE_LIMIT_PER_MINUTE")
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(60),
+            acme_cache_dir: data.get("ACME_CACHE_DIR").map(|dir| dir.to_string()),
+            acme_host: data.get("ACME_HOST").unwrap_or(&"0.0.0.0:443").to_string(),
+            demo: data.get("DEMO").is_some_and(|demo| *demo == "true"),
+            trusted_proxies: data
+                .get("TRUSTED_PROXIES")
+                .map(|proxies| {
+                    proxies
+                        .split(',')
+                        .filter_map(|ip| ip.trim().parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
         }
     }
 }
@@ -143,6 +540,53 @@ pub fn db() -> &'static Database {
     DB.get().expect("db is not initialized")
 }
 
+fn rate_limiter() -> &'static RateLimiter {
+    RATE_LIMITER.get_or_init(|| RateLimiter::new(env().rate_limit_per_minute))
+}
+
+/// One recovery email a minute per address, however many ips ask for it.
+fn recovery_limiter() -> &'static RateLimiter {
+    RECOVERY_LIMITER.get_or_init(|| RateLimiter::new(1))
+}
+
+/// Seconds until the client may try again once it's over the rate limit.
+/// Api tokens get their own bucket, everyone else shares one per ip. A
+/// token only counts once it's found, so made up ones can't each get a
+/// fresh bucket.
+async fn rate_limit_retry_after(req: &Request) -> Option<u64> {
+    let token = req.header::<String>(AUTHORIZATION).and_then(|header| {
+        header
+            .strip_prefix("Bearer ")
+            .map(|token| token.trim().to_string())
+    });
+    let api_token = match token {
+        Some(token) => db().api_token_by_token(&token).await.ok(),
+        None => None,
+    };
+    let key = match api_token {
+        Some(api_token) => format!("token:{}", api_token.id),
+        None => format!("ip:{}", client_ip(req).unwrap_or_default()),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    rate_limiter().take(&key, now)
+}
+
+#[handler]
+async fn rate_limit_handler(req: &mut Request, res: &mut Response, ctrl: &mut FlowCtrl) {
+    let Some(secs) = rate_limit_retry_after(req).await else {
+        return;
+    };
+    res.set_status_code(StatusCode::TOO_MANY_REQUESTS);
+    if res.add_header(RETRY_AFTER, secs.to_string(), true).is_err() {
+        tracing::warn!("could not set retry-after");
+    }
+    res.render(Json(AppError::RateLimited));
+    ctrl.skip_rest();
+}
+
 fn at(path: &str) -> salvo::Router {
     Router::with_path(path)
 }
@@ -158,18 +602,165 @@ fn routes() -> Router {
     let view = LiveViewPool::new();
     let arc_view = Arc::new(view);
     Router::new()
+        .hoop(compression_handler)
+        .hoop(custom_domain_handler)
         .push(
             Router::new()
                 .hoop(session_handler)
+                .hoop(csrf_handler)
                 .hoop(set_current_user_handler)
+                .hoop(role_handler)
                 .hoop(affix::inject(arc_view))
                 .get(index)
-                .push(at("/login").post(login))
-                .push(at("/signup").post(signup))
+                .push(at("/monitors").get(index))
+                .push(at("/incidents").get(index))
+                .push(at("/account").get(index))
+                .push(at("/sites/<id>").get(index))
+                .push(at("/login").get(index))
+                .push(at("/welcome").get(index))
+                .push(at("/login").hoop(rate_limit_handler).post(login))
+                .push(at("/signup").hoop(rate_limit_handler).post(signup))
                 .push(at("/logout").post(logout))
-                .push(at("/ws").get(liveview)),
+                .push(at("/channels").get(channels).post(add_channel))
+                .push(
+                    at("/channels/<id>")
+                        .put(update_channel)
+                        .delete(delete_channel),
+                )
+                .push(at("/channels/<id>/test").post(test_channel))
+                .push(at("/api/channels/<id>/test").post(test_channel))
+                .push(
+                    at("/channels/<id>/sites/<site_id>")
+                        .post(route_channel)
+                        .delete(unroute_channel),
+                )
+                .push(at("/sites/<id>/transfers").post(transfer_site))
+                .push(at("/transfers").post(accept_transfer))
+                .push(
+                    at("/rules/latency")
+                        .get(latency_rules)
+                        .post(add_latency_rule),
+                )
+                .push(at("/rules/latency/<id>").delete(delete_latency_rule))
+                .push(at("/rules/expiry").get(expiry_rules).post(add_expiry_rule))
+                .push(at("/rules/expiry/<id>").delete(delete_expiry_rule))
+                .push(at("/account/digest").post(update_digest))
+                .push(at("/account/quiet-hours").post(update_quiet_hours))
+                .push(at("/account/email").post(update_email))
+                .push(
+                    at("/passkeys/register")
+                        .get(start_passkey_registration)
+                        .post(finish_passkey_registration),
+                )
+                .push(
+                    at("/passkeys/login")
+                        .get(start_passkey_login)
+                        .post(finish_passkey_login),
+                )
+                .push(at("/api/passkeys").get(list_passkeys))
+                .push(at("/api/passkeys/<id>").delete(delete_passkey))
+                .push(
+                    at("/organizations")
+                        .get(list_organizations)
+                        .post(add_organization),
+                )
+                .push(at("/organizations/active").post(switch_organization))
+                .push(at("/organizations/<id>").delete(delete_organization))
+                .push(at("/organizations/<id>/members").get(list_members))
+                .push(
+                    at("/organizations/<id>/invitations")
+                        .get(list_invitations)
+                        .post(add_invitation),
+                )
+                .push(
+                    at("/organizations/<id>/invitations/<invitation_id>").delete(revoke_invitation),
+                )
+                .push(at("/invites/<token>").get(open_invitation))
+                .push(at("/invites/join").post(join_with_new_account))
+                .push(
+                    at("/organizations/<id>/members/<user_id>")
+                        .put(update_member)
+                        .delete(remove_member),
+                )
+                .push(at("/recover").hoop(rate_limit_handler).post(recover))
+                .push(at("/recover/<token>").get(recover_login))
+                .push(at("/session/<token>").get(session_link))
+                .push(at("/api/sites").get(list_sites).post(add_site))
+                .push(at("/api/sites/<id>/checks").get(site_checks))
+                .push(at("/api/sites/<id>/tags").post(tag_site))
+                .push(at("/api/sites/<id>/tags/<tag_id>").delete(untag_site))
+                .push(at("/api/tags").get(list_tags))
+                .push(at("/api/export").get(export))
+                .push(at("/api/account").delete(delete_account))
+                .push(at("/api/import").post(import_sites))
+                .push(at("/api/tags/<name>/sites").get(tagged_sites))
+                .push(at("/api/sites/<id>").put(update_site).delete(delete_site))
+                .push(at("/sites/<id>/critical").post(update_critical))
+                .push(at("/sites/<id>/maintenance").post(update_maintenance))
+                .push(
+                    at("/api/sites/<id>/maintenance-windows")
+                        .get(list_maintenance_windows)
+                        .post(add_maintenance_window),
+                )
+                .push(at("/api/maintenance-windows/<id>").delete(delete_maintenance_window))
+                .push(at("/sites/<id>/uptime").get(site_uptime))
+                .push(at("/sites/<id>/icon").get(site_icon))
+                .push(
+                    at("/api/sites/<id>/share")
+                        .get(list_share_links)
+                        .post(add_share_link),
+                )
+                .push(at("/api/sites/<id>/share/<share_id>").delete(revoke_share_link))
+                .push(
+                    at("/api/sites/<id>/status-incidents")
+                        .get(list_status_incidents)
+                        .post(add_status_incident),
+                )
+                .push(at("/api/status-incidents/<id>").delete(delete_status_incident))
+                .push(at("/api/status-incidents/<id>/updates").post(add_incident_update))
+                .push(at("/api/sites/<id>/share/<share_id>/domain").put(update_share_domain))
+                .push(api::router())
+                .push(at("/ws").get(liveview))
+                .push(
+                    Router::new()
+                        .hoop(admin_handler)
+                        .push(at("/admin").get(admin))
+                        .push(at("/ws/admin").get(admin_liveview)),
+                ),
+        )
+        .push(at("/flaky").get(flaky))
+        .push(at("/ack/<token>").get(acknowledge))
+        .push(at("/share/<token>").get(shared_site))
+        .push(at("/share/<token>/maintenance.ics").get(maintenance_calendar))
+        .push(
+            at("/share/<token>/subscribe")
+                .hoop(rate_limit_handler)
+                .post(subscribe),
+        )
+        .push(at("/subscribers/confirm/<token>").get(confirm_subscriber))
+        .push(at("/subscribers/unsubscribe/<token>").get(unsubscribe))
+        .push(
+            at("/badge/<file>")
+                .hoop(rate_limit_handler)
+                .get(status_badge),
+        )
+        .push(
+            at("/status/<file>")
+                .hoop(rate_limit_handler)
+                .get(status_json),
+        )
+        .push(
+            at("/widget/<file>")
+                .hoop(rate_limit_handler)
+                .get(widget_json),
+        )
+        .push(at("/account/email/<token>").get(verify_email))
+        .push(at("/metrics").get(metrics))
+        .push(
+            at("<**path>")
+                .hoop(asset_cache_handler)
+                .get(static_embed::<Assets>()),
         )
-        .push(at("<**path>").get(static_embed::<Assets>()))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -180,11 +771,40 @@ struct LoginParams {
 #[handler]
 async fn login(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
     let LoginParams { login_code } = req.parse_json::<LoginParams>().await?;
-    let user = db().user_by_login_code(login_code).await?;
+    let ip_address = client_ip(req);
+    let now = Database::now();
+    let failures = db()
+        .login_failures(
+            ip_address.as_deref(),
+            &login_code,
+            now - LoginFailures::WINDOW_SECS,
+        )
+        .await?;
+    if let Some(secs) = failures.retry_after(now) {
+        res.set_status_code(StatusCode::TOO_MANY_REQUESTS);
+        res.add_header(RETRY_AFTER, secs.to_string(), true)?;
+        res.render(Json(AppError::TooManyAttempts));
+        return Ok(());
+    }
+    let Ok(user) = db().user_by_login_code(login_code.clone()).await else {
+        db().insert_login_attempt(ip_address.as_deref(), &login_code)
+            .await?;
+        res.set_status_code(StatusCode::UNAUTHORIZED);
+        res.render(Json(AppError::Login));
+        return Ok(());
+    };
+    db().clear_login_attempts(&login_code).await?;
     let session = depot.session_mut().ok_or(AppError::Login)?;
     _ = session.insert("user_id", user.id)?;
-    let new_login: Login = Database::new_login(user.id);
-    if let Ok(_) = db().insert_login(new_login).await {
+    accept_pending_invitation(depot, user.id).await;
+    let mut new_login: Login = Database::new_login(user.id);
+    new_login.ip_address = ip_address;
+    new_login.user_agent = req.header::<String>(USER_AGENT);
+    let seen = db().login_seen(&new_login).await.unwrap_or(true);
+    if let Ok(login_row) = db().insert_login(new_login).await {
+        if !seen {
+            tokio::spawn(async move { dispatcher().new_login(&login_row).await });
+        }
         res.set_status_code(StatusCode::OK);
         res.render(Json(Login::default()));
     } else {
@@ -194,6 +814,158 @@ async fn login(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Resu
     Ok(())
 }
 
+/// The webauthn relying party, this instance as named by `ORIGIN`.
+fn relying_party() -> Result<webauthn::Webauthn, AppError> {
+    let origin = webauthn::Url::parse(&env().origin).map_err(|_| AppError::Passkey)?;
+    let rp_id = origin.host_str().ok_or(AppError::Passkey)?.to_string();
+    webauthn::WebauthnBuilder::new(&rp_id, &origin)
+        .and_then(|builder| builder.rp_name("updown").build())
+        .map_err(|err| {
+            tracing::error!("could not set up webauthn: {}", err);
+            AppError::Passkey
+        })
+}
+
+/// Starts adding a passkey to the logged in account. The ceremony state
+/// waits in the session until the browser answers.
+#[handler]
+async fn start_passkey_registration(
+    depot: &mut Depot,
+) -> Result<Json<webauthn::CreationChallengeResponse>> {
+    let user = person(depot).ok_or(AppError::Login)?.clone();
+    let exclude = db()
+        .passkeys_by_user_id(user.id)
+        .await?
+        .iter()
+        .filter_map(|row| serde_json::from_str::<webauthn::Passkey>(&row.passkey).ok())
+        .map(|passkey| passkey.cred_id().clone())
+        .collect::<Vec<_>>();
+    let name = format!("updown account {}", user.id);
+    let (challenge, state) = relying_party()?
+        .start_passkey_registration(
+            webauthn::Uuid::from_u64_pair(0, user.id as u64),
+            &name,
+            &name,
+            Some(exclude),
+        )
+        .map_err(|_| AppError::Passkey)?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    _ = session.insert("passkey_registration", state)?;
+    Ok(Json(challenge))
+}
+
+#[handler]
+async fn finish_passkey_registration(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<Passkey>> {
+    let user_id = person(depot).ok_or(AppError::Login)?.id;
+    let credential = req
+        .parse_json::<webauthn::RegisterPublicKeyCredential>()
+        .await?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    let state = session
+        .get::<webauthn::PasskeyRegistration>("passkey_registration")
+        .ok_or(AppError::Passkey)?;
+    session.remove("passkey_registration");
+    let passkey = relying_party()?
+        .finish_passkey_registration(&credential, &state)
+        .map_err(|_| AppError::Passkey)?;
+    let credential_id = URL_SAFE_NO_PAD.encode(passkey.cred_id().as_ref());
+    let row = db()
+        .insert_passkey(user_id, &credential_id, &serde_json::to_string(&passkey)?)
+        .await?;
+    Ok(Json(row))
+}
+
+/// Starts a login where the browser offers whichever passkey it has for
+/// this site, so there's nothing to type.
+#[handler]
+async fn start_passkey_login(
+    depot: &mut Depot,
+) -> Result<Json<webauthn::RequestChallengeResponse>> {
+    let (challenge, state) = relying_party()?
+        .start_discoverable_authentication()
+        .map_err(|_| AppError::Passkey)?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    _ = session.insert("passkey_login", state)?;
+    Ok(Json(challenge))
+}
+
+#[handler]
+async fn finish_passkey_login(depot: &mut Depot, req: &mut Request) -> Result<Json<Login>> {
+    let credential = req.parse_json::<webauthn::PublicKeyCredential>().await?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    let state = session
+        .get::<webauthn::DiscoverableAuthentication>("passkey_login")
+        .ok_or(AppError::Passkey)?;
+    session.remove("passkey_login");
+    let relying_party = relying_party()?;
+    let (_, credential_id) = relying_party
+        .identify_discoverable_authentication(&credential)
+        .map_err(|_| AppError::Passkey)?;
+    let row = db()
+        .passkey_by_credential_id(&URL_SAFE_NO_PAD.encode(credential_id))
+        .await
+        .map_err(|_| AppError::Passkey)?;
+    let mut passkey = serde_json::from_str::<webauthn::Passkey>(&row.passkey)?;
+    let result = relying_party
+        .finish_discoverable_authentication(&credential, state, &[(&passkey).into()])
+        .map_err(|_| AppError::Passkey)?;
+    passkey.update_credential(&result);
+    db().update_passkey(row.id, &serde_json::to_string(&passkey)?)
+        .await?;
+    _ = session.insert("user_id", row.user_id)?;
+    accept_pending_invitation(depot, row.user_id).await;
+    let mut new_login: Login = Database::new_login(row.user_id);
+    new_login.ip_address = client_ip(req);
+    new_login.user_agent = req.header::<String>(USER_AGENT);
+    let seen = db().login_seen(&new_login).await.unwrap_or(true);
+    let login_row = db().insert_login(new_login).await?;
+    if !seen {
+        let login_row = login_row.clone();
+        tokio::spawn(async move { dispatcher().new_login(&login_row).await });
+    }
+    Ok(Json(login_row))
+}
+
+#[handler]
+async fn list_passkeys(depot: &mut Depot) -> Result<Json<Vec<Passkey>>> {
+    let user = person(depot).ok_or(AppError::Login)?;
+    let passkeys = db().passkeys_by_user_id(user.id).await?;
+    Ok(Json(passkeys))
+}
+
+#[handler]
+async fn delete_passkey(depot: &mut Depot, req: &mut Request) -> Result<Json<i64>> {
+    let user = person(depot).ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    db().delete_passkey(user.id, id).await?;
+    Ok(Json(id))
+}
+
+/// The client's address. `X-Forwarded-For` is only read when one of
+/// `TRUSTED_PROXIES` sent the request, and then from the right, since
+/// anything left of the last trusted proxy was written by the client.
+fn client_ip(req: &Request) -> Option<String> {
+    let addr = req.remote_addr()?;
+    let remote = match (addr.as_ipv4(), addr.as_ipv6()) {
+        (Some(addr), _) => IpAddr::V4(*addr.ip()),
+        (None, Some(addr)) => IpAddr::V6(*addr.ip()),
+        (None, None) => return None,
+    };
+    let trusted = &env().trusted_proxies;
+    if !trusted.contains(&remote) {
+        return Some(remote.to_string());
+    }
+    let forwarded = req.header::<String>("X-Forwarded-For").unwrap_or_default();
+    let client = forwarded
+        .rsplit(',')
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .find(|ip| !trusted.contains(ip));
+    Some(client.unwrap_or(remote).to_string())
+}
+
 #[derive(Serialize, Deserialize)]
 struct SignupParams {
     url: String,
@@ -202,28 +974,35 @@ struct SignupParams {
 #[handler]
 async fn signup(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
     let SignupParams { url } = req.parse_json::<SignupParams>().await?;
-    if url.is_empty() {
-        res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
-        res.render(Json(AppError::UrlEmpty));
-        return Ok(());
-    }
-    let user = db().insert_user().await?;
-    let session = depot.session_mut().ok_or(AppError::Login)?;
-    session
-        .insert("user_id", user.id)
-        .expect("could not set user id in session");
+    let url = match Site::normalize_url(&url) {
+        Ok(url) => url,
+        Err(err) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+            return Ok(());
+        }
+    };
     let mut login_row = Login::default();
-    login_row.user_id = user.id;
-    _ = db().insert_login(login_row).await?;
-    let mut site = Site::default();
-    site.user_id = user.id;
-    site.url = url;
-    if let Ok(_) = db().insert_site(site).await {
-        res.set_status_code(StatusCode::OK);
-        res.render(Json(AppError::Login));
-    } else {
-        res.set_status_code(StatusCode::UNAUTHORIZED);
-        res.render(Json(AppError::Login));
+    login_row.ip_address = client_ip(req);
+    login_row.user_agent = req.header::<String>(USER_AGENT);
+    match db().signup(url, login_row).await {
+        Ok((user, _)) => {
+            let session = depot.session_mut().ok_or(AppError::Login)?;
+            session
+                .insert("user_id", user.id)
+                .expect("could not set user id in session");
+            accept_pending_invitation(depot, user.id).await;
+            res.set_status_code(StatusCode::OK);
+            res.render(Json(AppError::Login));
+        }
+        Err(AppError::SiteLimit) => {
+            res.set_status_code(StatusCode::FORBIDDEN);
+            res.render(Json(AppError::SiteLimit));
+        }
+        Err(_) => {
+            res.set_status_code(StatusCode::UNAUTHORIZED);
+            res.render(Json(AppError::Login));
+        }
     }
     Ok(())
 }
@@ -234,512 +1013,5331 @@ async fn logout(depot: &mut Depot) -> Result<Json<User>> {
         if let Some(user_id) = session.get::<i64>("user_id") {
             if let Some(_) = db().user_by_id(user_id).await.ok() {
                 session.remove("user_id");
+                session.remove("organization_id");
             }
         }
     }
     Ok(Json(User::default()))
 }
 
+/// Deletes the account and everything in it, then logs out.
 #[handler]
-async fn set_current_user_handler(depot: &mut Depot) {
-    let maybe_id: Option<i64> = depot.session().unwrap().get("user_id");
-    if let Some(id) = maybe_id {
-        if let Ok(user) = db().user_by_id(id).await {
-            depot.inject(user);
+async fn delete_account(depot: &mut Depot) -> Result<Json<User>> {
+    let user_id = person(depot).ok_or(AppError::Login)?.id;
+    let user = db().delete_user(user_id).await?;
+    if let Some(session) = depot.session_mut() {
+        session.remove("user_id");
+        session.remove("organization_id");
+    }
+    Ok(Json(user))
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChannelParams {
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    config: serde_json::Value,
+    /// An Apprise url like `tgram://bottoken/chat_id`, used instead of
+    /// `kind` and `config` when given.
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    cooldown_secs: i64,
+    #[serde(default)]
+    renotify_secs: i64,
+    #[serde(default)]
+    template: Option<String>,
+}
+
+impl ChannelParams {
+    fn channel(self, user_id: i64) -> Result<NotificationChannel, AppError> {
+        let template = self.template.filter(|template| !template.trim().is_empty());
+        if let Some(template) = &template {
+            template::validate(template)?;
         }
+        let (kind, config) = match &self.url {
+            Some(url) => {
+                let (kind, config) = notifications::apprise::parse(url)?;
+                (kind.to_string(), config)
+            }
+            None => (self.kind, self.config),
+        };
+        let mut channel = NotificationChannel::default();
+        channel.user_id = user_id;
+        channel.kind = kind;
+        channel.config = config.to_string();
+        channel.cooldown_secs = self.cooldown_secs;
+        channel.renotify_secs = self.renotify_secs;
+        channel.template = template;
+        Ok(channel)
     }
 }
 
-#[cfg(debug_assertions)]
-const TAILWIND_CSS: &'static str = r#"<script src="https://cdn.tailwindcss.com"></script>"#;
-#[cfg(not(debug_assertions))]
-const TAILWIND_CSS: &'static str = r#"<link href="./tailwind.css" rel="stylesheet" />"#;
-#[cfg(debug_assertions)]
-const RETRY_MS: u16 = 1_000;
-#[cfg(not(debug_assertions))]
-const RETRY_MS: u16 = 45_000;
+#[handler]
+async fn channels(depot: &mut Depot) -> Result<Json<Vec<NotificationChannel>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let user_channels = db().channels_by_user_id(user.id).await?;
+    Ok(Json(user_channels))
+}
 
 #[handler]
-async fn index(res: &mut Response) -> Result<()> {
-    let ws_addr = &env().ws_host;
-    res.render(Text::Html(format!(
-        r#"
-            <!DOCTYPE html>
-            <html lang=en class="h-full">
-                <head>
-                    <meta charset="utf-8">
-                    <meta content="width=device-width, initial-scale=1" name="viewport">
-                    <meta name="ws-addr" content="{ws_addr}"">
-                    <meta name="retry-ms" content="{RETRY_MS}">
-                    <title>updown</title>
-                    {TAILWIND_CSS}
-                    <style>
-                        .box-shadow-md {{ box-shadow: 0 6px var(--tw-shadow-color); }}
-                        .hover\:box-shadow-xs:hover {{ box-shadow: 0 4px var(--tw-shadow-color); }}
-                    </style>
-                    <script defer src="./main.js"></script>
-                </head>
-                <body class="h-full dark:bg-gray-950 bg-gray-50 dark:text-white text-gray-900">
-                    <div id="main" class="h-full"></div>
-                </body>
-            </html>
-        "#
-    )));
-    Ok(())
+async fn add_channel(depot: &mut Depot, req: &mut Request) -> Result<Json<NotificationChannel>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let channel = req.parse_json::<ChannelParams>().await?.channel(user.id)?;
+    let channel = db().insert_channel(channel).await?;
+    Ok(Json(channel))
 }
 
 #[handler]
-async fn liveview(
-    req: &mut Request,
-    depot: &mut Depot,
-    res: &mut Response,
-) -> Result<(), StatusError> {
-    let env_origin = &env().origin;
-    let origin = &req.header::<String>(ORIGIN).unwrap_or_default();
-    if env_origin != origin {
-        return Err(StatusError::not_found());
-    }
-    let view = depot
-        .obtain::<Arc<LiveViewPool>>()
-        .expect("LiveViewPool was not found in the middleware")
-        .clone();
-    let current_user = depot.obtain::<User>().cloned();
-    let (sites, login_count) = if let Some(user) = &current_user {
-        let sites = db().sites_by_user_id(user.id).await.unwrap_or(vec![]);
-        let login_count = db().login_count(user.id).await.unwrap_or(0);
-        (sites, login_count)
-    } else {
-        (vec![], 0)
-    };
-    WebSocketUpgrade::new()
-        .upgrade(req, res, move |ws| async move {
-            let _ = view
-                .launch_with_props::<RootProps>(
-                    dioxus_liveview::salvo_socket(ws),
-                    Root,
-                    RootProps {
-                        current_user,
-                        login_count,
-                        sites,
-                    },
-                )
-                .await;
-        })
-        .await
+async fn update_channel(depot: &mut Depot, req: &mut Request) -> Result<Json<NotificationChannel>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let mut channel = req.parse_json::<ChannelParams>().await?.channel(user.id)?;
+    channel.id = id;
+    let channel = db().update_channel(channel).await?;
+    Ok(Json(channel))
 }
 
-#[derive(Props, PartialEq)]
-struct RootProps {
-    current_user: Option<User>,
-    sites: Vec<Site>,
-    login_count: i32,
+#[handler]
+async fn delete_channel(depot: &mut Depot, req: &mut Request) -> Result<Json<NotificationChannel>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let channel = db().channel_by_id(user.id, id).await?;
+    db().delete_channel(user.id, id).await?;
+    Ok(Json(channel))
 }
 
-fn Root(cx: Scope<RootProps>) -> Element {
-    let RootProps {
-        current_user,
-        sites,
-        login_count,
-    } = cx.props;
-    use_shared_state_provider(cx, || RootProps {
-        current_user: cx.props.current_user.clone(),
-        sites: sites.clone(),
-        login_count: login_count.clone(),
-    });
-    let initial_view = match (current_user, login_count) {
-        (Some(_), 1) => View::Account,
-        (Some(_), _) => View::Monitors,
-        (None, _) => View::default(),
-    };
-    let view = use_state(cx, || initial_view);
-    let onnav = move |new_view| {
-        to_owned![view];
-        view.set(new_view);
-    };
-    let add_site_sheet_shown = use_state(cx, || false);
-    let show_add_site_sheet = move |_| {
-        add_site_sheet_shown.set(!add_site_sheet_shown.get());
-    };
-    let user_id = match current_user {
-        Some(u) => u.id,
-        None => 0,
+#[derive(Serialize)]
+struct ChannelTest {
+    channel: NotificationChannel,
+    delivered: bool,
+    error: Option<AppError>,
+}
+
+/// Fires a test alert through the channel and reports whether it got
+/// delivered instead of failing the request.
+#[handler]
+async fn test_channel(depot: &mut Depot, req: &mut Request) -> Result<Json<ChannelTest>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let channel = db().channel_by_id(user.id, id).await?;
+    let error = dispatcher().test(&channel).await.err();
+    let channel = db().channel_by_id(user.id, id).await?;
+    Ok(Json(ChannelTest {
+        channel,
+        delivered: error.is_none(),
+        error,
+    }))
+}
+
+#[handler]
+async fn route_channel(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<i64>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site_id = req.param::<i64>("site_id").unwrap_or_default();
+    db().route_channel(user.id, id, site_id).await?;
+    let site_ids = db().routed_site_ids(id).await?;
+    Ok(Json(site_ids))
+}
+
+#[handler]
+async fn unroute_channel(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<i64>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site_id = req.param::<i64>("site_id").unwrap_or_default();
+    db().unroute_channel(user.id, id, site_id).await?;
+    let site_ids = db().routed_site_ids(id).await?;
+    Ok(Json(site_ids))
+}
+
+#[handler]
+async fn latency_rules(depot: &mut Depot) -> Result<Json<Vec<LatencyRule>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let rules = db().latency_rules_by_user_id(user.id).await?;
+    Ok(Json(rules))
+}
+
+#[derive(Serialize, Deserialize)]
+struct LatencyRuleParams {
+    site_id: i64,
+    percentile: i64,
+    window_secs: i64,
+    threshold_ms: i64,
+}
+
+#[handler]
+async fn add_latency_rule(depot: &mut Depot, req: &mut Request) -> Result<Json<LatencyRule>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let LatencyRuleParams {
+        site_id,
+        percentile,
+        window_secs,
+        threshold_ms,
+    } = req.parse_json::<LatencyRuleParams>().await?;
+    let mut rule = LatencyRule::default();
+    rule.user_id = user.id;
+    rule.site_id = site_id;
+    rule.percentile = percentile;
+    rule.window_secs = window_secs;
+    rule.threshold_ms = threshold_ms;
+    let rule = db().insert_latency_rule(rule).await?;
+    Ok(Json(rule))
+}
+
+#[handler]
+async fn delete_latency_rule(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<Vec<LatencyRule>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    db().delete_latency_rule(user.id, id).await?;
+    let rules = db().latency_rules_by_user_id(user.id).await?;
+    Ok(Json(rules))
+}
+
+#[handler]
+async fn expiry_rules(depot: &mut Depot) -> Result<Json<Vec<ExpiryRule>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let rules = db().expiry_rules_by_user_id(user.id).await?;
+    Ok(Json(rules))
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExpiryRuleParams {
+    site_id: i64,
+    kind: ExpiryKind,
+    days: i64,
+}
+
+#[handler]
+async fn add_expiry_rule(depot: &mut Depot, req: &mut Request) -> Result<Json<ExpiryRule>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let ExpiryRuleParams {
+        site_id,
+        kind,
+        days,
+    } = req.parse_json::<ExpiryRuleParams>().await?;
+    let rule = db()
+        .upsert_expiry_rule(user.id, site_id, kind, days)
+        .await?;
+    Ok(Json(rule))
+}
+
+#[handler]
+async fn delete_expiry_rule(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<ExpiryRule>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    db().delete_expiry_rule(user.id, id).await?;
+    let rules = db().expiry_rules_by_user_id(user.id).await?;
+    Ok(Json(rules))
+}
+
+#[derive(Serialize, Deserialize)]
+struct DigestParams {
+    digest: Frequency,
+}
+
+#[handler]
+async fn update_digest(depot: &mut Depot, req: &mut Request) -> Result<Json<DigestParams>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let DigestParams { digest } = req.parse_json::<DigestParams>().await?;
+    db().update_user_digest(user.id, digest).await?;
+    Ok(Json(DigestParams { digest }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct OrganizationParams {
+    name: String,
+}
+
+#[handler]
+async fn list_organizations(depot: &mut Depot) -> Result<Json<Vec<Organization>>> {
+    let user = person(depot).ok_or(AppError::Login)?;
+    let organizations = db().organizations_by_user_id(user.id).await?;
+    Ok(Json(organizations))
+}
+
+#[handler]
+async fn add_organization(depot: &mut Depot, req: &mut Request) -> Result<Json<Organization>> {
+    let user_id = person(depot).ok_or(AppError::Login)?.id;
+    let OrganizationParams { name } = req.parse_json::<OrganizationParams>().await?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Organization.into());
+    }
+    let organization = db().insert_organization(user_id, name).await?;
+    Ok(Json(organization))
+}
+
+#[derive(Serialize, Deserialize)]
+struct SwitchParams {
+    /// `None` goes back to the person's own account.
+    organization_id: Option<i64>,
+}
+
+/// Makes the session act for an organization the person is a member of.
+#[handler]
+async fn switch_organization(depot: &mut Depot, req: &mut Request) -> Result<Json<SwitchParams>> {
+    let user_id = person(depot).ok_or(AppError::Login)?.id;
+    let params = req.parse_json::<SwitchParams>().await?;
+    if let Some(organization_id) = params.organization_id {
+        db().membership(organization_id, user_id)
+            .await
+            .map_err(|_| AppError::Role)?;
+    }
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    match params.organization_id {
+        Some(organization_id) => _ = session.insert("organization_id", organization_id)?,
+        None => session.remove("organization_id"),
+    }
+    Ok(Json(params))
+}
+
+/// The person's membership when they own the organization.
+async fn owner_membership(depot: &Depot, organization_id: i64) -> Result<Membership, AppError> {
+    let user_id = person(depot).ok_or(AppError::Login)?.id;
+    match db().membership(organization_id, user_id).await {
+        Ok(membership) if membership.role == Role::Owner => Ok(membership),
+        _ => Err(AppError::Role),
+    }
+}
+
+#[handler]
+async fn delete_organization(depot: &mut Depot, req: &mut Request) -> Result<Json<Organization>> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    owner_membership(depot, id).await?;
+    let organization = db().delete_organization(id).await?;
+    if let Some(session) = depot.session_mut() {
+        if session.get::<i64>("organization_id") == Some(id) {
+            session.remove("organization_id");
+        }
+    }
+    Ok(Json(organization))
+}
+
+#[handler]
+async fn list_members(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<Membership>>> {
+    let user_id = person(depot).ok_or(AppError::Login)?.id;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    db().membership(id, user_id)
+        .await
+        .map_err(|_| AppError::Role)?;
+    let members = db().memberships_by_organization_id(id).await?;
+    Ok(Json(members))
+}
+
+#[derive(Serialize, Deserialize)]
+struct MemberParams {
+    role: Role,
+}
+
+#[handler]
+async fn update_member(depot: &mut Depot, req: &mut Request) -> Result<Json<Membership>> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let user_id = req.param::<i64>("user_id").unwrap_or_default();
+    let MemberParams { role } = req.parse_json::<MemberParams>().await?;
+    owner_membership(depot, id).await?;
+    let membership = db()
+        .update_membership_role(id, user_id, role)
+        .await
+        .map_err(|_| AppError::LastOwner)?;
+    Ok(Json(membership))
+}
+
+/// Owners remove members, anyone can remove themselves to leave.
+#[handler]
+async fn remove_member(depot: &mut Depot, req: &mut Request) -> Result<Json<Membership>> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let user_id = req.param::<i64>("user_id").unwrap_or_default();
+    if person(depot).map(|user| user.id) != Some(user_id) {
+        owner_membership(depot, id).await?;
+    }
+    let membership = db()
+        .delete_membership(id, user_id)
+        .await
+        .map_err(|_| AppError::LastOwner)?;
+    Ok(Json(membership))
+}
+
+#[derive(Serialize, Deserialize)]
+struct InvitationParams {
+    #[serde(default)]
+    role: Role,
+}
+
+#[derive(Serialize)]
+struct InvitationLink {
+    invitation: Invitation,
+    url: String,
+}
+
+fn invitation_url(invitation: &Invitation) -> String {
+    let token = Signer::new(&env().session_key).sign(&invitation.claims());
+    format!("{}/invites/{}", env().origin, token)
+}
+
+#[handler]
+async fn add_invitation(depot: &mut Depot, req: &mut Request) -> Result<Json<InvitationLink>> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let InvitationParams { role } = req.parse_json::<InvitationParams>().await?;
+    let membership = owner_membership(depot, id).await?;
+    let invitation = db().insert_invitation(id, role, membership.user_id).await?;
+    let url = invitation_url(&invitation);
+    Ok(Json(InvitationLink { invitation, url }))
+}
+
+#[handler]
+async fn list_invitations(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<Invitation>>> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    owner_membership(depot, id).await?;
+    let invitations = db().invitations_by_organization_id(id).await?;
+    Ok(Json(invitations))
+}
+
+#[handler]
+async fn revoke_invitation(depot: &mut Depot, req: &mut Request) -> Result<Json<i64>> {
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let invitation_id = req.param::<i64>("invitation_id").unwrap_or_default();
+    owner_membership(depot, id).await?;
+    db().delete_invitation(id, invitation_id).await?;
+    Ok(Json(invitation_id))
+}
+
+/// The invitation a signed invite link stands for, if it's still open.
+async fn verify_invitation(token: &str) -> Result<Invitation, AppError> {
+    let claims = Signer::new(&env().session_key).verify(token, Database::now())?;
+    if claims.action != Action::Invite {
+        return Err(AppError::TokenInvalid);
+    }
+    db().invitation_by_id(claims.subject_id)
+        .await
+        .map_err(|_| AppError::TokenUsed)
+}
+
+/// Joins the organization when logged in. Otherwise the link waits in
+/// the session until the person logs in or signs up.
+#[handler]
+async fn open_invitation(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    verify_invitation(&token).await?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    _ = session.insert("invitation", &token)?;
+    if let Some(user_id) = person(depot).map(|user| user.id) {
+        accept_pending_invitation(depot, user_id).await;
+    }
+    res.render(Redirect::found("/"));
+    Ok(())
+}
+
+/// Joins the organization of the pending invitation with a fresh account,
+/// for someone who doesn't have one yet.
+#[handler]
+async fn join_with_new_account(depot: &mut Depot, req: &mut Request) -> Result<Json<Login>> {
+    let token = depot
+        .session()
+        .and_then(|session| session.get::<String>("invitation"))
+        .ok_or(AppError::TokenInvalid)?;
+    verify_invitation(&token).await?;
+    let user = db().insert_user().await?;
+    let mut new_login: Login = Database::new_login(user.id);
+    new_login.ip_address = client_ip(req);
+    new_login.user_agent = req.header::<String>(USER_AGENT);
+    let login_row = db().insert_login(new_login).await?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    _ = session.insert("user_id", user.id)?;
+    accept_pending_invitation(depot, user.id).await;
+    Ok(Json(login_row))
+}
+
+/// Accepts the invitation waiting in the session, if any, and switches to
+/// its organization. Called once someone is logged in.
+async fn accept_pending_invitation(depot: &mut Depot, user_id: i64) {
+    let Some(session) = depot.session_mut() else {
+        return;
+    };
+    let Some(token) = session.get::<String>("invitation") else {
+        return;
+    };
+    session.remove("invitation");
+    let accepted = match verify_invitation(&token).await {
+        Ok(invitation) => db().accept_invitation(invitation.id, user_id).await.ok(),
+        Err(_) => None,
+    };
+    if let Some(membership) = accepted {
+        _ = session.insert("organization_id", membership.organization_id);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EmailParams {
+    email: Option<String>,
+}
+
+/// Sets the recovery email and mails a link to verify it. An empty email
+/// removes it.
+#[handler]
+async fn update_email(depot: &mut Depot, req: &mut Request) -> Result<Json<EmailParams>> {
+    let user = person(depot).ok_or(AppError::Login)?;
+    let EmailParams { email } = req.parse_json::<EmailParams>().await?;
+    let email = email
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty());
+    if let Some(email) = &email {
+        if email.parse::<lettre::Address>().is_err() {
+            return Err(AppError::Email.into());
+        }
+    }
+    db().update_user_email(user.id, email.as_deref()).await?;
+    if let Some(email) = &email {
+        send_email_verification(user.id, email).await?;
+    }
+    Ok(Json(EmailParams { email }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecoverParams {
+    email: String,
+}
+
+#[handler]
+async fn recover(req: &mut Request) -> Result<Json<RecoverParams>> {
+    let params = req.parse_json::<RecoverParams>().await?;
+    if let Err(err) = send_recovery_links(&params.email).await {
+        tracing::warn!("could not send recovery links: {}", err.chain());
+    }
+    Ok(Json(params))
+}
+
+/// Logs in from the link in a recovery email.
+#[handler]
+async fn recover_login(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let claims = Signer::new(&env().session_key).verify(&token, Database::now())?;
+    if claims.action != Action::Recover {
+        return Err(AppError::TokenInvalid.into());
+    }
+    db().consume_token(&claims).await?;
+    let user = db().user_by_id(claims.user_id).await?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    _ = session.insert("user_id", user.id)?;
+    accept_pending_invitation(depot, user.id).await;
+    let mut new_login: Login = Database::new_login(user.id);
+    new_login.ip_address = client_ip(req);
+    new_login.user_agent = req.header::<String>(USER_AGENT);
+    let seen = db().login_seen(&new_login).await.unwrap_or(true);
+    let login_row = db().insert_login(new_login).await?;
+    if !seen {
+        tokio::spawn(async move { dispatcher().new_login(&login_row).await });
+    }
+    res.render(Redirect::found("/"));
+    Ok(())
+}
+
+/// How long the link the liveview sends the browser to after a login or
+/// logout works for.
+const SESSION_LINK_SECS: i64 = 60;
+
+/// Signs the link that logs the browser in or out. The liveview talks over
+/// a websocket, so it can't set the session cookie itself and sends the
+/// browser here instead.
+fn session_link_url(action: Action, user_id: i64) -> String {
+    let claims = Claims::new(
+        action,
+        user_id,
+        user_id,
+        Database::now() + SESSION_LINK_SECS,
+    );
+    format!("/session/{}", Signer::new(&env().session_key).sign(&claims))
+}
+
+/// Finishes a login or logout started in the liveview.
+#[handler]
+async fn session_link(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let claims = Signer::new(&env().session_key).verify(&token, Database::now())?;
+    if !matches!(claims.action, Action::Login | Action::Logout) {
+        return Err(AppError::TokenInvalid.into());
+    }
+    db().consume_token(&claims).await?;
+    let session = depot.session_mut().ok_or(AppError::Login)?;
+    if claims.action == Action::Logout {
+        // only the person who clicked it is logged out
+        if session.get::<i64>("user_id") == Some(claims.user_id) {
+            session.remove("user_id");
+            session.remove("organization_id");
+        }
+        res.render(Redirect::found("/"));
+        return Ok(());
+    }
+    let user = db().user_by_id(claims.user_id).await?;
+    _ = session.insert("user_id", user.id)?;
+    accept_pending_invitation(depot, user.id).await;
+    let mut new_login: Login = Database::new_login(user.id);
+    new_login.ip_address = client_ip(req);
+    new_login.user_agent = req.header::<String>(USER_AGENT);
+    let seen = db().login_seen(&new_login).await.unwrap_or(true);
+    let login_row = db().insert_login(new_login).await?;
+    if !seen {
+        tokio::spawn(async move { dispatcher().new_login(&login_row).await });
+    }
+    res.render(Redirect::found("/"));
+    Ok(())
+}
+
+/// Checks a login code typed into the liveview like `login` does, counting
+/// failures against the address the socket came from.
+async fn check_login_code(ip_address: Option<&str>, login_code: &str) -> Result<User, AppError> {
+    let now = Database::now();
+    let failures = db()
+        .login_failures(ip_address, login_code, now - LoginFailures::WINDOW_SECS)
+        .await
+        .map_err(|source| AppError::DatabaseSelect { source })?;
+    if failures.retry_after(now).is_some() {
+        return Err(AppError::TooManyAttempts);
+    }
+    match db().user_by_login_code(login_code.to_string()).await {
+        Ok(user) => {
+            _ = db().clear_login_attempts(login_code).await;
+            Ok(user)
+        }
+        Err(_) => {
+            _ = db().insert_login_attempt(ip_address, login_code).await;
+            Err(AppError::Login)
+        }
+    }
+}
+
+/// Verifies the recovery email from the link mailed to it. The link only
+/// works while the account still has the address it was sent to.
+#[handler]
+async fn verify_email(req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let claims = Signer::new(&env().session_key).verify(&token, Database::now())?;
+    let user = db().user_by_id(claims.user_id).await?;
+    let current = user.email.as_deref().map(token::fingerprint);
+    if claims.action != Action::VerifyEmail || current != Some(claims.subject_id) {
+        return Err(AppError::TokenInvalid.into());
+    }
+    db().consume_token(&claims).await?;
+    db().verify_user_email(user.id).await?;
+    res.render(Text::Plain("Email verified"));
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct QuietHoursParams {
+    timezone: String,
+    quiet_start: Option<i64>,
+    quiet_end: Option<i64>,
+}
+
+#[handler]
+async fn update_quiet_hours(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<QuietHoursParams>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let params = req.parse_json::<QuietHoursParams>().await?;
+    let minutes = 0..24 * 60;
+    let valid = params.timezone.parse::<chrono_tz::Tz>().is_ok()
+        && [params.quiet_start, params.quiet_end]
+            .iter()
+            .flatten()
+            .all(|minute| minutes.contains(minute));
+    if !valid {
+        return Err(AppError::QuietHours.into());
+    }
+    db().update_quiet_hours(
+        user.id,
+        &params.timezone,
+        params.quiet_start,
+        params.quiet_end,
+    )
+    .await?;
+    Ok(Json(params))
+}
+
+#[derive(Serialize, Deserialize)]
+struct CriticalParams {
+    critical: bool,
+}
+
+#[derive(Deserialize)]
+struct SiteParams {
+    url: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    interval_secs: Option<i64>,
+    #[serde(default)]
+    failure_threshold: Option<i64>,
+}
+
+impl SiteParams {
+    fn site(self, user_id: i64) -> Result<Site, AppError> {
+        let mut site = Site::default();
+        site.user_id = user_id;
+        site.url = Site::normalize_url(&self.url)?;
+        site.name = self.name.filter(|name| !name.trim().is_empty());
+        site.interval_secs = self.interval_secs.unwrap_or(Site::INTERVALS[0]);
+        site.failure_threshold = self.failure_threshold.unwrap_or(1);
+        site.validate()?;
+        // left out, the site gets the account's default interval
+        site.interval_secs = self.interval_secs.unwrap_or_default();
+        Ok(site)
+    }
+}
+
+#[handler]
+async fn list_sites(depot: &mut Depot, req: &mut Request) -> Result<Json<Page<Site>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let cursor = req.query::<i64>("cursor");
+    let limit = Page::<Site>::limit(req.query::<i64>("limit"));
+    let page = db().sites_page(user.id, cursor, limit).await?;
+    Ok(Json(page))
+}
+
+#[handler]
+async fn site_checks(depot: &mut Depot, req: &mut Request) -> Result<Json<Page<Check>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let cursor = req.query::<i64>("cursor");
+    let limit = Page::<Check>::limit(req.query::<i64>("limit"));
+    let page = db().checks_page(user.id, id, cursor, limit).await?;
+    Ok(Json(page))
+}
+
+#[derive(Deserialize)]
+struct TagParams {
+    name: String,
+}
+
+#[handler]
+async fn list_tags(depot: &mut Depot) -> Result<Json<Vec<Tag>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let tags = db().tags_by_user_id(user.id).await?;
+    Ok(Json(tags))
+}
+
+/// Takes a csv file, an Uptime Kuma backup or a json export as the body.
+#[handler]
+async fn import_sites(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let input = String::from_utf8_lossy(req.payload().await?).to_string();
+    match import::run(db(), user.id, &input).await {
+        Ok(report) => {
+            emit_sites_created(&report.imported);
+            res.render(Json(report));
+        }
+        Err(err @ AppError::ImportFormat) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+/// Sends the user's data as a json download.
+#[handler]
+async fn export(depot: &mut Depot, res: &mut Response) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let user_data = db().export_user(user.id).await?;
+    res.add_header(
+        CONTENT_DISPOSITION,
+        "attachment; filename=\"updown.json\"",
+        true,
+    )?;
+    res.render(Json(user_data));
+    Ok(())
+}
+
+#[handler]
+async fn tagged_sites(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<Site>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let name = req.param::<String>("name").unwrap_or_default();
+    let sites = db().sites_by_tag(user.id, &name).await?;
+    Ok(Json(sites))
+}
+
+#[handler]
+async fn tag_site(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let TagParams { name } = req.parse_json::<TagParams>().await?;
+    match db().tag_site(user.id, id, &name).await {
+        Ok(tag) => res.render(Json(tag)),
+        Err(AppError::TagName) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(AppError::TagName));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn untag_site(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<Tag>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let tag_id = req.param::<i64>("tag_id").unwrap_or_default();
+    db().untag_site(user.id, id, tag_id).await?;
+    let tags = db().tags_by_user_id(user.id).await?;
+    Ok(Json(tags))
+}
+
+#[handler]
+async fn add_site(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let site = match req.parse_json::<SiteParams>().await?.site(user.id) {
+        Ok(site) => site,
+        Err(err) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+            return Ok(());
+        }
+    };
+    match db().insert_site(site).await {
+        Ok(site) => {
+            emit_sites_created(std::slice::from_ref(&site));
+            res.render(Json(site));
+        }
+        Err(AppError::DuplicateSite) => {
+            res.set_status_code(StatusCode::CONFLICT);
+            res.render(Json(AppError::DuplicateSite));
+        }
+        Err(AppError::SiteLimit) => {
+            res.set_status_code(StatusCode::FORBIDDEN);
+            res.render(Json(AppError::SiteLimit));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn update_site(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let mut site = match req.parse_json::<SiteParams>().await?.site(user.id) {
+        Ok(site) => site,
+        Err(err) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+            return Ok(());
+        }
+    };
+    site.id = id;
+    match db().update_site(site).await {
+        Ok(site) => res.render(Json(site)),
+        Err(AppError::DuplicateSite) => {
+            res.set_status_code(StatusCode::CONFLICT);
+            res.render(Json(AppError::DuplicateSite));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn delete_site(depot: &mut Depot, req: &mut Request) -> Result<Json<Site>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site = db().delete_site(user.id, id).await?;
+    Ok(Json(site))
+}
+
+#[handler]
+async fn update_critical(depot: &mut Depot, req: &mut Request) -> Result<Json<CriticalParams>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let CriticalParams { critical } = req.parse_json::<CriticalParams>().await?;
+    db().set_site_critical(user.id, id, critical).await?;
+    Ok(Json(CriticalParams { critical }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct MaintenanceParams {
+    until: Option<i64>,
+}
+
+#[handler]
+async fn update_maintenance(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<MaintenanceParams>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let MaintenanceParams { until } = req.parse_json::<MaintenanceParams>().await?;
+    db().set_site_maintenance(user.id, id, until).await?;
+    Ok(Json(MaintenanceParams { until }))
+}
+
+#[derive(Deserialize)]
+struct MaintenanceWindowParams {
+    title: String,
+    starts_at: i64,
+    ends_at: i64,
+}
+
+/// The windows that haven't ended yet, soonest first.
+#[handler]
+async fn list_maintenance_windows(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<Vec<MaintenanceWindow>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site = db().site_by_id(id).await?;
+    if site.user_id != user.id {
+        return Err(AppError::DatabaseSelect {
+            source: sqlx::Error::RowNotFound,
+        }
+        .into());
+    }
+    let windows = db().maintenance_windows(site.id, Database::now()).await?;
+    Ok(Json(windows))
+}
+
+#[handler]
+async fn add_maintenance_window(
+    depot: &mut Depot,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let params = req.parse_json::<MaintenanceWindowParams>().await?;
+    match db()
+        .insert_maintenance_window(user.id, id, &params.title, params.starts_at, params.ends_at)
+        .await
+    {
+        Ok(window) => res.render(Json(window)),
+        Err(err @ AppError::MaintenanceWindow) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn delete_maintenance_window(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<MaintenanceWindow>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    Ok(Json(db().delete_maintenance_window(user.id, id).await?))
+}
+
+#[handler]
+async fn site_uptime(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<Uptime>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site = db().site_by_id(id).await?;
+    if site.user_id != user.id {
+        return Err(AppError::DatabaseSelect {
+            source: sqlx::Error::RowNotFound,
+        }
+        .into());
+    }
+    let mut uptimes = vec![];
+    for window_secs in Database::UPTIME_WINDOWS {
+        uptimes.push(db().uptime_by_site(site.id, window_secs).await?);
+    }
+    Ok(Json(uptimes))
+}
+
+/// The site's cached favicon, or a lettered placeholder when it has none.
+#[handler]
+async fn site_icon(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site = db().site_by_id(id).await?;
+    if site.user_id != user.id {
+        return Err(AppError::DatabaseSelect {
+            source: sqlx::Error::RowNotFound,
+        }
+        .into());
+    }
+    res.add_header(CACHE_CONTROL, "private, max-age=86400", true)?;
+    // the icon came from someone else's server, opened on its own it
+    // mustn't run anything on this origin
+    res.add_header(CONTENT_SECURITY_POLICY, "sandbox", true)?;
+    res.add_header(X_CONTENT_TYPE_OPTIONS, "nosniff", true)?;
+    match db().site_icon(site.id).await? {
+        Some(SiteIcon {
+            content_type: Some(content_type),
+            data: Some(data),
+            ..
+        }) => {
+            res.add_header(CONTENT_TYPE, content_type, true)?;
+            res.write_body(data)?;
+        }
+        _ => {
+            res.add_header(CONTENT_TYPE, "image/svg+xml", true)?;
+            res.write_body(favicon::placeholder(site.display_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ShareParams {
+    /// Days until the link stops working, forever when left out.
+    expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct SharedLink {
+    share_link: ShareLink,
+    url: String,
+}
+
+fn share_token(share_link: &ShareLink) -> String {
+    Signer::new(&env().session_key).sign(&share_link.claims())
+}
+
+fn share_url(share_link: &ShareLink) -> String {
+    format!("{}/share/{}", env().origin, share_token(share_link))
+}
+
+#[handler]
+async fn add_share_link(depot: &mut Depot, req: &mut Request) -> Result<Json<SharedLink>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let ShareParams { expires_in_days } = req.parse_json::<ShareParams>().await?;
+    let expires_at = expires_in_days
+        .filter(|days| *days > 0)
+        .map(|days| Database::now() + days * 86_400);
+    let share_link = db().insert_share_link(user.id, id, expires_at).await?;
+    let url = share_url(&share_link);
+    Ok(Json(SharedLink { share_link, url }))
+}
+
+#[handler]
+async fn list_share_links(depot: &mut Depot, req: &mut Request) -> Result<Json<Vec<SharedLink>>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let share_links = db()
+        .share_links_by_site_id(user.id, id)
+        .await?
+        .into_iter()
+        .map(|share_link| SharedLink {
+            url: share_url(&share_link),
+            share_link,
+        })
+        .collect();
+    Ok(Json(share_links))
+}
+
+#[derive(Deserialize)]
+struct DomainParams {
+    /// `None` takes the domain away.
+    domain: Option<String>,
+}
+
+/// Points a custom domain at the share link's page. The domain needs a
+/// DNS record pointing at this instance.
+#[handler]
+async fn update_share_domain(
+    depot: &mut Depot,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let share_id = req.param::<i64>("share_id").unwrap_or_default();
+    let DomainParams { domain } = req.parse_json::<DomainParams>().await?;
+    let domain = match domain
+        .as_deref()
+        .map(str::trim)
+        .filter(|domain| !domain.is_empty())
+    {
+        Some(domain) => match ShareLink::normalize_domain(domain) {
+            Some(domain) if Some(domain.as_str()) != origin_host().as_deref() => Some(domain),
+            _ => {
+                res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+                res.render(Json(AppError::Domain));
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    match db()
+        .update_share_link_domain(user.id, share_id, domain.as_deref())
+        .await
+    {
+        Ok(share_link) => res.render(Json(SharedLink {
+            url: share_url(&share_link),
+            share_link,
+        })),
+        Err(AppError::DuplicateDomain) => {
+            res.set_status_code(StatusCode::CONFLICT);
+            res.render(Json(AppError::DuplicateDomain));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn revoke_share_link(depot: &mut Depot, req: &mut Request) -> Result<Json<i64>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let share_id = req.param::<i64>("share_id").unwrap_or_default();
+    db().delete_share_link(user.id, share_id).await?;
+    Ok(Json(share_id))
+}
+
+#[derive(Deserialize)]
+struct IncidentParams {
+    /// Only for a new incident.
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    status: IncidentStatus,
+    message: String,
+}
+
+#[handler]
+async fn list_status_incidents(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<Vec<IncidentTimeline>>> {
+    const LIMIT: i64 = 50;
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let site = db().site_by_id(id).await?;
+    if site.user_id != user.id {
+        return Err(AppError::DatabaseSelect {
+            source: sqlx::Error::RowNotFound,
+        }
+        .into());
+    }
+    Ok(Json(db().incident_timelines(site.id, LIMIT).await?))
+}
+
+/// Publishes an incident on the site's share pages, apart from the ones
+/// checks open.
+#[handler]
+async fn add_status_incident(
+    depot: &mut Depot,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let params = req.parse_json::<IncidentParams>().await?;
+    match db()
+        .insert_status_incident(user.id, id, &params.title, params.status, &params.message)
+        .await
+    {
+        Ok(timeline) => {
+            notify_incident_subscribers(&timeline);
+            res.render(Json(timeline));
+        }
+        Err(err @ AppError::StatusIncident) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+/// Adds to the incident's timeline, a `resolved` update resolves it.
+/// Comes back as a timeline with just the new update.
+#[handler]
+async fn add_incident_update(
+    depot: &mut Depot,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<()> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    let params = req.parse_json::<IncidentParams>().await?;
+    match db()
+        .insert_incident_update(user.id, id, params.status, &params.message)
+        .await
+    {
+        Ok(timeline) => {
+            notify_incident_subscribers(&timeline);
+            res.render(Json(timeline));
+        }
+        Err(err @ AppError::StatusIncident) => {
+            res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+            res.render(Json(err));
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn delete_status_incident(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<Json<StatusIncident>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let id = req.param::<i64>("id").unwrap_or_default();
+    Ok(Json(db().delete_status_incident(user.id, id).await?))
+}
+
+/// The share link a token is for, as long as it wasn't revoked.
+async fn share_link_by_token(token: &str) -> Result<ShareLink> {
+    let claims = Signer::new(&env().session_key).verify(token, Database::now())?;
+    if claims.action != Action::Share {
+        return Err(AppError::TokenInvalid.into());
+    }
+    Ok(db()
+        .share_link_by_id(claims.subject_id)
+        .await
+        .map_err(|_| AppError::TokenInvalid)?)
+}
+
+/// The site a share link token points at, as long as the link wasn't
+/// revoked.
+async fn shared_site_by_token(token: &str) -> Result<Site> {
+    let share_link = share_link_by_token(token).await?;
+    Ok(db().site_by_id(share_link.site_id).await?)
+}
+
+/// One site's status, uptime and latency for someone without an account.
+/// The signed link is the credential and stops working once revoked.
+#[handler]
+async fn shared_site(req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let share_link = share_link_by_token(&token).await?;
+    render_shared_site(&share_link, res).await
+}
+
+/// The site's maintenance windows as a calendar to subscribe to, with the
+/// ones that ended in the last month so they don't vanish right away.
+#[handler]
+async fn maintenance_calendar(req: &mut Request, res: &mut Response) -> Result<()> {
+    const PAST_SECS: i64 = 30 * 24 * 60 * 60;
+    let token = req.param::<String>("token").unwrap_or_default();
+    let site = shared_site_by_token(&token).await?;
+    let now = Database::now();
+    let windows = db().maintenance_windows(site.id, now - PAST_SECS).await?;
+    let host = origin_host().unwrap_or_else(|| "updown".to_string());
+    let calendar = ical::maintenance_calendar(site.display_name(), &host, &windows, now);
+    res.add_header(CONTENT_TYPE, "text/calendar; charset=utf-8", true)?;
+    res.add_header(CACHE_CONTROL, "public, max-age=300", true)?;
+    res.write_body(calendar)?;
+    Ok(())
+}
+
+/// Takes the email from the form on a share page and mails it a link to
+/// confirm, so nobody can sign someone else up.
+#[handler]
+async fn subscribe(req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let share_link = share_link_by_token(&token).await?;
+    let email = req.form::<String>("email").await.unwrap_or_default();
+    let email = email.trim();
+    if email.parse::<lettre::Address>().is_err() {
+        res.set_status_code(StatusCode::UNPROCESSABLE_ENTITY);
+        res.render(Text::Plain("That doesn't look like an email address"));
+        return Ok(());
+    }
+    let mailer = mailer().ok_or(AppError::Notify)?;
+    let site = db().site_by_id(share_link.site_id).await?;
+    let subscriber = db().insert_subscriber(&share_link, email).await?;
+    if subscriber.confirmed_at.is_none() {
+        subscribers::send_confirmation(
+            &mailer,
+            &Signer::new(&env().session_key),
+            &env().origin,
+            &subscriber,
+            site.display_name(),
+        )
+        .await?;
+    }
+    res.render(Text::Plain("Check your email for a link to confirm"));
+    Ok(())
+}
+
+/// The link from the confirmation email.
+#[handler]
+async fn confirm_subscriber(req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let claims = Signer::new(&env().session_key).verify(&token, Database::now())?;
+    if claims.action != Action::Confirm {
+        return Err(AppError::TokenInvalid.into());
+    }
+    db().confirm_subscriber(claims.subject_id)
+        .await
+        .map_err(|_| AppError::TokenInvalid)?;
+    res.render(Text::Plain("You'll get an email when there's an incident"));
+    Ok(())
+}
+
+/// The link at the bottom of every incident email. Following it twice is
+/// fine, the second time there's nothing left to delete.
+#[handler]
+async fn unsubscribe(req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let claims = Signer::new(&env().session_key).verify(&token, Database::now())?;
+    if claims.action != Action::Unsubscribe {
+        return Err(AppError::TokenInvalid.into());
+    }
+    db().delete_subscriber(claims.subject_id).await?;
+    res.render(Text::Plain("You won't get any more emails about this site"));
+    Ok(())
+}
+
+/// Emails the latest update of a published incident.
+fn notify_incident_subscribers(timeline: &IncidentTimeline) {
+    let Some(update) = timeline.updates.first() else {
+        return;
+    };
+    notify_subscribers(
+        timeline.incident.site_id,
+        format!(
+            "{}: {}",
+            timeline.incident.title,
+            timeline.incident.status.label()
+        ),
+        update.message.clone(),
+    );
+}
+
+/// Emails the site's share page subscribers in the background, when
+/// there's SMTP to send with.
+fn notify_subscribers(site_id: i64, subject: String, message: String) {
+    let Some(mailer) = mailer() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let signer = Signer::new(&env().session_key);
+        subscribers::notify(
+            db(),
+            &mailer,
+            &signer,
+            &env().origin,
+            site_id,
+            &subject,
+            &message,
+        )
+        .await
+    });
+}
+
+/// The host a request was made to, without the port.
+fn request_host(req: &Request) -> Option<String> {
+    let host = req.header::<String>(HOST)?;
+    let host = host
+        .rsplit_once(':')
+        .map_or(host.as_str(), |(host, _)| host);
+    Some(host.to_lowercase())
+}
+
+/// Serves the share page at `/` for requests to a share link's custom
+/// domain. Everything else on that host, like the stylesheet, falls
+/// through to the usual routes.
+#[handler]
+async fn custom_domain_handler(req: &mut Request, res: &mut Response, ctrl: &mut FlowCtrl) {
+    let Some(host) = request_host(req) else {
+        return;
+    };
+    if Some(host.as_str()) == origin_host().as_deref() || req.uri().path() != "/" {
+        return;
+    }
+    let share_link = match db().share_link_by_domain(&host, Database::now()).await {
+        Ok(share_link) => share_link,
+        Err(sqlx::Error::RowNotFound) => return,
+        Err(err) => {
+            tracing::error!("could not look up custom domain {}: {}", host, err);
+            return;
+        }
+    };
+    if let Err(err) = render_shared_site(&share_link, res).await {
+        tracing::error!("could not render custom domain {}: {}", host, err);
+        res.set_status_code(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    ctrl.skip_rest();
+}
+
+fn origin_host() -> Option<String> {
+    reqwest::Url::parse(&env().origin)
+        .ok()?
+        .host_str()
+        .map(str::to_lowercase)
+}
+
+/// Latencies as polyline points on a 100 by 30 canvas with the slowest
+/// on top, and that slowest latency.
+fn latency_polyline(latencies: &[i64]) -> (String, i64) {
+    let slowest = latencies.iter().copied().max().unwrap_or(1).max(1);
+    let step = 100.0 / (latencies.len().max(2) - 1) as f64;
+    let points = latencies
+        .iter()
+        .enumerate()
+        .map(|(i, latency)| {
+            let y = 30.0 - 30.0 * *latency as f64 / slowest as f64;
+            format!("{:.1},{:.1}", i as f64 * step, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    (points, slowest)
+}
+
+async fn render_shared_site(share_link: &ShareLink, res: &mut Response) -> Result<()> {
+    const CHECKS: i64 = 100;
+    const TIMELINES: i64 = 5;
+    let site = db().site_by_id(share_link.site_id).await?;
+    let subscribe_path = format!("/share/{}/subscribe", share_token(share_link));
+    let subscribable = env().smtp_url.is_some() && env().mail_from.is_some();
+    let open_incident = db().open_incident_by_site(site.id).await?;
+    let mut uptimes = vec![];
+    for window_secs in Database::UPTIME_WINDOWS {
+        uptimes.push(db().uptime_by_site(site.id, window_secs).await?);
+    }
+    let mut checks = db().recent_checks(site.id, CHECKS).await?;
+    checks.reverse();
+    let timelines = db().incident_timelines(site.id, TIMELINES).await?;
+    let now = Database::now();
+    let status = match (&open_incident, checks.last()) {
+        (Some(incident), _) => format!(
+            "Down for {}",
+            notifications::format_duration(Database::now() - incident.started_at)
+        ),
+        (None, Some(_)) => "Up".to_string(),
+        (None, None) => "Not checked yet".to_string(),
+    };
+    let latencies = checks
+        .iter()
+        .map(|check| check.latency_ms)
+        .collect::<Vec<_>>();
+    let (points, slowest) = latency_polyline(&latencies);
+    let name = site.display_name().to_string();
+    let body = dioxus_ssr::render_lazy(rsx! {
+        div {
+            class: "flex flex-col gap-4 max-w-md mx-auto pt-16 px-4",
+            h1 { class: "text-2xl", "{name}" }
+            p { class: "text-sm text-gray-500 dark:text-gray-400 break-all", "{site.url}" }
+            p { class: "text-lg", "{status}" }
+            div {
+                class: "grid grid-cols-4 gap-2 text-center",
+                uptimes.iter().map(|uptime| {
+                    let days = uptime.window_secs / 86_400;
+                    let percentage = uptime
+                        .percentage()
+                        .map(|percentage| format!("{:.2}%", percentage))
+                        .unwrap_or("-".to_string());
+                    rsx! {
+                        div {
+                            p { class: "text-lg", "{percentage}" }
+                            p { class: "text-xs text-gray-500 dark:text-gray-400", "{days}d" }
+                        }
+                    }
+                })
+            }
+            p { class: "text-xs text-gray-500 dark:text-gray-400", "Latency of the last {checks.len()} checks, up to {slowest}ms" }
+            svg {
+                class: "w-full h-24 text-cyan-400",
+                view_box: "0 0 100 30",
+                preserve_aspect_ratio: "none",
+                polyline {
+                    points: "{points}",
+                    fill: "none",
+                    stroke: "currentColor",
+                    stroke_width: "0.5"
+                }
+            }
+            timelines.iter().map(|timeline| rsx! {
+                div {
+                    class: "flex flex-col gap-2 pt-4",
+                    h2 { class: "text-lg", "{timeline.incident.title}" }
+                    timeline.updates.iter().map(|update| {
+                        let ago = notifications::format_duration(now - update.created_at);
+                        rsx! {
+                            div {
+                                class: "border-l-2 border-cyan-400 pl-2",
+                                p { class: "text-xs text-gray-500 dark:text-gray-400", "{update.status.label()}, {ago} ago" }
+                                p { class: "text-sm whitespace-pre-wrap", "{update.message}" }
+                            }
+                        }
+                    })
+                }
+            })
+            subscribable.then(|| rsx! {
+                form {
+                    method: "post",
+                    action: "{subscribe_path}",
+                    class: "flex gap-2 pt-4",
+                    input {
+                        class: "grow rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                        r#type: "email",
+                        name: "email",
+                        required: true,
+                        placeholder: "Email me about incidents"
+                    }
+                    button {
+                        class: "px-4 py-3 bg-cyan-400 text-white rounded-3xl",
+                        "Subscribe"
+                    }
+                }
+            })
+        }
+    });
+    res.render(Text::Html(format!(
+        r#"
+            <!DOCTYPE html>
+            <html lang=en class="h-full">
+                <head>
+                    <meta charset="utf-8">
+                    <meta content="width=device-width, initial-scale=1" name="viewport">
+                    <meta name="robots" content="noindex">
+                    <title>{name} - updown</title>
+                    {tailwind_css}
+                </head>
+                <body class="h-full dark:bg-gray-950 bg-gray-50 dark:text-white text-gray-900">
+                    {body}
+                </body>
+            </html>
+        "#,
+        tailwind_css = tailwind_css(),
+        name = name
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+    )));
+    Ok(())
+}
+
+#[handler]
+async fn transfer_site(depot: &mut Depot, req: &mut Request) -> Result<Json<SiteTransfer>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let site_id = req.param::<i64>("id").unwrap_or_default();
+    let transfer = db().insert_site_transfer(user.id, site_id).await?;
+    Ok(Json(transfer))
+}
+
+#[derive(Serialize, Deserialize)]
+struct TransferParams {
+    token: String,
+}
+
+#[handler]
+async fn accept_transfer(depot: &mut Depot, req: &mut Request) -> Result<Json<Site>> {
+    let user = depot.obtain::<User>().ok_or(AppError::Login)?;
+    let TransferParams { token } = req.parse_json::<TransferParams>().await?;
+    let site = db().accept_site_transfer(user.id, token).await?;
+    Ok(Json(site))
+}
+
+/// Whether the request came from a page on this instance. Browsers send
+/// `Origin` with every request that isn't a GET, older ones at least a
+/// `Referer`.
+fn same_origin(req: &Request) -> Option<bool> {
+    let origin = &env().origin;
+    if let Some(header) = req.header::<String>(ORIGIN) {
+        return Some(&header == origin);
+    }
+    let referer = req.header::<String>(REFERER)?;
+    Some(
+        referer == *origin
+            || referer
+                .strip_prefix(origin.as_str())
+                .is_some_and(|rest| rest.starts_with('/')),
+    )
+}
+
+/// Rejects requests that change something when they come from another
+/// site, so a page elsewhere can't ride the session cookie. Requests
+/// without `Origin` or `Referer` aren't from a browser and go through.
+#[handler]
+async fn csrf_handler(req: &mut Request, res: &mut Response, ctrl: &mut FlowCtrl) {
+    if [Method::GET, Method::HEAD, Method::OPTIONS].contains(req.method()) {
+        return;
+    }
+    if same_origin(req) == Some(false) {
+        res.set_status_code(StatusCode::FORBIDDEN);
+        res.render(Json(AppError::CrossOrigin));
+        ctrl.skip_rest();
+    }
+}
+
+/// The logged in person while they act for an organization. The depot's
+/// `User` is then the organization's account, so handlers work on its
+/// sites and channels.
+#[derive(Clone, PartialEq)]
+struct OrganizationSession {
+    member: User,
+    organization: Organization,
+    role: Role,
+}
+
+#[handler]
+async fn set_current_user_handler(depot: &mut Depot) {
+    let maybe_id: Option<i64> = depot.session().unwrap().get("user_id");
+    let organization_id: Option<i64> = depot.session().unwrap().get("organization_id");
+    let Some(id) = maybe_id else {
+        return;
+    };
+    let Ok(user) = db().user_by_id(id).await else {
+        return;
+    };
+    if let Some(organization_id) = organization_id {
+        if let Ok(organization_session) = organization_session(user.clone(), organization_id).await
+        {
+            if let Ok(account) = db()
+                .user_by_id(organization_session.organization.user_id)
+                .await
+            {
+                depot.inject(organization_session);
+                depot.inject(account);
+                return;
+            }
+        }
+    }
+    // no organization, or no longer a member of it
+    depot.inject(user);
+}
+
+async fn organization_session(
+    member: User,
+    organization_id: i64,
+) -> Result<OrganizationSession, sqlx::Error> {
+    let membership = db().membership(organization_id, member.id).await?;
+    let organization = db().organization_by_id(organization_id).await?;
+    Ok(OrganizationSession {
+        member,
+        organization,
+        role: membership.role,
+    })
+}
+
+/// The person logged in, who isn't the depot's `User` while they act for
+/// an organization.
+fn person(depot: &Depot) -> Option<&User> {
+    match depot.obtain::<OrganizationSession>() {
+        Some(organization_session) => Some(&organization_session.member),
+        None => depot.obtain::<User>(),
+    }
+}
+
+/// Routes about the person rather than the account they act for.
+const PERSONAL_PATHS: [&str; 5] = [
+    "/logout",
+    "/organizations",
+    "/passkeys",
+    "/account/email",
+    "/api/account",
+];
+
+/// Viewers can look but not change anything, apart from what's their own
+/// like logging out or switching organizations.
+#[handler]
+async fn role_handler(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    ctrl: &mut FlowCtrl,
+) {
+    let Some(organization_session) = depot.obtain::<OrganizationSession>() else {
+        return;
+    };
+    let path = req.uri().path();
+    let personal = PERSONAL_PATHS.iter().any(|prefix| path.starts_with(prefix));
+    if organization_session.role.can_edit()
+        || personal
+        || [Method::GET, Method::HEAD, Method::OPTIONS].contains(req.method())
+    {
+        return;
+    }
+    res.set_status_code(StatusCode::FORBIDDEN);
+    res.render(Json(AppError::Role));
+    ctrl.skip_rest();
+}
+
+/// One-click acknowledgement from the link in a notification. The token
+/// is the credential here so there's no session involved.
+#[handler]
+async fn acknowledge(req: &mut Request, res: &mut Response) -> Result<()> {
+    let token = req.param::<String>("token").unwrap_or_default();
+    let claims = Signer::new(&env().session_key).verify(&token, Database::now())?;
+    if claims.action != Action::Acknowledge {
+        return Err(AppError::TokenInvalid.into());
+    }
+    db().consume_token(&claims).await?;
+    db().acknowledge_incident(claims.user_id, claims.subject_id)
+        .await?;
+    res.render(Text::Plain("Incident acknowledged"));
+    Ok(())
+}
+
+/// What `/status/<token>.json` tells widgets about a site.
+#[derive(Serialize)]
+struct PublicStatus {
+    name: String,
+    url: String,
+    /// `up`, `down`, `disabled`, or `unknown` before the first check.
+    state: &'static str,
+    last_checked_at: Option<i64>,
+    down_since: Option<i64>,
+    uptime_24h: Option<f64>,
+}
+
+impl PublicStatus {
+    async fn new(site: &Site) -> Result<Self> {
+        let open_incident = db().open_incident_by_site(site.id).await?;
+        let last_check = db().recent_checks(site.id, 1).await?.pop();
+        let state = match (site.disabled_at, &open_incident, &last_check) {
+            (Some(_), _, _) => "disabled",
+            _ if site.paused_at.is_some() => "paused",
+            (None, Some(_), _) => "down",
+            (None, None, Some(_)) => "up",
+            (None, None, None) => "unknown",
+        };
+        let uptime = db().uptime_by_site(site.id, 24 * 60 * 60).await?;
+        Ok(Self {
+            name: site.display_name().to_string(),
+            url: site.url.clone(),
+            state,
+            last_checked_at: last_check.map(|check| check.created_at),
+            down_since: open_incident.map(|incident| incident.started_at),
+            uptime_24h: uptime.percentage(),
+        })
+    }
+}
+
+/// One site's status as json for widgets, `/status/<share token>.json`.
+/// Like the badge it only needs the share link, not an account.
+#[handler]
+async fn status_json(req: &mut Request, res: &mut Response) -> Result<()> {
+    let file = req.param::<String>("file").unwrap_or_default();
+    let token = file.strip_suffix(".json").ok_or(AppError::TokenInvalid)?;
+    let site = shared_site_by_token(token).await?;
+    let status = PublicStatus::new(&site).await?;
+    res.add_header(ACCESS_CONTROL_ALLOW_ORIGIN, "*", true)?;
+    res.add_header(CACHE_CONTROL, "public, max-age=30", true)?;
+    res.render(Json(status));
+    Ok(())
+}
+
+/// What `/widget/<token>.json` tells the embeddable widget, one pill
+/// with a color and a sentence.
+#[derive(Serialize)]
+struct WidgetStatus {
+    /// `operational`, `partial`, `major` or `unknown`.
+    indicator: &'static str,
+    description: String,
+    /// The share page the pill links to.
+    url: String,
+}
+
+impl WidgetStatus {
+    async fn new(share_link: &ShareLink) -> Result<Self> {
+        let site = db().site_by_id(share_link.site_id).await?;
+        let open_incident = db().open_incident_by_site(site.id).await?;
+        let status_incident = db().open_status_incident(site.id).await?;
+        let checked = !db().recent_checks(site.id, 1).await?.is_empty();
+        let (indicator, description) = match (&open_incident, &status_incident) {
+            (Some(incident), _) if !incident.flapping => ("major", "Major outage".to_string()),
+            (Some(_), _) => ("partial", "Partial outage".to_string()),
+            (None, Some(incident)) => ("partial", incident.title.clone()),
+            _ if site.disabled_at.is_some() || site.paused_at.is_some() || !checked => {
+                ("unknown", "Status unknown".to_string())
+            }
+            _ => ("operational", "All systems operational".to_string()),
+        };
+        Ok(Self {
+            indicator,
+            description,
+            url: share_url(share_link),
+        })
+    }
+}
+
+/// The json behind `widget.js`. It's the same for everyone with the
+/// token, so caches in front of the instance can hold it for a minute.
+#[handler]
+async fn widget_json(req: &mut Request, res: &mut Response) -> Result<()> {
+    let file = req.param::<String>("file").unwrap_or_default();
+    let token = file.strip_suffix(".json").ok_or(AppError::TokenInvalid)?;
+    let share_link = share_link_by_token(token).await?;
+    let status = WidgetStatus::new(&share_link).await?;
+    res.add_header(ACCESS_CONTROL_ALLOW_ORIGIN, "*", true)?;
+    res.add_header(
+        CACHE_CONTROL,
+        "public, max-age=60, s-maxage=60, stale-while-revalidate=300",
+        true,
+    )?;
+    res.render(Json(status));
+    Ok(())
+}
+
+/// A status badge for READMEs, `/badge/<share token>.svg`. Add
+/// `?uptime=30d` to show the uptime over that window instead of up or
+/// down. The ETag lets image proxies skip unchanged badges.
+#[handler]
+async fn status_badge(req: &mut Request, res: &mut Response) -> Result<()> {
+    let file = req.param::<String>("file").unwrap_or_default();
+    let token = file.strip_suffix(".svg").ok_or(AppError::TokenInvalid)?;
+    let site = shared_site_by_token(token).await?;
+    let status = PublicStatus::new(&site).await?;
+    let color = match status.state {
+        "up" => badge::GREEN,
+        "down" => badge::RED,
+        _ => badge::GREY,
+    };
+    let mut message = status.state.to_string();
+    if let Some(window) = req.query::<String>("uptime") {
+        let secs = api::window_secs(&window).ok_or(AppError::QueryParams)?;
+        let uptime = db().uptime_by_site(site.id, secs).await?;
+        if let Some(percentage) = uptime.percentage() {
+            message = format!("{:.2}%", percentage);
+        }
+    }
+    let label = req
+        .query::<String>("label")
+        .unwrap_or_else(|| site.display_name().to_string());
+    let svg = badge::svg(&label, &message, color);
+    let etag = format!("\"{}\"", &token::hash(&svg)[..16]);
+    res.add_header(ETAG, &etag, true)?;
+    res.add_header(CACHE_CONTROL, "public, max-age=60", true)?;
+    if req.header::<String>(IF_NONE_MATCH).as_deref() == Some(etag.as_str()) {
+        res.set_status_code(StatusCode::NOT_MODIFIED);
+        return Ok(());
+    }
+    res.add_header(CONTENT_TYPE, "image/svg+xml", true)?;
+    res.write_body(svg)?;
+    Ok(())
+}
+
+/// Prometheus style gauges for the most recent watch cycle. The watcher
+/// runs in its own process so this reads them back from the database.
+#[handler]
+async fn metrics(res: &mut Response) -> Result<()> {
+    let cycle = db().watch_cycles(1).await?.pop().unwrap_or_default();
+    let gauges = [
+        ("updown_watch_sites_checked", cycle.sites_checked),
+        ("updown_watch_failures", cycle.failures),
+        ("updown_watch_new_incidents", cycle.new_incidents),
+        ("updown_watch_duration_ms", cycle.duration_ms),
+        ("updown_watch_lag_ms", cycle.lag_ms),
+        ("updown_watch_finished_at", cycle.created_at),
+    ]
+    .iter()
+    .map(|(name, value)| format!("# TYPE {} gauge\n{} {}\n", name, name, value))
+    .collect::<String>();
+    res.render(Text::Plain(gauges));
+    Ok(())
+}
+
+#[handler]
+async fn flaky(res: &mut Response) {
+    let Some(period) = env().flaky_period else {
+        res.set_status_code(StatusCode::NOT_FOUND);
+        return;
+    };
+    if Database::now() % period < period / 2 {
+        res.render(Text::Plain("up"));
+    } else {
+        res.set_status_code(StatusCode::SERVICE_UNAVAILABLE);
+        res.render(Text::Plain("down"));
+    }
+}
+
+/// The url of an embedded file with a hash of its contents, so it can be
+/// cached for good and a new build still gets fetched.
+fn asset_url(path: &str) -> String {
+    match Assets::get(path) {
+        Some(file) => format!(
+            "/{}?v={}",
+            path,
+            hex::encode(&file.metadata.sha256_hash()[..6])
+        ),
+        None => format!("/{}", path),
+    }
+}
+
+#[cfg(debug_assertions)]
+fn tailwind_css() -> String {
+    r#"<script src="https://cdn.tailwindcss.com"></script>"#.to_string()
+}
+#[cfg(not(debug_assertions))]
+fn tailwind_css() -> String {
+    format!(
+        r#"<link href="{}" rel="stylesheet" />"#,
+        asset_url("tailwind.css")
+    )
+}
+#[cfg(debug_assertions)]
+const RETRY_MS: u16 = 1_000;
+#[cfg(not(debug_assertions))]
+const RETRY_MS: u16 = 45_000;
+
+#[handler]
+async fn index(res: &mut Response) -> Result<()> {
+    res.render(Text::Html(app_page(&env().ws_host)));
+    Ok(())
+}
+
+/// Hashed asset urls never change, anything else is checked each time.
+/// The widget script is embedded on other sites with a fixed url, so it
+/// gets an hour.
+#[handler]
+async fn asset_cache_handler(req: &mut Request, res: &mut Response) -> Result<()> {
+    let cache_control = match req.query::<String>("v") {
+        Some(_) => "public, max-age=31536000, immutable",
+        None if req.uri().path() == "/widget.js" => "public, max-age=3600",
+        None => "no-cache",
+    };
+    res.add_header(CACHE_CONTROL, cache_control, true)?;
+    Ok(())
+}
+
+/// Brotli when the browser takes it, gzip otherwise. Small and binary
+/// responses are left alone by the compressor.
+#[handler]
+async fn compression_handler(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    ctrl: &mut FlowCtrl,
+) {
+    let accepted = req.header::<String>(ACCEPT_ENCODING).unwrap_or_default();
+    let compression = match (accepted.contains("br"), accepted.contains("gzip")) {
+        (true, _) => compression::brotli(),
+        (false, true) => compression::gzip(),
+        (false, false) => return,
+    };
+    compression.handle(req, depot, res, ctrl).await;
+}
+
+/// The page a liveview app mounts into, connected to `ws_addr`.
+fn app_page(ws_addr: &str) -> String {
+    let tailwind_css = tailwind_css();
+    let main_js = asset_url("main.js");
+    format!(
+        r##"
+            <!DOCTYPE html>
+            <html lang=en class="h-full">
+                <head>
+                    <meta charset="utf-8">
+                    <meta content="width=device-width, initial-scale=1" name="viewport">
+                    <meta name="ws-addr" content="{ws_addr}"">
+                    <meta name="retry-ms" content="{RETRY_MS}">
+                    <meta name="theme-color" content="#22d3ee">
+                    <title>updown</title>
+                    <link rel="manifest" href="/manifest.webmanifest">
+                    <link rel="apple-touch-icon" href="/icon-192.png">
+                    {tailwind_css}
+                    <style>
+                        .box-shadow-md {{ box-shadow: 0 6px var(--tw-shadow-color); }}
+                        .hover\:box-shadow-xs:hover {{ box-shadow: 0 4px var(--tw-shadow-color); }}
+                    </style>
+                    <script defer src="{main_js}"></script>
+                </head>
+                <body class="h-full dark:bg-gray-950 bg-gray-50 dark:text-white text-gray-900">
+                    <div id="main" class="h-full"></div>
+                </body>
+            </html>
+        "##
+    )
+}
+
+/// Only instance admins get past this, everyone else sees a 404.
+#[handler]
+async fn admin_handler(depot: &mut Depot, ctrl: &mut FlowCtrl) -> Result<(), StatusError> {
+    if person(depot).is_some_and(|user| user.is_admin) {
+        Ok(())
+    } else {
+        ctrl.skip_rest();
+        Err(StatusError::not_found())
+    }
+}
+
+#[handler]
+async fn admin(res: &mut Response) -> Result<()> {
+    res.render(Text::Html(app_page(&format!("{}/admin", env().ws_host))));
+    Ok(())
+}
+
+#[handler]
+async fn admin_liveview(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    if same_origin(req) != Some(true) {
+        return Err(StatusError::not_found());
+    }
+    let view = depot
+        .obtain::<Arc<LiveViewPool>>()
+        .expect("LiveViewPool was not found in the middleware")
+        .clone();
+    WebSocketUpgrade::new()
+        .upgrade(req, res, move |ws| async move {
+            let _ = view
+                .launch(dioxus_liveview::salvo_socket(ws), AdminRoot)
+                .await;
+        })
+        .await
+}
+
+#[handler]
+async fn liveview(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    if same_origin(req) != Some(true) {
+        return Err(StatusError::not_found());
+    }
+    let view = depot
+        .obtain::<Arc<LiveViewPool>>()
+        .expect("LiveViewPool was not found in the middleware")
+        .clone();
+    let dashboard = match depot.obtain::<User>() {
+        Some(user) => db().dashboard(user.id).await.ok(),
+        None => None,
+    };
+    let organization = depot.obtain::<OrganizationSession>().cloned();
+    // someone who opened an invite link before logging in
+    let pending = depot
+        .session()
+        .and_then(|session| session.get::<String>("invitation"));
+    let invited_to = match pending {
+        Some(token) => match verify_invitation(&token).await {
+            Ok(invitation) => db()
+                .organization_by_id(invitation.organization_id)
+                .await
+                .ok()
+                .map(|organization| organization.name),
+            Err(_) => None,
+        },
+        None => None,
+    };
+    let ip_address = client_ip(req);
+    let path = req.query::<String>("path").unwrap_or_default();
+    let (current_user, sites, login_count) = match dashboard {
+        Some(Dashboard {
+            user,
+            sites,
+            login_count,
+        }) => (Some(user), sites, login_count),
+        None => (None, vec![], 0),
+    };
+    let locale = match &current_user {
+        Some(user) => user.locale,
+        None => req
+            .header::<String>(ACCEPT_LANGUAGE)
+            .map(|header| Locale::negotiate(&header))
+            .unwrap_or_default(),
+    };
+    WebSocketUpgrade::new()
+        .upgrade(req, res, move |ws| async move {
+            let _ = view
+                .launch_with_props::<RootProps>(
+                    dioxus_liveview::salvo_socket(ws),
+                    Root,
+                    RootProps {
+                        current_user,
+                        login_count,
+                        sites,
+                        organization,
+                        invited_to,
+                        ip_address,
+                        path,
+                        locale,
+                    },
+                )
+                .await;
+        })
+        .await
+}
+
+#[derive(Props, PartialEq)]
+struct RootProps {
+    current_user: Option<User>,
+    sites: Vec<DashboardSite>,
+    login_count: i32,
+    /// Set while acting for an organization, `current_user` is then its
+    /// account.
+    organization: Option<OrganizationSession>,
+    /// The organization a pending invite link is for.
+    invited_to: Option<String>,
+    /// Where the socket came from, failed logins are counted against it.
+    ip_address: Option<String>,
+    /// The page the browser is on, it picks the first view.
+    path: String,
+    /// The user's language, or the browser's before logging in.
+    locale: Locale,
+}
+
+/// The language to render strings in, components re-render when it's
+/// changed in the settings.
+fn use_locale(cx: &ScopeState) -> Locale {
+    use_shared_state::<RootProps>(cx).unwrap().read().locale
+}
+
+/// How often an open dashboard looks for checks the watcher stored.
+const SITE_UPDATE_INTERVAL_SECS: u64 = 5;
+
+fn Root(cx: Scope<RootProps>) -> Element {
+    let RootProps {
+        current_user,
+        sites,
+        login_count,
+        organization,
+        invited_to,
+        ip_address,
+        path,
+        locale,
+    } = cx.props;
+    use_shared_state_provider(cx, || RootProps {
+        current_user: cx.props.current_user.clone(),
+        sites: sites.clone(),
+        login_count: login_count.clone(),
+        organization: organization.clone(),
+        invited_to: invited_to.clone(),
+        ip_address: ip_address.clone(),
+        path: path.clone(),
+        locale: *locale,
+    });
+    use_shared_state_provider(cx, Toasts::default);
+    let toasts = use_shared_state::<Toasts>(cx).unwrap();
+    let read_only = organization
+        .as_ref()
+        .is_some_and(|organization| !organization.role.can_edit());
+    let initial_view = match (View::from_path(path), current_user, login_count) {
+        (Some(view), Some(_), _) if view.needs_login() => view,
+        (Some(view), None, _) if !view.needs_login() => view,
+        (_, Some(_), 1) => View::Onboarding(OnboardingStep::default()),
+        (_, Some(_), _) => View::Monitors,
+        (_, None, _) => View::default(),
+    };
+    let view = use_state(cx, || initial_view);
+    let onnav = move |new_view| {
+        to_owned![view];
+        view.set(new_view);
+    };
+    let add_site_sheet_shown = use_state(cx, || false);
+    let show_add_site_sheet = move |_| {
+        add_site_sheet_shown.set(!add_site_sheet_shown.get());
+    };
+    let user_id = match current_user {
+        Some(u) => u.id,
+        None => 0,
+    };
+    let sites = use_state(cx, || sites.clone());
+    // the watcher runs in its own process, so new checks are picked up
+    // from the database to keep statuses, latency and incidents current
+    use_coroutine(cx, |_: UnboundedReceiver<()>| {
+        to_owned![sites, user_id];
+        async move {
+            if user_id == 0 {
+                return;
+            }
+            let mut last_id = db().last_check_id().await.unwrap_or_default();
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(SITE_UPDATE_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                let checks = match db().checks_after(user_id, last_id).await {
+                    Ok(checks) => checks,
+                    Err(err) => {
+                        tracing::warn!("could not load new checks: {}", err);
+                        continue;
+                    }
+                };
+                let Some(last) = checks.last() else {
+                    continue;
+                };
+                last_id = last.id;
+                // only the newest check of each site is shown
+                let latest = checks
+                    .into_iter()
+                    .map(|check| (check.site_id, check))
+                    .collect::<HashMap<_, _>>();
+                for (site_id, check) in latest {
+                    let response = db().latest_response_by_site(site_id).await.ok();
+                    let open_incident = db()
+                        .open_incident_by_site(site_id)
+                        .await
+                        .unwrap_or_default();
+                    sites.with_mut(|sites| {
+                        sites
+                            .iter_mut()
+                            .filter(|site| site.site.id == site_id)
+                            .for_each(|site| {
+                                site.latest_response = response.clone();
+                                site.latency_ms = Some(check.latency_ms);
+                                site.open_incident = open_incident.clone();
+                            })
+                    });
+                }
+            }
+        }
+    });
+    let add_site_errors = use_state(cx, FieldErrors::default);
+    let adding_site = use_state(cx, || false);
+    let onadd = move |event: FormEvent| {
+        cx.spawn({
+            to_owned![
+                sites,
+                user_id,
+                add_site_sheet_shown,
+                add_site_errors,
+                adding_site
+            ];
+            if user_id == 0 || read_only || *adding_site.get() {
+                return;
+            }
+            let url = match Site::normalize_url(&form_value(&event, "url")) {
+                Ok(url) => url,
+                Err(err) => {
+                    add_site_errors.set(FieldErrors::site(&err));
+                    return;
+                }
+            };
+            adding_site.set(true);
+            let name = Some(form_value(&event, "name")).filter(|name| !name.trim().is_empty());
+            async move {
+                let mut site = Site::default();
+                site.user_id = user_id;
+                site.url = url;
+                site.name = name;
+                match db().insert_site(site).await {
+                    Ok(site) => {
+                        emit_sites_created(std::slice::from_ref(&site));
+                        let site = DashboardSite {
+                            site,
+                            ..Default::default()
+                        };
+                        sites.with_mut(|sites| sites.insert(0, site));
+                        add_site_errors.set(FieldErrors::default());
+                        add_site_sheet_shown.set(false);
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not add site: {}", err.chain());
+                        add_site_errors.set(FieldErrors::site(&err));
+                    }
+                }
+                adding_site.set(false);
+            }
+        })
+    };
+    let ondelete = move |site_id: i64| {
+        cx.spawn({
+            to_owned![sites, user_id, toasts];
+            async move {
+                if read_only {
+                    return;
+                }
+                match db().delete_site(user_id, site_id).await {
+                    Ok(_) => sites.with_mut(|sites| sites.retain(|site| site.site.id != site_id)),
+                    Err(err) => {
+                        tracing::warn!("could not delete site: {}", err);
+                        toasts.write().error("Could not delete the site, try again");
+                    }
+                }
+            }
+        })
+    };
+    let onbulk = move |(action, site_ids): (BulkAction, Vec<i64>)| {
+        cx.spawn({
+            to_owned![sites, user_id, toasts];
+            async move {
+                if read_only {
+                    return;
+                }
+                let insert_error = |source: sqlx::Error| AppError::DatabaseInsert { source };
+                let result = match &action {
+                    BulkAction::Pause | BulkAction::Resume => db()
+                        .pause_sites(user_id, &site_ids, action == BulkAction::Pause)
+                        .await
+                        .map_err(insert_error)
+                        .map(|paused| {
+                            sites.with_mut(|sites| {
+                                for site in sites.iter_mut() {
+                                    if let Some(paused) =
+                                        paused.iter().find(|p| p.id == site.site.id)
+                                    {
+                                        site.site = paused.clone();
+                                    }
+                                }
+                            })
+                        }),
+                    BulkAction::Delete => db()
+                        .delete_sites(user_id, &site_ids)
+                        .await
+                        .map_err(insert_error)
+                        .map(|deleted| {
+                            sites.with_mut(|sites| {
+                                sites.retain(|site| !deleted.iter().any(|d| d.id == site.site.id))
+                            })
+                        }),
+                    BulkAction::Tag(name) => {
+                        db().tag_sites(user_id, &site_ids, name).await.map(|tag| {
+                            sites.with_mut(|sites| {
+                                sites
+                                    .iter_mut()
+                                    .filter(|site| site_ids.contains(&site.site.id))
+                                    .filter(|site| !site.tags.contains(&tag.name))
+                                    .for_each(|site| {
+                                        site.tags.push(tag.name.clone());
+                                        site.tags.sort();
+                                    })
+                            })
+                        })
+                    }
+                    BulkAction::Untag(name) => db()
+                        .untag_sites(user_id, &site_ids, name)
+                        .await
+                        .map(|name| {
+                            sites.with_mut(|sites| {
+                                sites
+                                    .iter_mut()
+                                    .filter(|site| site_ids.contains(&site.site.id))
+                                    .for_each(|site| site.tags.retain(|tag| *tag != name))
+                            })
+                        }),
+                };
+                if let Err(err) = result {
+                    tracing::warn!("could not change sites in bulk: {}", err.chain());
+                    toasts.write().error(site_error_message(&err));
+                }
+            }
+        })
+    };
+    let editing = use_state(cx, || None::<Site>);
+    let onedit = move |site: Site| {
+        editing.set(Some(site));
+    };
+    let edit_site_errors = use_state(cx, FieldErrors::default);
+    let saving_site = use_state(cx, || false);
+    let onsave = move |event: FormEvent| {
+        cx.spawn({
+            to_owned![sites, editing, edit_site_errors, saving_site, toasts];
+            let Some(mut site) = editing.get().clone().filter(|_| !read_only) else {
+                return;
+            };
+            if *saving_site.get() {
+                return;
+            }
+            site.url = form_value(&event, "url");
+            site.name = Some(form_value(&event, "name")).filter(|name| !name.trim().is_empty());
+            site.interval_secs = form_value(&event, "interval_secs")
+                .parse()
+                .unwrap_or_default();
+            site.failure_threshold = form_value(&event, "failure_threshold")
+                .parse()
+                .unwrap_or_default();
+            let tags = form_value(&event, "tags")
+                .split(',')
+                .filter(|tag| !tag.trim().is_empty())
+                .map(Tag::normalize_name)
+                .collect::<Result<Vec<_>, _>>();
+            let validated = Site::normalize_url(&site.url).and_then(|url| {
+                site.url = url;
+                site.validate()?;
+                tags
+            });
+            let tags = match validated {
+                Ok(tags) => tags,
+                Err(err) => {
+                    edit_site_errors.set(FieldErrors::site(&err));
+                    return;
+                }
+            };
+            saving_site.set(true);
+            async move {
+                let saved = match db().update_site(site).await {
+                    Ok(site) => db()
+                        .set_site_tags(site.user_id, site.id, &tags)
+                        .await
+                        .map(|tags| (site, tags)),
+                    Err(err) => Err(err),
+                };
+                match saved {
+                    Ok((site, tags)) => {
+                        sites.with_mut(|sites| {
+                            sites
+                                .iter_mut()
+                                .filter(|dashboard_site| dashboard_site.site.id == site.id)
+                                .for_each(|dashboard_site| {
+                                    dashboard_site.site = site.clone();
+                                    dashboard_site.tags = tags.clone();
+                                })
+                        });
+                        edit_site_errors.set(FieldErrors::default());
+                        editing.set(None);
+                        toasts.write().info("Saved");
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not save site: {}", err.chain());
+                        edit_site_errors.set(FieldErrors::site(&err));
+                    }
+                }
+                saving_site.set(false);
+            }
+        })
+    };
+    let import_sheet_shown = use_state(cx, || false);
+    let import_message = use_state(cx, || None::<String>);
+    let onimport = move |event: FormEvent| {
+        cx.spawn({
+            to_owned![sites, user_id, import_message];
+            let input = form_value(&event, "data");
+            async move {
+                if read_only {
+                    return;
+                }
+                match import::run(db(), user_id, &input).await {
+                    Ok(report) => {
+                        emit_sites_created(&report.imported);
+                        sites.with_mut(|sites| {
+                            for site in &report.imported {
+                                let site = DashboardSite {
+                                    site: site.clone(),
+                                    ..Default::default()
+                                };
+                                sites.insert(0, site);
+                            }
+                        });
+                        import_message.set(Some(report.summary()));
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not import sites: {}", err.chain());
+                        import_message.set(Some(site_error_message(&err).to_string()));
+                    }
+                }
+            }
+        })
+    };
+    let ondemo = move |_| {
+        cx.spawn({
+            to_owned![sites, view, user_id, toasts];
+            async move {
+                if read_only {
+                    return;
+                }
+                let mut site = Site::default();
+                site.user_id = user_id;
+                site.url = format!("{}/flaky", env().origin);
+                match db().insert_site(site).await {
+                    Ok(site) => {
+                        emit_sites_created(std::slice::from_ref(&site));
+                        let site = DashboardSite {
+                            site,
+                            ..Default::default()
+                        };
+                        sites.with_mut(|sites| sites.insert(0, site));
+                        view.set(View::Monitors);
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not add the demo site: {}", err.chain());
+                        toasts.write().error(site_error_message(&err));
+                    }
+                }
+            }
+        })
+    };
+    let onexamples = move |_| {
+        cx.spawn({
+            to_owned![sites, user_id, toasts];
+            async move {
+                if read_only || !env().demo {
+                    return;
+                }
+                match demo::seed(db(), user_id).await {
+                    Ok(seeded) => {
+                        emit_sites_created(&seeded);
+                        sites.with_mut(|sites| {
+                            for site in seeded {
+                                let site = DashboardSite {
+                                    site,
+                                    ..Default::default()
+                                };
+                                sites.insert(0, site);
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not add the example sites: {}", err.chain());
+                        toasts.write().error(site_error_message(&err));
+                    }
+                }
+            }
+        })
+    };
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col justify-center md:items-center pt-4 md:pt-16 lg:pt-32 px-4 md:px-0 max-w-md mx-auto gap-4 md:gap-16 md:mb-0 pb-32 overflow-auto",
+            Header {}
+            if current_user.is_none() {
+                rsx! {
+                    invited_to.as_ref().map(|name| rsx! {
+                        div {
+                            class: "flex flex-col gap-2 w-full",
+                            p { class: "dark:text-white", "You're invited to {name}, log in to join or" }
+                            Button { id: "join-btn", "Join with a new account" }
+                        }
+                    })
+                }
+            }
+            match view.get() {
+                View::Index => rsx! {
+                    Index {}
+                },
+                View::Monitors => rsx! {
+                    Monitors {
+                        user_id: user_id,
+                        sites: sites.get(),
+                        onopen: move |id| view.set(View::SiteDetail(id)),
+                        onedit: onedit,
+                        ondelete: ondelete,
+                        onbulk: onbulk,
+                        onexamples: onexamples
+                    }
+                },
+                View::SiteDetail(id) => {
+                    match sites.get().iter().find(|site| site.site.id == *id) {
+                        Some(site) => rsx! {
+                            SiteDetail {
+                                key: "{id}",
+                                site: site,
+                                onback: move |_| view.set(View::Monitors)
+                            }
+                        },
+                        None => rsx! {
+                            p { class: "dark:text-white", "That site is gone" }
+                        },
+                    }
+                }
+                View::Incidents => rsx! {
+                    Incidents { user_id: user_id, sites: sites.get() }
+                },
+                View::Login => rsx! {
+                    NewLogin {}
+                },
+                View::Onboarding(step) => rsx! {
+                    Onboarding {
+                        step: *step,
+                        sites: sites.get(),
+                        login_code: current_user.as_ref().map(|user| user.login_code.clone()).unwrap_or_default(),
+                        user_id: user_id,
+                        onnav: onnav
+                    }
+                },
+                View::Account => rsx! {
+                    Account {
+                        onnav: onnav,
+                        ondemo: ondemo,
+                        onimport: move |_| import_sheet_shown.set(true),
+                        current_user: current_user,
+                        organization: organization
+                    }
+                }
+            }
+        }
+        Nav { onclick: onnav, active_view: view.get() }
+        // main.js keeps the address bar on this
+        div { hidden: true, "data-path": "{view.get().path()}" }
+        if current_user.is_some() && !read_only {
+            rsx! {
+                Fab {
+                    onclick: show_add_site_sheet,
+                    shortcut: "n",
+                    div { class: "text-2xl", "+" }
+                }
+                Sheet {
+                    shown: *add_site_sheet_shown.get(),
+                    onclose: move |_| {
+                        to_owned![add_site_sheet_shown];
+                        add_site_sheet_shown.set(false);
+                    }
+                    div {
+                        AddSite { onadd: onadd, errors: add_site_errors.get(), pending: *adding_site.get() }
+                    }
+                }
+                Sheet {
+                    shown: *import_sheet_shown.get(),
+                    onclose: move |_| {
+                        to_owned![import_sheet_shown, import_message];
+                        import_sheet_shown.set(false);
+                        import_message.set(None);
+                    }
+                    ImportSites {
+                        onimport: onimport,
+                        message: import_message.get().as_deref().unwrap_or_default()
+                    }
+                }
+                Sheet {
+                    shown: editing.get().is_some(),
+                    onclose: move |_| {
+                        to_owned![editing];
+                        editing.set(None);
+                    }
+                    editing.get().as_ref().map(|site| rsx! {
+                        EditSite {
+                            site: site,
+                            tags: sites
+                                .get()
+                                .iter()
+                                .find(|dashboard_site| dashboard_site.site.id == site.id)
+                                .map(|dashboard_site| dashboard_site.tags.join(", "))
+                                .unwrap_or_default(),
+                            onsave: onsave,
+                            errors: edit_site_errors.get(),
+                            pending: *saving_site.get()
+                        }
+                        ShareSite { key: "{site.id}", site_id: site.id, user_id: site.user_id }
+                        PublishIncident { key: "{site.id}", site_id: site.id, user_id: site.user_id }
+                    })
+                }
+            }
+            ToastList {}
+        }
+    })
+}
+
+fn form_value(event: &FormEvent, name: &str) -> String {
+    event
+        .values
+        .get(name)
+        .and_then(|values| values.first().cloned())
+        .unwrap_or_default()
+}
+
+/// Slides up over the page. main.js moves focus into the open sheet,
+/// keeps tab inside it and closes it on Esc.
+#[inline_props]
+fn Sheet<'a>(
+    cx: Scope,
+    shown: bool,
+    onclose: EventHandler<'a>,
+    children: Element<'a>,
+) -> Element<'a> {
+    let (translate_y, state) = match shown {
+        true => ("", "open"),
+        false => ("translate-y-full", "closed"),
+    };
+    return cx.render(
+        rsx! {
+            div {
+                class: "transition ease-out overflow-y-auto {translate_y} min-h-[80%] left-0 right-0 bottom-0 lg:max-w-3xl lg:mx-auto fixed p-6 rounded-md bg-gray-50 dark:bg-gray-900 z-30",
+                "data-sheet": "{state}",
+                div {
+                    class: "flex justify-end items-end mb-6",
+                    "data-sheet-close": "true",
+                    CircleButton {
+                        onclick: move |_| onclose.call(()),
+                        div { class: "text-2xl mb-1", "x" }
+                    }
+                }
+                children
+            }
+        }
+    );
+}
+
+#[inline_props]
+fn CircleButton<'a>(cx: Scope, onclick: EventHandler<'a>, children: Element<'a>) -> Element<'a> {
+    cx.render(rsx! {
+        button {
+            class: "rounded-full bg-gray-800 w-12 h-12 flex justify-center items-center",
+            onclick: move |_| onclick.call(()),
+            children
+        }
+    })
+}
+
+fn NewLogin(cx: Scope) -> Element {
+    let ss = use_shared_state::<RootProps>(cx).unwrap();
+    let ip_address = ss.read().ip_address.clone();
+    let locale = use_locale(cx);
+    let logging_in = use_state(cx, || false);
+    let login_errors = use_state(cx, FieldErrors::default);
+    let login_url = use_state(cx, || None::<String>);
+    let onlogin = move |event: FormEvent| {
+        let login_code = form_value(&event, "login-code").trim().to_string();
+        if *logging_in.get() {
+            return;
+        }
+        if login_code.is_empty() {
+            login_errors.set(FieldErrors::field(
+                "login-code",
+                t(locale, "login-code-empty"),
+            ));
+            return;
+        }
+        logging_in.set(true);
+        cx.spawn({
+            to_owned![logging_in, login_errors, login_url, ip_address];
+            async move {
+                match check_login_code(ip_address.as_deref(), &login_code).await {
+                    Ok(user) => login_url.set(Some(session_link_url(Action::Login, user.id))),
+                    Err(err) => {
+                        login_errors.set(match err {
+                            AppError::TooManyAttempts => {
+                                FieldErrors::form(t(locale, "login-too-many-attempts"))
+                            }
+                            AppError::Login => {
+                                FieldErrors::field("login-code", t(locale, "login-code-wrong"))
+                            }
+                            _ => FieldErrors::form(t(locale, "login-failed")),
+                        });
+                        logging_in.set(false);
+                    }
+                }
+            }
+        })
+    };
+    let login_label = match logging_in.get() {
+        true => t(locale, "login-pending"),
+        false => t(locale, "login-button"),
+    };
+    let code_placeholder = t(locale, "login-code-placeholder");
+    let passkey_label = t(locale, "login-passkey");
+    let lost_code_label = t(locale, "login-lost-code");
+    let recovery_placeholder = t(locale, "login-recovery-placeholder");
+    let recovery_label = t(locale, "login-recovery-button");
+    let recovery_sent = t(locale, "login-recovery-sent");
+    let recovering = use_state(cx, || false);
+    let sent = use_state(cx, || false);
+    let onrecover = move |event: FormEvent| {
+        let email = form_value(&event, "email");
+        cx.spawn({
+            to_owned![sent];
+            async move {
+                if let Err(err) = send_recovery_links(&email).await {
+                    tracing::warn!("could not send recovery links: {}", err.chain());
+                }
+                sent.set(true);
+            }
+        })
+    };
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-2 w-full",
+            form {
+                onsubmit: onlogin,
+                class: "flex flex-col gap-2 w-full",
+                TextInput {
+                    placeholder: "{code_placeholder}",
+                    name: "login-code",
+                    error: login_errors.get().get("login-code").unwrap_or_default()
+                }
+                FormError { error: login_errors.get().form_error().unwrap_or_default() }
+                Button { disabled: *logging_in.get(), "{login_label}" }
+            }
+            login_url.get().as_ref().map(|url| rsx! {
+                Navigate { to: url }
+            })
+            Button { id: "passkey-login-btn", "{passkey_label}" }
+            if *sent.get() {
+                rsx! {
+                    p { class: "text-sm dark:text-white", "{recovery_sent}" }
+                }
+            } else if *recovering.get() {
+                rsx! {
+                    form {
+                        onsubmit: onrecover,
+                        class: "flex flex-col gap-2 w-full",
+                        TextInput { placeholder: "{recovery_placeholder}", name: "email" }
+                        Button { "{recovery_label}" }
+                    }
+                }
+            } else {
+                rsx! {
+                    button {
+                        class: "text-sm text-cyan-400",
+                        onclick: move |_| recovering.set(true),
+                        "{lost_code_label}"
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn Index(cx: Scope) -> Element {
+    cx.render(rsx! {
+        AddSite { id: "signup-btn" }
+    })
+}
+
+/// What can be done to the sites picked in the monitors list at once.
+#[derive(Clone, PartialEq)]
+enum BulkAction {
+    Pause,
+    Resume,
+    Delete,
+    Tag(String),
+    Untag(String),
+}
+
+#[inline_props]
+fn Monitors<'a>(
+    cx: Scope,
+    user_id: i64,
+    sites: &'a Vec<DashboardSite>,
+    onopen: EventHandler<'a, i64>,
+    onedit: EventHandler<'a, Site>,
+    ondelete: EventHandler<'a, i64>,
+    onbulk: EventHandler<'a, (BulkAction, Vec<i64>)>,
+    onexamples: EventHandler<'a>,
+) -> Element {
+    let query = use_state(cx, SiteQuery::default);
+    let selected = use_state(cx, Vec::<i64>::new);
+    let bulk_tag = use_state(cx, String::new);
+    // the first click asks, the second one deletes
+    let confirming_delete = use_state(cx, || false);
+    // grows a page at a time, so hundreds of sites aren't all rendered
+    let limit = use_state(cx, || Page::<Site>::DEFAULT_LIMIT);
+    let mut tags = sites
+        .iter()
+        .flat_map(|site| site.tags.iter())
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags.dedup();
+    // the database does the matching, sites added, removed or going down
+    // ask it again
+    let down = sites
+        .iter()
+        .filter(|site| site.open_incident.is_some())
+        .count();
+    let matching = use_future(
+        cx,
+        (
+            *user_id,
+            query.get().clone(),
+            *limit.get(),
+            sites.len(),
+            down,
+        ),
+        |(user_id, query, limit, _, _)| async move {
+            // one extra tells whether there's more
+            db().matching_site_ids(user_id, &query, limit + 1)
+                .await
+                .map_err(|err| tracing::warn!("could not search sites: {}", err))
+                .ok()
+        },
+    );
+    let mut shown = match matching.value().cloned().flatten() {
+        Some(ids) => ids
+            .iter()
+            .filter_map(|id| sites.iter().find(|site| site.site.id == *id))
+            .collect::<Vec<_>>(),
+        None => sites.iter().collect(),
+    };
+    let more = shown.len() as i64 > *limit.get();
+    shown.truncate(*limit.get() as usize);
+    let shown_ids = shown.iter().map(|site| site.site.id).collect::<Vec<_>>();
+    let recent_checks = use_future(cx, (shown_ids.clone(),), |(site_ids,)| async move {
+        db().recent_checks_for_sites(&site_ids, SPARKLINE_CHECKS)
+            .await
+            .unwrap_or_default()
+    });
+    let recent_checks = recent_checks.value().map(Vec::as_slice).unwrap_or_default();
+    let tag = query.get().tag.clone();
+    let down_only = query.get().down_only;
+    // sites that went away or were deleted elsewhere can't stay picked
+    let picked = move || {
+        selected
+            .get()
+            .iter()
+            .copied()
+            .filter(|id| sites.iter().any(|site| site.site.id == *id))
+            .collect::<Vec<_>>()
+    };
+    let picked_ids = picked();
+    let picked_count = picked_ids.len();
+    let all_picked = !shown_ids.is_empty() && shown_ids.iter().all(|id| picked_ids.contains(id));
+    let bulk = move |action: BulkAction| {
+        onbulk.call((action, picked()));
+        selected.set(vec![]);
+        bulk_tag.set(String::new());
+        confirming_delete.set(false);
+    };
+    let locale = use_locale(cx);
+    let delete_label = match confirming_delete.get() {
+        true => t_with(
+            locale,
+            "monitors-confirm-delete",
+            [("count", picked_count.into())],
+        ),
+        false => t(locale, "monitors-delete"),
+    };
+    if sites.is_empty() {
+        let title = t(locale, "monitors-empty-title");
+        let detail = t(locale, "monitors-empty-detail");
+        let examples_label = t(locale, "monitors-examples");
+        return cx.render(rsx! {
+            EmptyState {
+                title: "{title}",
+                p { class: "text-sm text-gray-500 dark:text-gray-400", "{detail}" }
+                if env().demo {
+                    rsx! {
+                        Button { onclick: move |_| onexamples.call(()), "{examples_label}" }
+                    }
+                }
+            }
+        });
+    }
+    let search_placeholder = t(locale, "monitors-search-placeholder");
+    let no_matches_title = t(locale, "monitors-no-matches-title");
+    let no_matches_detail = t(locale, "monitors-no-matches-detail");
+    let clear_label = t(locale, "monitors-clear-filters");
+    let no_matches = shown.is_empty();
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-4",
+            div {
+                class: "flex flex-col gap-2",
+                input {
+                    class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                    r#type: "search",
+                    placeholder: "{search_placeholder}",
+                    "data-shortcut": "/",
+                    value: "{query.get().search}",
+                    oninput: move |event| query.with_mut(|query| query.search = event.value.clone())
+                }
+                div {
+                    class: "flex items-center gap-2",
+                    select {
+                        class: "rounded-lg px-2 py-1 text-xs border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                        onchange: move |event| {
+                            let sort = SiteSort::parse(&event.value).unwrap_or_default();
+                            query.with_mut(|query| query.sort = sort)
+                        },
+                        SiteSort::ALL.iter().map(|sort| rsx! {
+                            option {
+                                key: "{sort.label()}",
+                                value: "{sort.label()}",
+                                selected: query.get().sort == *sort,
+                                "{sort.label()}"
+                            }
+                        })
+                    }
+                    TagButton {
+                        active: down_only,
+                        onclick: move |_| query.with_mut(|query| query.down_only = !query.down_only),
+                        "down only"
+                    }
+                }
+            }
+            if !tags.is_empty() {
+                rsx! {
+                    div {
+                        class: "flex flex-wrap gap-2",
+                        TagButton {
+                            active: tag.is_none(),
+                            onclick: move |_| query.with_mut(|query| query.tag = None),
+                            "all"
+                        }
+                        tags.iter().map(|name| rsx! {
+                            TagButton {
+                                key: "{name}",
+                                active: tag.as_ref() == Some(*name),
+                                onclick: move |_| query.with_mut(|query| query.tag = Some(name.to_string())),
+                                "{name}"
+                            }
+                        })
+                    }
+                }
+            }
+            div {
+                class: "flex items-center gap-2 text-xs dark:text-white",
+                input {
+                    r#type: "checkbox",
+                    checked: all_picked,
+                    onclick: move |_| {
+                        match all_picked {
+                            true => selected.set(vec![]),
+                            false => selected.set(shown_ids.clone()),
+                        }
+                    }
+                }
+                match picked_count {
+                    0 => rsx! { span { "Select all" } },
+                    count => rsx! { span { "{count} selected" } },
+                }
+            }
+            if picked_count > 0 {
+                rsx! {
+                    div {
+                        class: "flex flex-wrap items-center gap-2 border border-gray-200 dark:border-gray-800 rounded-md p-2",
+                        TagButton { active: false, onclick: move |_| bulk(BulkAction::Pause), "pause" }
+                        TagButton { active: false, onclick: move |_| bulk(BulkAction::Resume), "resume" }
+                        input {
+                            class: "rounded-lg px-2 py-1 text-xs border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                            r#type: "text",
+                            placeholder: "tag",
+                            value: "{bulk_tag}",
+                            oninput: move |event| bulk_tag.set(event.value.clone())
+                        }
+                        TagButton { active: false, onclick: move |_| bulk(BulkAction::Tag(bulk_tag.get().clone())), "add tag" }
+                        TagButton { active: false, onclick: move |_| bulk(BulkAction::Untag(bulk_tag.get().clone())), "remove tag" }
+                        button {
+                            class: "rounded-full px-3 py-1 text-xs bg-red-500 text-white",
+                            onclick: move |_| {
+                                if *confirming_delete.get() {
+                                    bulk(BulkAction::Delete);
+                                } else {
+                                    confirming_delete.set(true);
+                                }
+                            },
+                            "{delete_label}"
+                        }
+                    }
+                }
+            }
+            if no_matches {
+                rsx! {
+                    EmptyState {
+                        title: "{no_matches_title}",
+                        p { class: "text-sm text-gray-500 dark:text-gray-400", "{no_matches_detail}" }
+                        button {
+                            class: "text-sm text-cyan-400",
+                            onclick: move |_| query.set(SiteQuery::default()),
+                            "{clear_label}"
+                        }
+                    }
+                }
+            }
+            shown.into_iter().map(|site| rsx! {
+                ShowSite {
+                    key: "{site.site.id}",
+                    site: site,
+                    selected: selected.get().contains(&site.site.id),
+                    onselect: move |id| {
+                        selected.with_mut(|selected| match selected.iter().position(|picked| *picked == id) {
+                            Some(position) => {
+                                selected.remove(position);
+                            }
+                            None => selected.push(id),
+                        })
+                    },
+                    recent_checks: recent_checks,
+                    onopen: move |id| onopen.call(id),
+                    onedit: move |site| onedit.call(site),
+                    ondelete: move |id| ondelete.call(id)
+                }
+            })
+            if more {
+                rsx! {
+                    button {
+                        class: "text-sm text-cyan-400",
+                        onclick: move |_| limit.set(limit.get() + Page::<Site>::DEFAULT_LIMIT),
+                        "Show more"
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Stands in for a list with nothing in it, with what to do about that.
+#[inline_props]
+fn EmptyState<'a>(cx: Scope, title: &'a str, children: Element<'a>) -> Element {
+    cx.render(rsx! {
+        div {
+            class: "border-2 border-dashed border-gray-200 dark:border-gray-800 rounded-md p-8 flex flex-col items-center gap-2 text-center dark:text-white",
+            p { class: "text-lg", "{title}" }
+            children
+        }
+    })
+}
+
+#[inline_props]
+fn TagButton<'a>(
+    cx: Scope,
+    active: bool,
+    onclick: EventHandler<'a, MouseEvent>,
+    children: Element<'a>,
+) -> Element {
+    let class = match active {
+        true => "bg-cyan-400 text-black",
+        false => "bg-gray-200 dark:bg-gray-800 dark:text-white",
+    };
+    cx.render(rsx! {
+        button {
+            class: "rounded-full px-3 py-1 text-xs {class}",
+            onclick: move |event| onclick.call(event),
+            children
+        }
+    })
+}
+
+fn Header(cx: Scope) -> Element {
+    cx.render(rsx! {
+        div {
+            class: "flex md:flex-col md:gap-2",
+            h1 { class: "text-xl md:text-4xl md:text-center text-left", "updown" }
+            h2 { class: "text-xl text-center hidden md:block", "your friendly neighborhood uptime monitor" }
+        }
+    })
+}
+
+#[derive(Default, Clone, PartialEq)]
+enum View {
+    #[default]
+    Index,
+    Monitors,
+    /// One site with its latency chart, by id.
+    SiteDetail(i64),
+    Incidents,
+    Account,
+    Login,
+    /// The steps a new account is walked through after signing up.
+    Onboarding(OnboardingStep),
+}
+
+impl View {
+    /// Where the view lives, so it can be linked to and survives a reload.
+    fn path(&self) -> String {
+        match self {
+            View::Index => "/".to_string(),
+            View::Monitors => "/monitors".to_string(),
+            View::SiteDetail(id) => format!("/sites/{}", id),
+            View::Incidents => "/incidents".to_string(),
+            View::Account => "/account".to_string(),
+            View::Login => "/login".to_string(),
+            View::Onboarding(_) => "/welcome".to_string(),
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        let view = match path.trim_end_matches('/') {
+            "" => View::Index,
+            "/monitors" => View::Monitors,
+            "/incidents" => View::Incidents,
+            "/account" => View::Account,
+            "/login" => View::Login,
+            "/welcome" => View::Onboarding(OnboardingStep::default()),
+            path => View::SiteDetail(path.strip_prefix("/sites/")?.parse().ok()?),
+        };
+        Some(view)
+    }
+
+    fn needs_login(&self) -> bool {
+        !matches!(self, View::Index | View::Login)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum OnboardingStep {
+    /// The site from the signup form is in.
+    #[default]
+    SiteAdded,
+    /// It's checked right away rather than on the next watch cycle.
+    FirstCheck,
+    /// There's no other way back in, so it's worth a stop.
+    LoginCode,
+    /// Alerts need somewhere to go.
+    Channel,
+}
+
+impl OnboardingStep {
+    const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::SiteAdded,
+        OnboardingStep::FirstCheck,
+        OnboardingStep::LoginCode,
+        OnboardingStep::Channel,
+    ];
+
+    fn number(&self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|step| step == self)
+            .unwrap_or_default()
+            + 1
+    }
+
+    /// The step after this one, none after the last.
+    fn next(&self) -> Option<Self> {
+        Self::ALL.get(self.number()).copied()
+    }
+}
+
+#[inline_props]
+fn Nav<'a>(cx: Scope, onclick: EventHandler<'a, View>, active_view: &'a View) -> Element {
+    let ss = use_shared_state::<RootProps>(cx).unwrap();
+    let logged_in = ss.read().current_user.is_some();
+    // the person, not the organization they act for
+    let person_id = match (&ss.read().organization, &ss.read().current_user) {
+        (Some(organization), _) => organization.member.id,
+        (None, Some(user)) => user.id,
+        (None, None) => 0,
+    };
+    let locale = use_locale(cx);
+    let logout_url = use_state(cx, || None::<String>);
+    let logout_label = match logout_url.get() {
+        Some(_) => t(locale, "nav-logging-out"),
+        None => t(locale, "nav-logout"),
+    };
+    let sites_label = t(locale, "nav-sites");
+    let incidents_label = t(locale, "nav-incidents");
+    let account_label = t(locale, "nav-account");
+    let home_label = t(locale, "nav-home");
+    let login_label = t(locale, "nav-login");
+    cx.render(rsx! {
+        nav {
+            class: "fixed lg lg:top-0 lg:bottom-auto bottom-0 w-full py-6 dark:bg-gray-900",
+            ul {
+                class: "flex lg:justify-center lg:gap-4 justify-around",
+                if logged_in {
+                    rsx! {
+                        NavLink { active: **active_view == View::Monitors, onclick: move |_| onclick.call(View::Monitors), "{sites_label}" }
+                        NavLink { active: **active_view == View::Incidents, onclick: move |_| onclick.call(View::Incidents), "{incidents_label}" }
+                        NavLink { active: **active_view == View::Account, onclick: move |_| onclick.call(View::Account), "{account_label}" }
+                        NavLink {
+                            onclick: move |_| logout_url.set(Some(session_link_url(Action::Logout, person_id))),
+                            "{logout_label}"
+                        }
+                    }
+                } else {
+                    rsx! {
+                        NavLink { active: **active_view == View::Index, onclick: move |_| onclick.call(View::Index), "{home_label}" }
+                        NavLink { active: **active_view == View::Login, onclick: move |_| onclick.call(View::Login), "{login_label}" }
+                    }
+                }
+            }
+            logout_url.get().as_ref().map(|url| rsx! {
+                Navigate { to: url }
+            })
+        }
+    })
+}
+
+/// Sends the browser to `to` once rendered, main.js watches for it. For
+/// the links that have to be real requests, like the ones setting cookies.
+#[inline_props]
+fn Navigate<'a>(cx: Scope, to: &'a str) -> Element {
+    cx.render(rsx! {
+        div { hidden: true, "data-navigate": "{to}" }
+    })
+}
+
+#[inline_props]
+fn NavLink<'a>(
+    cx: Scope,
+    active: Option<bool>,
+    onclick: Option<EventHandler<'a, ()>>,
+    id: Option<&'a str>,
+    children: Element<'a>,
+) -> Element {
+    let active_class = match active {
+        Some(true) => "text-cyan-400",
+        Some(false) | None => "",
+    };
+    let onclick = move |_| {
+        if let Some(click) = onclick {
+            click.call(())
+        }
+    };
+    let id = id.unwrap_or_default();
+    cx.render(rsx! {
+        li {
+            class: "cursor-pointer group transition duration-300", onclick: onclick,
+            a { id: id, class: "{active_class}", children }
+            div { class: "max-w-0 group-hover:max-w-full transition-all duration-300 h-1 bg-cyan-400" }
+        }
+    })
+}
+
+#[inline_props]
+fn AddSite<'a>(
+    cx: Scope,
+    id: Option<&'a str>,
+    onadd: Option<EventHandler<'a, FormEvent>>,
+    errors: Option<&'a FieldErrors>,
+    pending: Option<bool>,
+) -> Element {
+    let id = id.unwrap_or_default();
+    let errors = errors.cloned().unwrap_or_default();
+    let url_error = errors.get("url").unwrap_or_default();
+    let name_error = errors.get("name").unwrap_or_default();
+    let form_error = errors.form_error().unwrap_or_default();
+    let label = match pending {
+        Some(true) => "Adding the site",
+        _ => "Monitor a site",
+    };
+    let onsubmit = move |event| {
+        if let Some(onadd) = onadd {
+            onadd.call(event)
+        }
+    };
+    cx.render(rsx! {
+        form {
+            onsubmit: onsubmit,
+            class: "flex flex-col gap-2 w-full",
+            TextInput { name: "url", placeholder: "https://example.com", error: "{url_error}" }
+            TextInput { name: "name", placeholder: "Name (optional)", error: "{name_error}" }
+            FormError { error: "{form_error}" }
+            Button { id: "{id}", disabled: pending.unwrap_or_default(), "{label}" }
+        }
+    })
+}
+
+/// Validation messages for a form, each shown under the input it's about.
+/// The ones without a field go at the bottom of the form.
+#[derive(Default, Clone, PartialEq, Debug)]
+struct FieldErrors(Vec<(Option<&'static str>, String)>);
+
+impl FieldErrors {
+    fn field(name: &'static str, message: impl Into<String>) -> Self {
+        Self(vec![(Some(name), message.into())])
+    }
+
+    fn form(message: impl Into<String>) -> Self {
+        Self(vec![(None, message.into())])
+    }
+
+    fn add(&mut self, name: &'static str, message: impl Into<String>) {
+        self.0.push((Some(name), message.into()));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == Some(name))
+            .map(|(_, message)| message.as_str())
+    }
+
+    fn form_error(&self) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(field, _)| field.is_none())
+            .map(|(_, message)| message.as_str())
+    }
+
+    /// Puts a site's validation error under the input it came from,
+    /// whether it was caught here or by the database.
+    fn site(err: &AppError) -> Self {
+        let message = site_error_message(err);
+        match err {
+            AppError::UrlEmpty
+            | AppError::UrlInvalid
+            | AppError::UrlScheme
+            | AppError::DuplicateSite => Self::field("url", message),
+            AppError::TagName => Self::field("tags", message),
+            _ => Self::form(message),
+        }
+    }
+}
+
+pub(crate) fn site_error_message(err: &AppError) -> &'static str {
+    match err {
+        AppError::UrlEmpty => "Enter the url of the site to monitor",
+        AppError::UrlInvalid => "That doesn't look like a url",
+        AppError::UrlScheme => "Only http and https urls can be monitored",
+        AppError::SiteSettings => "Pick one of the intervals and thresholds",
+        AppError::DuplicateSite => "You're already monitoring this site",
+        AppError::TagName => "Tags can't be longer than 32 characters",
+        AppError::ImportFormat => "Paste a csv file, an Uptime Kuma backup or an export",
+        AppError::SiteLimit => "You're monitoring as many sites as your account allows",
+        AppError::StatusIncident => {
+            "An incident needs a title of up to 200 characters and a message"
+        }
+        _ => "Something went wrong, try again",
+    }
+}
+
+/// Short lived messages for things that happen away from a form, like a
+/// failed delete. Forms show their own errors with [`FormError`].
+#[derive(Default)]
+struct Toasts {
+    items: Vec<Toast>,
+    next_id: usize,
+}
+
+struct Toast {
+    id: usize,
+    message: String,
+    error: bool,
+    shown_at: std::time::Instant,
+}
+
+impl Toasts {
+    /// How long a toast stays up unless it's dismissed.
+    const SHOWN_FOR: std::time::Duration = std::time::Duration::from_secs(5);
+
+    fn push(&mut self, message: impl Into<String>, error: bool) {
+        self.next_id += 1;
+        self.items.push(Toast {
+            id: self.next_id,
+            message: message.into(),
+            error,
+            shown_at: std::time::Instant::now(),
+        });
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.push(message, true)
+    }
+
+    fn info(&mut self, message: impl Into<String>) {
+        self.push(message, false)
+    }
+}
+
+fn ToastList(cx: Scope) -> Element {
+    let toasts = use_shared_state::<Toasts>(cx).unwrap();
+    let newest = toasts.read().next_id;
+    let count = toasts.read().items.len();
+    // wakes up once the oldest toast is due and drops everything that is
+    use_future(cx, (&newest, &count), |_| {
+        to_owned![toasts];
+        async move {
+            let Some(oldest) = toasts.read().items.first().map(|toast| toast.shown_at) else {
+                return;
+            };
+            tokio::time::sleep(Toasts::SHOWN_FOR.saturating_sub(oldest.elapsed())).await;
+            toasts
+                .write()
+                .items
+                .retain(|toast| toast.shown_at.elapsed() < Toasts::SHOWN_FOR);
+        }
+    });
+    let toasts_read = toasts.read();
+    cx.render(rsx! {
+        div {
+            class: "fixed top-4 left-0 right-0 flex flex-col items-center gap-2 z-40 pointer-events-none",
+            toasts_read.items.iter().map(|toast| {
+                let id = toast.id;
+                let class = match toast.error {
+                    true => "bg-red-500 text-white",
+                    false => "bg-gray-800 text-white",
+                };
+                rsx! {
+                    div {
+                        key: "{id}",
+                        class: "pointer-events-auto rounded-md px-4 py-2 text-sm shadow-md flex gap-4 {class}",
+                        span { "{toast.message}" }
+                        button {
+                            onclick: move |_| toasts.write().items.retain(|toast| toast.id != id),
+                            "x"
+                        }
+                    }
+                }
+            })
+        }
+    })
+}
+
+#[inline_props]
+fn FormError<'a>(cx: Scope, error: &'a str) -> Element {
+    if error.is_empty() {
+        return None;
+    }
+    cx.render(rsx! {
+        p { class: "text-sm text-red-500", "{error}" }
+    })
+}
+
+#[inline_props]
+fn ImportSites<'a>(cx: Scope, onimport: EventHandler<'a, FormEvent>, message: &'a str) -> Element {
+    cx.render(rsx! {
+        form {
+            onsubmit: move |event| onimport.call(event),
+            class: "flex flex-col gap-2 w-full",
+            label {
+                class: "text-sm dark:text-white",
+                "Paste a csv file with a url and an optional name per line, or an Uptime Kuma backup"
+            }
+            textarea {
+                name: "data",
+                rows: "10",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black font-mono text-sm",
+                placeholder: "https://example.com,Example"
+            }
+            (!message.is_empty()).then(|| rsx! {
+                p { class: "text-sm dark:text-white", "{message}" }
+            })
+            Button { "Import" }
+        }
+    })
+}
+
+/// Makes a link that shows the site's status to someone without an
+/// account. Links are revoked through the api.
+#[inline_props]
+fn ShareSite(cx: Scope, site_id: i64, user_id: i64) -> Element {
+    let url = use_state(cx, || None::<String>);
+    let onshare = move |_| {
+        cx.spawn({
+            to_owned![url, site_id, user_id];
+            async move {
+                match db().insert_share_link(user_id, site_id, None).await {
+                    Ok(share_link) => url.set(Some(share_url(&share_link))),
+                    Err(err) => tracing::warn!("could not add share link: {}", err),
+                }
+            }
+        })
     };
-    let sites = use_state(cx, || sites.clone());
-    let onadd = move |event: FormEvent| {
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-2 w-full pt-4",
+            url.get().as_ref().map(|url| rsx! {
+                p {
+                    class: "text-xs break-all text-gray-500 dark:text-gray-400",
+                    "Anyone with this link can see the site's status: {url}"
+                }
+            })
+            button {
+                class: "text-sm text-cyan-400",
+                onclick: onshare,
+                "Create a share link"
+            }
+        }
+    })
+}
+
+/// Publishes incidents on the site's share page by hand and posts
+/// updates to the ones still open.
+#[inline_props]
+fn PublishIncident(cx: Scope, site_id: i64, user_id: i64) -> Element {
+    const LIMIT: i64 = 5;
+    let error = use_state(cx, || None::<&'static str>);
+    let timelines = use_future(cx, (site_id,), |(site_id,)| async move {
+        db().incident_timelines(site_id, LIMIT)
+            .await
+            .unwrap_or_default()
+    });
+    let onpublish = move |event: FormEvent| {
+        let incident_id = form_value(&event, "incident").parse::<i64>().ok();
+        let title = form_value(&event, "title");
+        let message = form_value(&event, "message");
+        let status = IncidentStatus::ALL
+            .into_iter()
+            .find(|status| status.label() == form_value(&event, "status"))
+            .unwrap_or_default();
         cx.spawn({
-            to_owned![sites, user_id, add_site_sheet_shown];
-            if user_id == 0 {
-                return;
+            to_owned![timelines, error, site_id, user_id];
+            async move {
+                let published = match incident_id {
+                    Some(id) => {
+                        db().insert_incident_update(user_id, id, status, &message)
+                            .await
+                    }
+                    None => {
+                        db().insert_status_incident(user_id, site_id, &title, status, &message)
+                            .await
+                    }
+                };
+                match published {
+                    Ok(timeline) => {
+                        notify_incident_subscribers(&timeline);
+                        error.set(None);
+                        timelines.restart();
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not publish incident: {}", err.chain());
+                        error.set(Some(site_error_message(&err)));
+                    }
+                }
+            }
+        })
+    };
+    let Some(timelines) = timelines.value() else {
+        return cx.render(rsx! { div {} });
+    };
+    let now = Database::now();
+    cx.render(rsx! {
+        form {
+            onsubmit: onpublish,
+            class: "flex flex-col gap-2 w-full pt-4",
+            h2 { class: "text-sm dark:text-white", "Incidents on the share page" }
+            timelines.iter().map(|timeline| {
+                let updated_at = timeline
+                    .updates
+                    .first()
+                    .map(|update| update.created_at)
+                    .unwrap_or(timeline.incident.created_at);
+                let ago = notifications::format_duration(now - updated_at);
+                rsx! {
+                    p {
+                        key: "{timeline.incident.id}",
+                        class: "text-xs text-gray-500 dark:text-gray-400",
+                        "{timeline.incident.title}, {timeline.incident.status.label()} {ago} ago"
+                    }
+                }
+            })
+            select {
+                name: "incident",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                option { value: "new", "New incident" }
+                timelines
+                    .iter()
+                    .filter(|timeline| timeline.incident.resolved_at.is_none())
+                    .map(|timeline| rsx! {
+                        option {
+                            value: "{timeline.incident.id}",
+                            "Update {timeline.incident.title}"
+                        }
+                    })
+            }
+            TextInput { name: "title", placeholder: "Title, for a new incident" }
+            select {
+                name: "status",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                IncidentStatus::ALL.iter().map(|status| rsx! {
+                    option { value: "{status.label()}", "{status.label()}" }
+                })
+            }
+            textarea {
+                name: "message",
+                rows: "3",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black text-sm",
+                placeholder: "What's going on"
+            }
+            FormError { error: error.get().unwrap_or_default() }
+            Button { "Publish" }
+        }
+    })
+}
+
+#[inline_props]
+fn EditSite<'a>(
+    cx: Scope,
+    site: &'a Site,
+    tags: String,
+    onsave: EventHandler<'a, FormEvent>,
+    errors: &'a FieldErrors,
+    pending: bool,
+) -> Element {
+    let name = site.name.clone().unwrap_or_default();
+    cx.render(rsx! {
+        form {
+            onsubmit: move |event| onsave.call(event),
+            class: "flex flex-col gap-2 w-full",
+            TextInput { name: "url", value: "{site.url}", placeholder: "https://example.com", error: errors.get("url").unwrap_or_default() }
+            TextInput { name: "name", value: "{name}", placeholder: "Name" }
+            TextInput { name: "tags", value: "{tags}", placeholder: "Tags, like prod, api", error: errors.get("tags").unwrap_or_default() }
+            label {
+                class: "text-sm dark:text-white",
+                "Check every"
+            }
+            select {
+                name: "interval_secs",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                Site::INTERVALS.iter().map(|interval| rsx! {
+                    option {
+                        value: "{interval}",
+                        selected: *interval == site.interval_secs,
+                        "{notifications::format_duration(*interval)}"
+                    }
+                })
+            }
+            label {
+                class: "text-sm dark:text-white",
+                "Failed checks in a row before an incident opens"
+            }
+            select {
+                name: "failure_threshold",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                (1..=Site::MAX_FAILURE_THRESHOLD).map(|threshold| rsx! {
+                    option {
+                        value: "{threshold}",
+                        selected: threshold == site.failure_threshold,
+                        "{threshold}"
+                    }
+                })
+            }
+            FormError { error: errors.form_error().unwrap_or_default() }
+            Button { disabled: *pending, "Save" }
+        }
+    })
+}
+
+/// How many checks the latency sparkline in each monitor row shows.
+const SPARKLINE_CHECKS: i64 = 30;
+
+#[derive(Props)]
+struct ShowSiteProps<'a> {
+    site: &'a DashboardSite,
+    /// The last [`SPARKLINE_CHECKS`] checks of each site on the page,
+    /// loaded in one go and oldest first. Live updates are newer.
+    recent_checks: &'a [Check],
+    /// Picked for a bulk action.
+    selected: bool,
+    onselect: EventHandler<'a, i64>,
+    onopen: EventHandler<'a, i64>,
+    onedit: EventHandler<'a, Site>,
+    ondelete: EventHandler<'a, i64>,
+}
+
+fn ShowSite<'a>(cx: Scope<'a, ShowSiteProps<'a>>) -> Element<'a> {
+    let ShowSiteProps {
+        site,
+        recent_checks,
+        selected,
+        onselect,
+        onopen,
+        onedit,
+        ondelete,
+    } = cx.props;
+    let DashboardSite {
+        site: Site {
+            id, url, user_id, ..
+        },
+        latest_response,
+        uptime,
+        open_incident,
+        tags,
+        latency_ms,
+    } = site;
+    let name = site.site.display_name();
+    // incidents can open and close under the component, so this is the one
+    // acknowledged here rather than a flag
+    let acknowledged_id = use_state(cx, || None::<i64>);
+    let toasts = use_shared_state::<Toasts>(cx).unwrap();
+    let acknowledged = match open_incident {
+        Some(incident) => {
+            incident.acknowledged_at.is_some() || *acknowledged_id.get() == Some(incident.id)
+        }
+        None => true,
+    };
+    let onacknowledge = move |_| {
+        cx.spawn({
+            to_owned![acknowledged_id, open_incident, user_id, toasts];
+            async move {
+                let Some(incident) = open_incident else {
+                    return;
+                };
+                match db().acknowledge_incident(user_id, incident.id).await {
+                    Ok(_) => acknowledged_id.set(Some(incident.id)),
+                    Err(err) => {
+                        tracing::warn!("could not acknowledge incident: {}", err);
+                        toasts
+                            .write()
+                            .error("Could not acknowledge the incident, try again");
+                    }
+                }
+            }
+        })
+    };
+    let history_shown = use_state(cx, || false);
+    // the first click asks, the second one deletes
+    let confirming = use_state(cx, || false);
+    let ondeleteclick = move |_| {
+        if *confirming.get() {
+            ondelete.call(*id);
+        } else {
+            confirming.set(true);
+        }
+    };
+    let delete_label = match confirming.get() {
+        true => "Really delete?",
+        false => "Delete",
+    };
+    let flapping = open_incident
+        .as_ref()
+        .map(|incident| incident.flapping)
+        .unwrap_or_default();
+    // whole class names so tailwind finds them
+    let (status, ring, dot) = match latest_response {
+        _ if site.site.paused_at.is_some() => ("Paused", "bg-gray-400/20", "bg-gray-400"),
+        _ if flapping => ("Flapping", "bg-amber-400/20", "bg-amber-400"),
+        Some(response) if response.is_up() => ("Online", "bg-emerald-500/20", "bg-emerald-500"),
+        Some(_) => ("Offline", "bg-red-500/20", "bg-red-500"),
+        None => ("Unknown", "bg-gray-400/20", "bg-gray-400"),
+    };
+    let status_code = match latest_response {
+        Some(response) => format!("last status code {}", response.status_code),
+        None => "not checked yet".to_string(),
+    };
+    let uptime = match uptime {
+        Some(uptime) => format!("{:.2}%", uptime),
+        None => String::with_capacity(0),
+    };
+    let checked = match latest_response {
+        Some(response) => format!("checked {}", response.updated_at.ago(Timestamp::now())),
+        None => String::with_capacity(0),
+    };
+    let latencies = recent_checks
+        .iter()
+        .filter(|check| check.site_id == *id)
+        .map(|check| check.latency_ms)
+        .collect::<Vec<_>>();
+    let (points, slowest) = latency_polyline(&latencies);
+    let latency = match latency_ms.or(latencies.last().copied()) {
+        Some(ms) => format!("{}ms", ms),
+        None => String::with_capacity(0),
+    };
+    cx.render(rsx! {
+        div {
+            class: "border border-gray-200 dark:border-gray-800 dark:text-white p-2 rounded-md flex items-center justify-between",
+            // main.js keeps these for when the app is opened offline
+            "data-site": "{name}",
+            "data-status": "{status}",
+            div {
+                class: "flex items-start gap-2",
+                input {
+                    class: "mt-1.5",
+                    r#type: "checkbox",
+                    checked: *selected,
+                    onclick: move |_| onselect.call(*id)
+                }
+                div {
+                    button {
+                        class: "text-left hover:text-cyan-400 flex items-center gap-2",
+                        onclick: move |_| onopen.call(*id),
+                        img {
+                            class: "h-4 w-4 rounded-sm",
+                            src: "/sites/{id}/icon",
+                            alt: ""
+                        }
+                        "{name}"
+                    }
+                    if site.site.name.is_some() {
+                        rsx! {
+                            div { class: "text-xs text-gray-500 dark:text-gray-400", "{url}" }
+                        }
+                    }
+                    div {
+                        class: "flex gap-1",
+                        tags.iter().map(|tag| rsx! {
+                            span { key: "{tag}", class: "text-xs text-cyan-500", "#{tag}" }
+                        })
+                    }
+                }
+            }
+            div {
+                class: "flex items-center gap-x-1.5",
+                div {
+                    class: "flex-none rounded-full {ring} p-1",
+                    title: "{status_code}",
+                    div {
+                        class: "h-1.5 w-1.5 rounded-full {dot}"
+                    }
+                }
+                p {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400", "{status}"
+                }
+                p {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400", "{uptime}"
+                }
+                if latencies.len() > 1 {
+                    rsx! {
+                        svg {
+                            class: "w-16 h-4 text-cyan-400",
+                            view_box: "0 0 100 30",
+                            preserve_aspect_ratio: "none",
+                            title { "Last {latencies.len()} checks, up to {slowest}ms" }
+                            polyline {
+                                points: "{points}",
+                                fill: "none",
+                                stroke: "currentColor",
+                                stroke_width: "3"
+                            }
+                        }
+                    }
+                }
+                p {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400", "{latency}"
+                }
+                p {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400", "{checked}"
+                }
+                if !acknowledged {
+                    rsx! {
+                        button {
+                            class: "text-xs leading-5 text-amber-500",
+                            onclick: onacknowledge,
+                            "Acknowledge"
+                        }
+                    }
+                }
+                button {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400",
+                    onclick: move |_| history_shown.set(!history_shown.get()),
+                    "History"
+                }
+                button {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400",
+                    onclick: move |_| onedit.call(site.site.clone()),
+                    "Edit"
+                }
+                button {
+                    class: "text-xs leading-5 text-red-500",
+                    onclick: ondeleteclick,
+                    "{delete_label}"
+                }
+            }
+        }
+        UptimeBar { site_id: *id, interval_secs: site.site.interval_secs }
+        if *history_shown.get() {
+            rsx! {
+                SiteChecks { user_id: *user_id, site_id: *id }
+            }
+        }
+    })
+}
+
+/// The ranges the site detail chart can show.
+#[derive(Clone, Copy, PartialEq)]
+enum ChartRange {
+    Day,
+    Week,
+    Month,
+}
+
+impl ChartRange {
+    const ALL: [ChartRange; 3] = [ChartRange::Day, ChartRange::Week, ChartRange::Month];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ChartRange::Day => "24h",
+            ChartRange::Week => "7d",
+            ChartRange::Month => "30d",
+        }
+    }
+
+    fn secs(&self) -> i64 {
+        match self {
+            ChartRange::Day => 86_400,
+            ChartRange::Week => 7 * 86_400,
+            ChartRange::Month => 30 * 86_400,
+        }
+    }
+
+    /// Hours up to a week, days past that, so the chart has 24 to 168
+    /// points.
+    fn resolution(&self) -> Resolution {
+        match self {
+            ChartRange::Month => Resolution::Day,
+            _ => Resolution::Hour,
+        }
+    }
+}
+
+/// One site with its average latency over the range as a chart, from
+/// the same buckets as the api's `resolution`.
+#[inline_props]
+fn SiteDetail<'a>(
+    cx: Scope,
+    site: &'a DashboardSite,
+    onback: EventHandler<'a, MouseEvent>,
+) -> Element {
+    let range = use_state(cx, || ChartRange::Day);
+    let buckets = use_future(
+        cx,
+        (site.site.id, *range.get()),
+        |(site_id, range)| async move {
+            let now = Database::now();
+            db().check_buckets(site_id, now - range.secs(), now + 1, range.resolution())
+                .await
+                .unwrap_or_default()
+        },
+    );
+    let name = site.site.display_name();
+    let buckets = buckets.value().cloned().unwrap_or_default();
+    let latencies = buckets
+        .iter()
+        .map(|bucket| bucket.latency_avg_ms)
+        .collect::<Vec<_>>();
+    let (points, slowest) = latency_polyline(&latencies);
+    let checks = buckets.iter().map(|bucket| bucket.checks).sum::<i64>();
+    let counted = buckets
+        .iter()
+        .map(|bucket| bucket.checks - bucket.maintenance)
+        .sum::<i64>();
+    let up = buckets.iter().map(|bucket| bucket.up).sum::<i64>();
+    let average = match checks {
+        0 => "-".to_string(),
+        _ => {
+            let total = buckets
+                .iter()
+                .map(|bucket| bucket.latency_avg_ms * bucket.checks)
+                .sum::<i64>();
+            format!("{}ms", total / checks)
+        }
+    };
+    let fastest = buckets
+        .iter()
+        .map(|bucket| bucket.latency_min_ms)
+        .min()
+        .map(|ms| format!("{}ms", ms))
+        .unwrap_or("-".to_string());
+    let uptime = match counted {
+        0 => "-".to_string(),
+        _ => format!("{:.2}%", up as f64 * 100.0 / counted as f64),
+    };
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-4 dark:text-white",
+            button {
+                class: "text-left text-sm text-cyan-400",
+                onclick: move |event| onback.call(event),
+                "Back to sites"
+            }
+            div {
+                h1 { class: "text-xl", "{name}" }
+                p { class: "text-xs text-gray-500 dark:text-gray-400 break-all", "{site.site.url}" }
+            }
+            div {
+                class: "flex gap-2",
+                ChartRange::ALL.iter().map(|option| rsx! {
+                    TagButton {
+                        key: "{option.label()}",
+                        active: range.get() == option,
+                        onclick: move |_| range.set(*option),
+                        "{option.label()}"
+                    }
+                })
+            }
+            div {
+                class: "grid grid-cols-3 gap-2 text-center",
+                div {
+                    p { class: "text-lg", "{average}" }
+                    p { class: "text-xs text-gray-500 dark:text-gray-400", "average" }
+                }
+                div {
+                    p { class: "text-lg", "{fastest}" }
+                    p { class: "text-xs text-gray-500 dark:text-gray-400", "fastest" }
+                }
+                div {
+                    p { class: "text-lg", "{uptime}" }
+                    p { class: "text-xs text-gray-500 dark:text-gray-400", "uptime" }
+                }
+            }
+            UptimeBar { site_id: site.site.id, interval_secs: site.site.interval_secs }
+            p { class: "text-xs text-gray-500 dark:text-gray-400", "Average latency per {range.get().resolution().label()}, up to {slowest}ms" }
+            svg {
+                class: "w-full h-32 text-cyan-400",
+                view_box: "0 0 100 30",
+                preserve_aspect_ratio: "none",
+                polyline {
+                    points: "{points}",
+                    fill: "none",
+                    stroke: "currentColor",
+                    stroke_width: "0.5"
+                }
+            }
+        }
+    })
+}
+
+/// One segment per day for the last 90 days, green without downtime,
+/// yellow above 95% and red below. Days without checks are grey. Downtime
+/// is the failed checks times the interval, so it's as close as the
+/// interval allows.
+#[inline_props]
+fn UptimeBar(cx: Scope, site_id: i64, interval_secs: i64) -> Element {
+    const DAYS: i64 = 90;
+    let buckets = use_future(cx, (site_id,), |(site_id,)| async move {
+        let now = Database::now();
+        let from = (now / 86_400 - DAYS + 1) * 86_400;
+        db().check_buckets(site_id, from, now + 1, Resolution::Day)
+            .await
+            .unwrap_or_default()
+    });
+    let Some(buckets) = buckets.value() else {
+        return cx.render(rsx! { div { class: "h-6" } });
+    };
+    let today = Database::now() / 86_400;
+    let days = (today - DAYS + 1..=today).map(|day| {
+        let at = day * 86_400;
+        let date = chrono::Utc
+            .timestamp_opt(at, 0)
+            .single()
+            .map(|date| date.format("%b %-d").to_string())
+            .unwrap_or_default();
+        let (class, title) = match buckets.iter().find(|bucket| bucket.at == at) {
+            Some(bucket) if bucket.checks > bucket.maintenance => {
+                let down = bucket.checks - bucket.maintenance - bucket.up;
+                let uptime = bucket.up as f64 * 100.0 / (bucket.checks - bucket.maintenance) as f64;
+                let class = match (down, uptime) {
+                    (0, _) => "bg-emerald-500",
+                    (_, uptime) if uptime >= 95.0 => "bg-amber-400",
+                    _ => "bg-red-500",
+                };
+                let title = match down {
+                    0 => format!("{}: no downtime", date),
+                    _ => format!(
+                        "{}: down for about {}, {:.2}% up",
+                        date,
+                        notifications::format_duration(down * interval_secs),
+                        uptime
+                    ),
+                };
+                (class, title)
+            }
+            Some(_) => (
+                "bg-gray-300 dark:bg-gray-700",
+                format!("{}: maintenance", date),
+            ),
+            None => (
+                "bg-gray-200 dark:bg-gray-800",
+                format!("{}: no checks", date),
+            ),
+        };
+        (day, class, title)
+    });
+    cx.render(rsx! {
+        div {
+            class: "flex gap-px h-6 w-full",
+            days.map(|(day, class, title)| rsx! {
+                div {
+                    key: "{day}",
+                    class: "flex-1 rounded-sm {class}",
+                    title: "{title}"
+                }
+            })
+        }
+    })
+}
+
+/// Past outages across the user's sites, filtered by site and by the days
+/// they started on, the last 30 by default.
+#[inline_props]
+fn Incidents<'a>(cx: Scope, user_id: i64, sites: &'a Vec<DashboardSite>) -> Element {
+    let today = chrono::Utc::now().date_naive();
+    let site_id = use_state(cx, || None::<i64>);
+    let from = use_state(cx, || (today - chrono::Duration::days(30)).to_string());
+    let to = use_state(cx, || today.to_string());
+    let day = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|date| date.timestamp())
+    };
+    let range = (
+        day(from.get()).unwrap_or_default(),
+        day(to.get()).map(|to| to + 86_400).unwrap_or(i64::MAX),
+    );
+    let incidents = use_future(
+        cx,
+        (*user_id, *site_id.get(), range),
+        |(user_id, site_id, (from, to))| async move {
+            db().past_incidents(user_id, site_id, from, to)
+                .await
+                .unwrap_or_default()
+        },
+    );
+    let name = |site_id: i64| {
+        sites
+            .iter()
+            .find(|site| site.site.id == site_id)
+            .map(|site| site.site.display_name())
+            .unwrap_or_default()
+    };
+    let incidents = incidents.value().cloned().unwrap_or_default();
+    let locale = use_locale(cx);
+    let (empty_title, empty_detail) = match sites.is_empty() {
+        true => (
+            t(locale, "incidents-empty-title"),
+            t(locale, "incidents-empty-detail"),
+        ),
+        false => (
+            t(locale, "incidents-quiet-title"),
+            t(locale, "incidents-quiet-detail"),
+        ),
+    };
+    let input_class = "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black";
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-4 dark:text-white",
+            h1 { class: "text-xl", "Incidents" }
+            div {
+                class: "flex flex-col gap-2",
+                select {
+                    class: "{input_class}",
+                    onchange: move |event| site_id.set(event.value.parse().ok()),
+                    option { value: "", "All sites" }
+                    sites.iter().map(|site| rsx! {
+                        option {
+                            key: "{site.site.id}",
+                            value: "{site.site.id}",
+                            selected: *site_id.get() == Some(site.site.id),
+                            "{site.site.display_name()}"
+                        }
+                    })
+                }
+                div {
+                    class: "flex gap-2",
+                    input {
+                        r#type: "date",
+                        class: "{input_class} flex-1",
+                        value: "{from}",
+                        onchange: move |event| from.set(event.value.clone())
+                    }
+                    input {
+                        r#type: "date",
+                        class: "{input_class} flex-1",
+                        value: "{to}",
+                        onchange: move |event| to.set(event.value.clone())
+                    }
+                }
             }
-            let url = match event.values.get("url") {
-                Some(values) => values.first().cloned().unwrap_or_default(),
-                None => String::with_capacity(0),
-            };
-            if url.is_empty() {
-                return;
+            if incidents.is_empty() {
+                rsx! {
+                    EmptyState {
+                        title: "{empty_title}",
+                        p { class: "text-sm text-gray-500 dark:text-gray-400", "{empty_detail}" }
+                    }
+                }
             }
-            async move {
-                let mut site = Site::default();
-                site.user_id = user_id;
-                site.url = url;
-                match db().insert_site(site).await {
-                    Ok(s) => {
-                        sites.with_mut(|sites| sites.insert(0, s));
-                        add_site_sheet_shown.set(false);
+            incidents.iter().map(|incident| {
+                let started = chrono::Utc
+                    .timestamp_opt(incident.started_at, 0)
+                    .single()
+                    .map(|date| date.format("%b %-d %Y, %H:%M UTC").to_string())
+                    .unwrap_or_default();
+                let (status, duration) = match incident.duration() {
+                    Some(secs) => ("Resolved", notifications::format_duration(secs)),
+                    None if incident.flapping => (
+                        "Flapping",
+                        notifications::format_duration(Database::now() - incident.started_at),
+                    ),
+                    None => (
+                        "Ongoing",
+                        notifications::format_duration(Database::now() - incident.started_at),
+                    ),
+                };
+                let cause = match incident.failure {
+                    Some(checker::FailureKind::Status) | None if incident.status_code > 0 => {
+                        format!("status {}", incident.status_code)
+                    }
+                    Some(failure) => failure.label().to_string(),
+                    None => "unknown".to_string(),
+                };
+                let codes = match incident.last_status_code {
+                    Some(last) if last != incident.status_code => {
+                        format!("{} then {}", incident.status_code, last)
+                    }
+                    _ => incident.status_code.to_string(),
+                };
+                rsx! {
+                    div {
+                        key: "{incident.id}",
+                        class: "border border-gray-200 dark:border-gray-800 p-2 rounded-md flex flex-col gap-1",
+                        div {
+                            class: "flex justify-between",
+                            span { "{name(incident.site_id)}" }
+                            span { class: "text-xs text-gray-500 dark:text-gray-400", "{status}" }
+                        }
+                        div {
+                            class: "flex justify-between text-xs text-gray-500 dark:text-gray-400",
+                            span { "{started}" }
+                            span { "{duration}" }
+                        }
+                        div {
+                            class: "flex justify-between text-xs text-gray-500 dark:text-gray-400",
+                            span { "{cause}" }
+                            span { "{codes}" }
+                        }
                     }
-                    Err(_) => {}
                 }
+            })
+        }
+    })
+}
+
+/// The site's checks, a page at a time, newest first.
+#[inline_props]
+fn SiteChecks(cx: Scope, user_id: i64, site_id: i64) -> Element {
+    let checks = use_state(cx, Vec::<Check>::new);
+    let cursor = use_state(cx, || None::<i64>);
+    let done = use_state(cx, || false);
+    let load = move || {
+        cx.spawn({
+            to_owned![checks, cursor, done, user_id, site_id];
+            async move {
+                let limit = Page::<Check>::DEFAULT_LIMIT;
+                let Ok(page) = db()
+                    .checks_page(user_id, site_id, *cursor.get(), limit)
+                    .await
+                else {
+                    return;
+                };
+                checks.with_mut(|checks| checks.extend(page.items));
+                done.set(page.next_cursor.is_none());
+                cursor.set(page.next_cursor);
             }
         })
     };
+    use_effect(cx, (user_id, site_id), |_| {
+        load();
+        async {}
+    });
     cx.render(rsx! {
         div {
-            class: "flex flex-col justify-center md:items-center pt-4 md:pt-16 lg:pt-32 px-4 md:px-0 max-w-md mx-auto gap-4 md:gap-16 md:mb-0 pb-32 overflow-auto",
-            Header {}
-            match view.get() {
-                View::Index => rsx! {
-                    Index {}
-                },
-                View::Monitors => rsx! {
-                    Monitors {
-                        sites: sites.get()
+            class: "flex flex-col gap-1 text-xs text-gray-500 dark:text-gray-400",
+            checks.iter().map(|check| {
+                let checked = Timestamp::from_secs(check.created_at).ago(Timestamp::now());
+                rsx! {
+                    div {
+                        key: "{check.id}",
+                        class: "flex justify-between",
+                        span { "{check.status_code}" }
+                        span { "{check.latency_ms}ms" }
+                        span { "{checked}" }
+                    }
+                }
+            })
+            if !done.get() {
+                rsx! {
+                    button {
+                        class: "text-xs leading-5",
+                        onclick: move |_| load(),
+                        "Older"
                     }
-                },
-                View::Login => rsx! {
-                    NewLogin {}
-                },
-                View::Account => rsx! {
-                    Account { onnav: onnav, current_user: current_user }
                 }
             }
         }
-        Nav { onclick: onnav, active_view: view.get() }
-        if current_user.is_some() {
-            rsx! {
-                Fab {
-                    onclick: show_add_site_sheet,
-                    div { class: "text-2xl", "+" }
-                }
-                Sheet {
-                    shown: *add_site_sheet_shown.get(),
-                    onclose: move |_| {
-                        to_owned![add_site_sheet_shown];
-                        add_site_sheet_shown.set(false);
-                    }
-                    div {
-                        AddSite { onadd: onadd }
-                    }
+    })
+}
+
+#[derive(Props)]
+struct ButtonProps<'a> {
+    #[props(optional)]
+    id: Option<&'a str>,
+    #[props(optional)]
+    onclick: Option<EventHandler<'a, MouseEvent>>,
+    /// Set while the form it submits is pending.
+    #[props(optional)]
+    disabled: Option<bool>,
+    /// A key that presses the button from anywhere on the page, main.js
+    /// listens for it.
+    #[props(optional)]
+    shortcut: Option<&'a str>,
+    children: Element<'a>,
+}
+
+fn Button<'a>(cx: Scope<'a, ButtonProps<'a>>) -> Element {
+    let ButtonProps {
+        id,
+        onclick,
+        disabled,
+        shortcut,
+        children,
+    } = cx.props;
+    let onclick = move |event| {
+        if let Some(click) = onclick {
+            click.call(event);
+        }
+    };
+    let id = id.unwrap_or_default();
+    cx.render(rsx! {
+        button {
+            id: id,
+            class: "px-4 py-3 w-full bg-cyan-400 text-white rounded-3xl box-shadow-md shadow-cyan-600 hover:box-shadow-xs hover:top-0.5 active:shadow-none active:top-1 relative disabled:opacity-50",
+            disabled: disabled.unwrap_or_default(),
+            "data-shortcut": shortcut.unwrap_or_default(),
+            onclick: onclick,
+            children
+        }
+    })
+}
+
+fn Fab<'a>(cx: Scope<'a, ButtonProps<'a>>) -> Element {
+    let ButtonProps {
+        id,
+        onclick,
+        shortcut,
+        children,
+        ..
+    } = cx.props;
+    let onclick = move |event| {
+        if let Some(click) = onclick {
+            click.call(event);
+        }
+    };
+    let id = id.unwrap_or_default();
+    cx.render(rsx! {
+        div {
+            class: "absolute bottom-24 right-4 z-20",
+            button {
+                id: id,
+                class: "h-12 w-12 rounded-full bg-cyan-400 text-white box-shadow-md shadow-cyan-600 hover:box-shadow-xs hover:top-0.5 active:shadow-none active:top-1 relative",
+                "data-shortcut": shortcut.unwrap_or_default(),
+                onclick: onclick,
+                children
+            }
+        }
+    })
+}
+
+#[derive(Props)]
+struct TextInputProps<'a> {
+    #[props(optional)]
+    placeholder: Option<&'a str>,
+    #[props(optional)]
+    value: Option<&'a str>,
+    /// Shown under the input, which gets a red border.
+    #[props(default)]
+    error: &'a str,
+    name: &'a str,
+}
+
+fn TextInput<'a>(cx: Scope<'a, TextInputProps<'a>>) -> Element {
+    let TextInputProps {
+        name,
+        placeholder,
+        value,
+        error,
+    } = cx.props;
+    let border = match error.is_empty() {
+        true => "dark:border-gray-700",
+        false => "border-red-500",
+    };
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-1",
+            input {
+                class: "rounded-lg px-2 py-3 border {border} dark:text-white dark:bg-gray-800 outline-none text-black",
+                r#type: "text",
+                name: "{name}",
+                value: value.unwrap_or_default(),
+                placeholder: placeholder.unwrap_or_default()
+            }
+            if !error.is_empty() {
+                rsx! {
+                    p { class: "text-xs text-red-500", "{error}" }
                 }
             }
         }
     })
 }
 
+/// Walks a new account from its first site to its first channel, each
+/// step can be skipped.
 #[inline_props]
-fn Sheet<'a>(
+fn Onboarding<'a>(
     cx: Scope,
-    shown: bool,
-    onclose: EventHandler<'a>,
-    children: Element<'a>,
-) -> Element<'a> {
-    let translate_y = match shown {
-        true => "",
-        false => "translate-y-full",
+    step: OnboardingStep,
+    sites: &'a Vec<DashboardSite>,
+    login_code: String,
+    user_id: i64,
+    onnav: EventHandler<'a, View>,
+) -> Element {
+    let next = match step.next() {
+        Some(step) => View::Onboarding(step),
+        None => View::Monitors,
     };
-    return cx.render(
-        rsx! {
-            div {
-                class: "transition ease-out overflow-y-auto {translate_y} min-h-[80%] left-0 right-0 bottom-0 lg:max-w-3xl lg:mx-auto fixed p-6 rounded-md bg-gray-50 dark:bg-gray-900 z-30",
-                div {
-                    class: "flex justify-end items-end mb-6",
-                    CircleButton {
-                        onclick: move |_| onclose.call(()),
-                        div { class: "text-2xl mb-1", "x" }
+    let locale = use_locale(cx);
+    let next_label = t(
+        locale,
+        match step {
+            OnboardingStep::SiteAdded => "onboarding-check-now",
+            OnboardingStep::FirstCheck => "onboarding-next",
+            OnboardingStep::LoginCode => "onboarding-saved-code",
+            OnboardingStep::Channel => "onboarding-done",
+        },
+    );
+    let step_label = t_with(
+        locale,
+        "onboarding-step",
+        [
+            ("number", step.number().into()),
+            ("count", OnboardingStep::ALL.len().into()),
+        ],
+    );
+    let site = sites.first();
+    let site_added_title = t(locale, "onboarding-site-added-title");
+    let site_added_detail = site
+        .map(|site| {
+            t_with(
+                locale,
+                "onboarding-site-added-detail",
+                [
+                    ("site", site.site.display_name().into()),
+                    (
+                        "interval",
+                        notifications::format_duration(site.site.interval_secs).into(),
+                    ),
+                ],
+            )
+        })
+        .unwrap_or_default();
+    let no_site = t(locale, "onboarding-no-site");
+    let login_code_title = t(locale, "onboarding-login-code-title");
+    let channel_title = t(locale, "onboarding-channel-title");
+    let skip_label = t(locale, "onboarding-skip");
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-4",
+            p { class: "text-xs text-gray-500 dark:text-gray-400", "{step_label}" }
+            match (step, site) {
+                (OnboardingStep::SiteAdded, Some(_)) => rsx! {
+                    h1 { class: "text-lg dark:text-white", "{site_added_title}" }
+                    p { class: "dark:text-white", "{site_added_detail}" }
+                },
+                (OnboardingStep::FirstCheck, Some(site)) => rsx! {
+                    FirstCheck { site: site.site.clone() }
+                },
+                (OnboardingStep::SiteAdded | OnboardingStep::FirstCheck, None) => rsx! {
+                    p { class: "dark:text-white", "{no_site}" }
+                },
+                (OnboardingStep::LoginCode, _) => rsx! {
+                    h1 { class: "text-lg dark:text-white", "{login_code_title}" }
+                    LoginCodeAlert { login_code: login_code.clone() }
+                },
+                (OnboardingStep::Channel, _) => rsx! {
+                    h1 { class: "text-lg dark:text-white", "{channel_title}" }
+                    Channels { user_id: *user_id }
+                },
+            }
+            Button { onclick: move |_| onnav.call(next.clone()), "{next_label}" }
+            if step.next().is_some() {
+                rsx! {
+                    button {
+                        class: "text-sm text-gray-500 dark:text-gray-400",
+                        onclick: move |_| onnav.call(View::Monitors),
+                        "{skip_label}"
                     }
                 }
-                children
             }
         }
-    );
+    })
 }
 
+/// Checks the site once as soon as it's shown and stores the check like
+/// the watcher would, open dashboards get it as a live update.
 #[inline_props]
-fn CircleButton<'a>(cx: Scope, onclick: EventHandler<'a>, children: Element<'a>) -> Element<'a> {
-    cx.render(rsx! {
-        button {
-            class: "rounded-full bg-gray-800 w-12 h-12 flex justify-center items-center",
-            onclick: move |_| onclick.call(()),
-            children
+fn FirstCheck(cx: Scope, site: Site) -> Element {
+    let check = use_future(cx, (site.clone(),), |(site,)| async move {
+        let check = checker::check(&reqwest::Client::new(), &site).await;
+        let stored = async {
+            db().upsert_response(check.response()).await?;
+            db().insert_check(&check).await?;
+            Ok::<_, sqlx::Error>(())
+        }
+        .await;
+        if let Err(err) = stored {
+            tracing::warn!("could not store the first check: {}", err);
         }
+        check
+    });
+    let result = match check.value() {
+        None => "Checking...".to_string(),
+        Some(check) if check.response().is_up() => format!(
+            "It's up, it answered with {} in {}ms.",
+            check.status_code, check.latency_ms
+        ),
+        Some(check) => match check.failure {
+            Some(failure) => format!(
+                "It looks down, the check failed with a {}.",
+                failure.label()
+            ),
+            None => format!("It looks down, it answered with {}.", check.status_code),
+        },
+    };
+    cx.render(rsx! {
+        h1 { class: "text-lg dark:text-white", "Your first check" }
+        p { class: "dark:text-white", "{result}" }
     })
 }
 
-fn NewLogin(cx: Scope) -> Element {
+#[inline_props]
+fn Account<'a>(
+    cx: Scope,
+    current_user: &'a Option<User>,
+    organization: &'a Option<OrganizationSession>,
+    onnav: EventHandler<'a, View>,
+    ondemo: EventHandler<'a>,
+    onimport: EventHandler<'a>,
+) -> Element {
+    let login_code = use_state(cx, || match current_user {
+        Some(u) => u.login_code.clone(),
+        None => "".to_string(),
+    });
+    let toasts = use_shared_state::<Toasts>(cx).unwrap();
+    let onrotate = move |_| {
+        cx.spawn({
+            to_owned![login_code, toasts];
+            let user_id = current_user.as_ref().map(|u| u.id).unwrap_or_default();
+            async move {
+                match db().rotate_login_code(user_id).await {
+                    Ok(user) => {
+                        login_code.set(user.login_code);
+                        toasts
+                            .write()
+                            .info("Your old login code no longer works, note the new one");
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not rotate login code: {}", err);
+                        toasts
+                            .write()
+                            .error("Could not change your login code, try again");
+                    }
+                }
+            }
+        })
+    };
+    let person_id = match organization {
+        Some(organization) => organization.member.id,
+        None => current_user.as_ref().map(|u| u.id).unwrap_or_default(),
+    };
+    let can_edit = organization
+        .as_ref()
+        .map_or(true, |organization| organization.role.can_edit());
+    // the first click asks, the second one is sent by main.js so the
+    // session cookie goes with it
+    let deleting = use_state(cx, || false);
     cx.render(rsx! {
         div {
-            class: "flex flex-col gap-2 w-full",
-            TextInput { placeholder: "Your login code goes here", name: "login-code" }
-            Button { id: "login-btn", "Login" }
+            class: "grid place-content-center gap-4",
+            match organization {
+                Some(organization) => {
+                    let role = format!("{:?}", organization.role).to_lowercase();
+                    rsx! {
+                        p {
+                            class: "dark:text-white",
+                            "You're in {organization.organization.name} as {role}"
+                        }
+                    }
+                }
+                None => rsx! {
+                    LoginCodeAlert { key: "{login_code}", login_code: login_code.get().clone() }
+                }
+            }
+            Button { onclick: move |_| onnav.call(View::Monitors), "View your sites" }
+            if env().flaky_period.is_some() && can_edit {
+                rsx! {
+                    Button { onclick: move |_| ondemo.call(()), "Monitor this instance's flaky endpoint" }
+                }
+            }
+            if organization.is_none() {
+                rsx! {
+                    current_user.as_ref().map(|user| rsx! { AccountSettings { user: user.clone() } })
+                }
+            }
+            h2 { class: "text-sm dark:text-white", "Notification channels" }
+            Channels { user_id: current_user.as_ref().map(|u| u.id).unwrap_or_default() }
+            Organizations {
+                user_id: person_id,
+                active_id: organization
+                    .as_ref()
+                    .map(|organization| organization.organization.id)
+                    .unwrap_or_default()
+            }
+            organization
+                .as_ref()
+                .filter(|organization| organization.role == Role::Owner)
+                .map(|organization| rsx! {
+                    Invitations {
+                        organization_id: organization.organization.id,
+                        user_id: person_id
+                    }
+                })
+            if can_edit {
+                rsx! {
+                    Button { onclick: move |_| onimport.call(()), "Import sites" }
+                    ApiTokens {
+                        user_id: current_user.as_ref().map(|u| u.id).unwrap_or_default(),
+                        created_by: person_id
+                    }
+                }
+            }
+            a {
+                class: "px-4 py-3 w-full text-center text-cyan-400 border border-cyan-400 rounded-3xl",
+                href: "/api/export",
+                download: "updown.json",
+                "Download your data"
+            }
+            if organization.is_none() {
+                rsx! {
+                    current_user.as_ref().map(|user| rsx! { RecoveryEmail { user: user.clone() } })
+                    Passkeys { user_id: person_id }
+                    RecentLogins { user_id: person_id }
+                    div {
+                        class: "flex flex-col gap-2 border border-red-500 rounded-md p-4",
+                        h2 { class: "text-sm text-red-500", "Danger zone" }
+                        p {
+                            class: "text-xs text-gray-500 dark:text-gray-400",
+                            "A new login code stops the old one from working, anyone logged in stays logged in."
+                        }
+                        Button { onclick: onrotate, "Get a new login code" }
+                        if *deleting.get() {
+                            rsx! {
+                                Button { id: "delete-account-btn", "Really delete your account and all your sites?" }
+                            }
+                        } else {
+                            rsx! {
+                                Button { onclick: move |_| deleting.set(true), "Delete account" }
+                            }
+                        }
+                    }
+                }
+            }
         }
     })
 }
 
-fn Index(cx: Scope) -> Element {
+/// The name, timezone, digest and default interval, saved together.
+#[inline_props]
+fn AccountSettings(cx: Scope, user: User) -> Element {
+    let errors = use_state(cx, FieldErrors::default);
+    let saving = use_state(cx, || false);
+    let toasts = use_shared_state::<Toasts>(cx).unwrap();
+    let ss = use_shared_state::<RootProps>(cx).unwrap();
+    let locale = use_locale(cx);
+    let user_id = user.id;
+    let onsave = move |event: FormEvent| {
+        if *saving.get() {
+            return;
+        }
+        let name = User::normalize_name(&form_value(&event, "name"));
+        let timezone = form_value(&event, "timezone").trim().to_string();
+        let digest = match form_value(&event, "digest").as_str() {
+            "daily" => Frequency::Daily,
+            "weekly" => Frequency::Weekly,
+            _ => Frequency::Off,
+        };
+        let new_locale = Locale::parse(&form_value(&event, "locale")).unwrap_or_default();
+        let interval_secs = form_value(&event, "default_interval_secs")
+            .parse::<i64>()
+            .unwrap_or_default();
+        let mut invalid = FieldErrors::default();
+        if name.is_err() {
+            invalid.add("name", t(locale, "settings-name-too-long"));
+        }
+        if timezone.parse::<chrono_tz::Tz>().is_err() {
+            invalid.add("timezone", t(locale, "settings-timezone-invalid"));
+        }
+        if !Site::INTERVALS.contains(&interval_secs) {
+            invalid.add(
+                "default_interval_secs",
+                t(locale, "settings-interval-invalid"),
+            );
+        }
+        let name = match name {
+            Ok(name) if invalid.is_empty() => name,
+            _ => {
+                errors.set(invalid);
+                return;
+            }
+        };
+        saving.set(true);
+        cx.spawn({
+            to_owned![errors, saving, toasts, ss];
+            async move {
+                let saved = async {
+                    db().update_user_name(user_id, name.as_deref()).await?;
+                    db().update_user_timezone(user_id, &timezone).await?;
+                    db().update_user_digest(user_id, digest).await?;
+                    db().update_user_locale(user_id, new_locale).await?;
+                    db().update_default_interval(user_id, interval_secs).await
+                }
+                .await;
+                match saved {
+                    Ok(_) => {
+                        errors.set(FieldErrors::default());
+                        // every view is shown in the new language right away
+                        {
+                            let mut root = ss.write();
+                            root.locale = new_locale;
+                            if let Some(user) = root.current_user.as_mut() {
+                                user.locale = new_locale;
+                            }
+                        }
+                        toasts.write().info(t(new_locale, "settings-saved"));
+                    }
+                    Err(err) => {
+                        tracing::warn!("could not save settings: {}", err);
+                        errors.set(FieldErrors::form(t(locale, "settings-save-failed")));
+                    }
+                }
+                saving.set(false);
+            }
+        })
+    };
+    let name = user.name.clone().unwrap_or_default();
+    let digests = [
+        (Frequency::Off, "off", t(locale, "settings-digest-off")),
+        (
+            Frequency::Daily,
+            "daily",
+            t(locale, "settings-digest-daily"),
+        ),
+        (
+            Frequency::Weekly,
+            "weekly",
+            t(locale, "settings-digest-weekly"),
+        ),
+    ];
+    let intervals = Site::INTERVALS.map(|interval| {
+        let label = t_with(
+            locale,
+            "settings-interval",
+            [("interval", notifications::format_duration(interval).into())],
+        );
+        (interval, label)
+    });
+    let title = t(locale, "settings-title");
+    let name_placeholder = t(locale, "settings-name-placeholder");
+    let save_label = t(locale, "settings-save");
+    let select_class = "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black";
     cx.render(rsx! {
-        AddSite { id: "signup-btn" }
+        form {
+            onsubmit: onsave,
+            class: "flex flex-col gap-2",
+            h2 { class: "text-sm dark:text-white", "{title}" }
+            TextInput {
+                name: "name",
+                value: "{name}",
+                placeholder: "{name_placeholder}",
+                error: errors.get().get("name").unwrap_or_default()
+            }
+            TextInput {
+                name: "timezone",
+                value: "{user.timezone}",
+                placeholder: "Europe/Berlin",
+                error: errors.get().get("timezone").unwrap_or_default()
+            }
+            select {
+                name: "digest",
+                class: "{select_class}",
+                digests.iter().map(|(frequency, value, label)| rsx! {
+                    option {
+                        key: "{value}",
+                        value: "{value}",
+                        selected: user.digest == *frequency,
+                        "{label}"
+                    }
+                })
+            }
+            select {
+                name: "default_interval_secs",
+                class: "{select_class}",
+                intervals.iter().map(|(interval, label)| rsx! {
+                    option {
+                        key: "{interval}",
+                        value: "{interval}",
+                        selected: *interval == user.default_interval_secs,
+                        "{label}"
+                    }
+                })
+            }
+            select {
+                name: "locale",
+                class: "{select_class}",
+                Locale::ALL.iter().map(|language| rsx! {
+                    option {
+                        key: "{language.as_str()}",
+                        value: "{language.as_str()}",
+                        selected: *language == user.locale,
+                        "{language.label()}"
+                    }
+                })
+            }
+            FormError {
+                error: errors
+                    .get()
+                    .get("default_interval_secs")
+                    .or(errors.get().form_error())
+                    .unwrap_or_default()
+            }
+            Button { disabled: *saving.get(), "{save_label}" }
+        }
     })
 }
 
+/// Owners make invite links here, one per person since a link works
+/// once.
 #[inline_props]
-fn Monitors<'a>(cx: Scope, sites: &'a Vec<Site>) -> Element {
+fn Invitations(cx: Scope, organization_id: i64, user_id: i64) -> Element {
+    let url = use_state(cx, || None::<String>);
+    let oninvite = move |event: FormEvent| {
+        let role = match form_value(&event, "role").as_str() {
+            "owner" => Role::Owner,
+            "viewer" => Role::Viewer,
+            _ => Role::Member,
+        };
+        cx.spawn({
+            to_owned![url, organization_id, user_id];
+            async move {
+                match db().insert_invitation(organization_id, role, user_id).await {
+                    Ok(invitation) => url.set(Some(invitation_url(&invitation))),
+                    Err(err) => tracing::warn!("could not add invitation: {}", err),
+                }
+            }
+        })
+    };
     cx.render(rsx! {
-        div {
-            class: "flex flex-col gap-4",
-            sites.iter().map(|site| rsx! {
-                ShowSite {
-                    key: "{site.id}",
-                    site: site
+        form {
+            onsubmit: oninvite,
+            class: "flex flex-col gap-2",
+            h2 { class: "text-sm dark:text-white", "Invite someone" }
+            select {
+                name: "role",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                option { value: "member", "Member" }
+                option { value: "viewer", "Viewer" }
+                option { value: "owner", "Owner" }
+            }
+            url.get().as_ref().map(|url| rsx! {
+                p {
+                    class: "text-xs break-all text-gray-500 dark:text-gray-400",
+                    "Send them this link, it works once within a week: {url}"
                 }
             })
+            Button { "Create invite link" }
         }
     })
 }
 
-fn Header(cx: Scope) -> Element {
+/// Instance totals and a way to stop checking abusive sites.
+fn AdminRoot(cx: Scope) -> Element {
+    const SITES: i64 = 50;
+    let search = use_state(cx, String::new);
+    let stats = use_future(cx, (), |_| async move {
+        db().instance_stats().await.unwrap_or_default()
+    });
+    let found = use_future(cx, (search.get().clone(),), |(search,)| async move {
+        db().search_sites(&search, SITES).await.unwrap_or_default()
+    });
+    let ontoggle = move |site: Site| {
+        cx.spawn({
+            to_owned![found, stats];
+            async move {
+                let disabled = site.disabled_at.is_none();
+                if db().set_site_disabled(site.id, disabled).await.is_ok() {
+                    found.restart();
+                    stats.restart();
+                }
+            }
+        })
+    };
+    let stats = stats.value().cloned().unwrap_or_default();
+    let checks_per_minute = format!("{:.1}", stats.checks_per_minute());
+    let totals = [
+        ("users", stats.users.to_string()),
+        ("sites", stats.sites.to_string()),
+        ("disabled sites", stats.disabled_sites.to_string()),
+        ("checks per minute", checks_per_minute),
+        ("failing sites", stats.failing_sites.to_string()),
+        ("queued alerts", stats.queue_depth.to_string()),
+    ];
     cx.render(rsx! {
         div {
-            class: "flex md:flex-col md:gap-2",
-            h1 { class: "text-xl md:text-4xl md:text-center text-left", "updown" }
-            h2 { class: "text-xl text-center hidden md:block", "your friendly neighborhood uptime monitor" }
+            class: "flex flex-col gap-8 pt-16 px-4 max-w-md mx-auto",
+            h1 { class: "text-2xl", "Admin" }
+            div {
+                class: "grid grid-cols-3 gap-4 text-center",
+                totals.iter().map(|(label, value)| rsx! {
+                    div {
+                        key: "{label}",
+                        p { class: "text-2xl", "{value}" }
+                        p { class: "text-xs text-gray-500 dark:text-gray-400", "{label}" }
+                    }
+                })
+            }
+            input {
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                r#type: "text",
+                placeholder: "Search sites by url",
+                "data-shortcut": "/",
+                value: "{search}",
+                oninput: move |event| search.set(event.value.clone())
+            }
+            div {
+                class: "flex flex-col gap-2",
+                found.value().into_iter().flatten().map(|site| {
+                    let toggled = site.clone();
+                    let label = match site.disabled_at {
+                        Some(_) => "Enable",
+                        None => "Disable",
+                    };
+                    rsx! {
+                        div {
+                            key: "{site.id}",
+                            class: "flex justify-between gap-2 text-sm",
+                            span { class: "truncate", title: "{site.url}", "{site.url}" }
+                            span { class: "shrink-0 text-gray-500 dark:text-gray-400", "user {site.user_id}" }
+                            button {
+                                class: "shrink-0 text-cyan-400",
+                                onclick: move |_| ontoggle(toggled.clone()),
+                                "{label}"
+                            }
+                        }
+                    }
+                })
+            }
         }
     })
 }
 
-#[derive(Default, Clone, PartialEq)]
-enum View {
-    #[default]
-    Index,
-    Monitors,
-    Account,
-    Login,
-}
-
+/// The organizations the person is in, to switch between, and a form to
+/// start a new one. Switching goes through main.js since it changes the
+/// session. `active_id` is 0 while they're in their own account.
 #[inline_props]
-fn Nav<'a>(cx: Scope, onclick: EventHandler<'a, View>, active_view: &'a View) -> Element {
-    let ss = use_shared_state::<RootProps>(cx).unwrap();
-    let logged_in = ss.read().current_user.is_some();
-    cx.render(rsx! {
-        nav {
-            class: "fixed lg lg:top-0 lg:bottom-auto bottom-0 w-full py-6 dark:bg-gray-900",
-            ul {
-                class: "flex lg:justify-center lg:gap-4 justify-around",
-                if logged_in {
-                    rsx! {
-                        NavLink { active: **active_view == View::Monitors, onclick: move |_| onclick.call(View::Monitors), "Sites" }
-                        NavLink { active: **active_view == View::Account, onclick: move |_| onclick.call(View::Account), "Account" }
-                        NavLink { id: "logout-btn", "Logout" }
+fn Organizations(cx: Scope, user_id: i64, active_id: i64) -> Element {
+    let organizations = use_future(cx, (user_id,), |(user_id,)| async move {
+        db().organizations_by_user_id(user_id)
+            .await
+            .unwrap_or_default()
+    });
+    let error = use_state(cx, || None::<&'static str>);
+    let onadd = move |event: FormEvent| {
+        let name = form_value(&event, "name").trim().to_string();
+        cx.spawn({
+            to_owned![organizations, error, user_id];
+            async move {
+                if name.is_empty() {
+                    error.set(Some("Give the organization a name"));
+                    return;
+                }
+                match db().insert_organization(user_id, &name).await {
+                    Ok(_) => {
+                        error.set(None);
+                        organizations.restart();
                     }
-                } else {
-                    rsx! {
-                        NavLink { active: **active_view == View::Index, onclick: move |_| onclick.call(View::Index), "Home" }
-                        NavLink { active: **active_view == View::Login, onclick: move |_| onclick.call(View::Login), "Login" }
+                    Err(err) => {
+                        tracing::warn!("could not add organization: {}", err);
+                        error.set(Some("Couldn't create the organization"));
+                    }
+                }
+            }
+        })
+    };
+    let Some(rows) = organizations.value() else {
+        return cx.render(rsx! { div {} });
+    };
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col gap-2",
+            h2 { class: "text-sm dark:text-white", "Organizations" }
+            if *active_id != 0 {
+                rsx! {
+                    button {
+                        class: "text-left text-sm text-cyan-400",
+                        name: "switch-organization",
+                        value: "",
+                        "Back to your own account"
                     }
                 }
             }
+            rows.iter().filter(|row| row.id != *active_id).map(|row| rsx! {
+                button {
+                    key: "{row.id}",
+                    class: "text-left text-sm text-cyan-400",
+                    name: "switch-organization",
+                    value: "{row.id}",
+                    "Switch to {row.name}"
+                }
+            })
+            form {
+                onsubmit: onadd,
+                class: "flex flex-col gap-2",
+                TextInput { name: "name", placeholder: "New organization name" }
+                FormError { error: error.get().unwrap_or_default() }
+                Button { "Create organization" }
+            }
         }
     })
 }
 
 #[inline_props]
-fn NavLink<'a>(
-    cx: Scope,
-    active: Option<bool>,
-    onclick: Option<EventHandler<'a, ()>>,
-    id: Option<&'a str>,
-    children: Element<'a>,
-) -> Element {
-    let active_class = match active {
-        Some(true) => "text-cyan-400",
-        Some(false) | None => "",
-    };
-    let onclick = move |_| {
-        if let Some(click) = onclick {
-            click.call(())
-        }
+fn Channels(cx: Scope, user_id: i64) -> Element {
+    let user_channels = use_future(cx, (user_id,), |(user_id,)| async move {
+        db().channels_by_user_id(user_id).await.unwrap_or_default()
+    });
+    let Some(user_channels) = user_channels.value() else {
+        return cx.render(rsx! { div {} });
     };
-    let id = id.unwrap_or_default();
     cx.render(rsx! {
-        li {
-            class: "cursor-pointer group transition duration-300", onclick: onclick,
-            a { id: id, class: "{active_class}", children }
-            div { class: "max-w-0 group-hover:max-w-full transition-all duration-300 h-1 bg-cyan-400" }
+        div {
+            class: "flex flex-col gap-2",
+            user_channels.iter().map(|channel| rsx! {
+                ShowChannel {
+                    key: "{channel.id}",
+                    channel: channel.clone()
+                }
+            })
         }
     })
 }
 
+/// An optional email that can get a login link when the login code is
+/// lost.
 #[inline_props]
-fn AddSite<'a>(
-    cx: Scope,
-    id: Option<&'a str>,
-    onadd: Option<EventHandler<'a, FormEvent>>,
-) -> Element {
-    let id = id.unwrap_or_default();
-    let onsubmit = move |event| {
-        if let Some(onadd) = onadd {
-            onadd.call(event)
-        }
+fn RecoveryEmail(cx: Scope, user: User) -> Element {
+    let status = use_state(cx, || match (&user.email, user.email_verified_at) {
+        (Some(_), Some(_)) => "Verified".to_string(),
+        (Some(_), None) => "Check your inbox to verify it".to_string(),
+        (None, _) => String::with_capacity(0),
+    });
+    let email = user.email.clone().unwrap_or_default();
+    let user_id = user.id;
+    let onsave = move |event: FormEvent| {
+        let email = form_value(&event, "email").trim().to_string();
+        cx.spawn({
+            to_owned![status];
+            async move {
+                if !email.is_empty() && email.parse::<lettre::Address>().is_err() {
+                    status.set("That doesn't look like an email".to_string());
+                    return;
+                }
+                let email = Some(email).filter(|email| !email.is_empty());
+                if db()
+                    .update_user_email(user_id, email.as_deref())
+                    .await
+                    .is_err()
+                {
+                    status.set("Couldn't save the email".to_string());
+                    return;
+                }
+                let Some(email) = email else {
+                    status.set("Removed".to_string());
+                    return;
+                };
+                match send_email_verification(user_id, &email).await {
+                    Ok(_) => status.set("Check your inbox to verify it".to_string()),
+                    Err(_) => {
+                        status.set("Saved, but the verification email couldn't be sent".to_string())
+                    }
+                }
+            }
+        })
     };
     cx.render(rsx! {
         form {
-            onsubmit: onsubmit,
-            class: "flex flex-col gap-2 w-full",
-            TextInput { name: "url", placeholder: "https://example.com" }
-            Button { id: "{id}", "Monitor a site" }
+            onsubmit: onsave,
+            class: "flex flex-col gap-2",
+            h2 { class: "text-sm dark:text-white", "Recovery email" }
+            TextInput { name: "email", value: "{email}", placeholder: "you@example.com" }
+            p { class: "text-xs text-gray-500 dark:text-gray-400", "{status}" }
+            Button { "Save email" }
         }
     })
 }
 
-#[derive(Props, PartialEq)]
-struct ShowSiteProps<'a> {
-    site: &'a Site,
-}
-
-fn ShowSite<'a>(cx: Scope<'a, ShowSiteProps<'a>>) -> Element<'a> {
-    let ShowSiteProps { site } = cx.props;
-    let Site { url, id, .. } = site;
-    // let response = use_state(cx, || Response::default());
-    let response_future = use_future(cx, (), |_| {
-        to_owned![id];
-        async move { db().latest_response_by_site(id).await }
+/// Passkeys log in without the login code, which keeps working as a
+/// fallback.
+#[inline_props]
+fn Passkeys(cx: Scope, user_id: i64) -> Element {
+    let passkeys = use_future(cx, (user_id,), |(user_id,)| async move {
+        db().passkeys_by_user_id(user_id).await.unwrap_or_default()
     });
-    let status = match response_future.value() {
-        Some(Ok(response)) => {
-            if response.status_code >= 200 && response.status_code < 300 {
-                "Online"
-            } else {
-                "Offline"
+    let ondelete = move |id: i64| {
+        cx.spawn({
+            to_owned![passkeys, user_id];
+            async move {
+                if db().delete_passkey(user_id, id).await.is_ok() {
+                    passkeys.restart();
+                }
             }
-        }
-        Some(Err(_)) => "Unknown",
-        None => "Loading",
+        })
     };
+    let Some(rows) = passkeys.value() else {
+        return cx.render(rsx! { div {} });
+    };
+    let now = Database::now();
     cx.render(rsx! {
         div {
-            class: "border border-gray-200 dark:border-gray-800 dark:text-white p-2 rounded-md flex items-center justify-between",
-            div { "{url}" }
-            div {
-                class: "flex items-center gap-x-1.5",
-                div {
-                    class: "flex-none rounded-full bg-emerald-500/20 p-1",
+            class: "flex flex-col gap-2",
+            h2 { class: "text-sm dark:text-white", "Passkeys" }
+            rows.iter().map(|row| {
+                let id = row.id;
+                let used = match row.last_used_at {
+                    Some(at) => format!("used {} ago", notifications::format_duration(now - at)),
+                    None => "never used".to_string(),
+                };
+                rsx! {
                     div {
-                        class: "h-1.5 w-1.5 rounded-full bg-emerald-500"
+                        key: "{row.id}",
+                        class: "flex justify-between gap-2 text-xs text-gray-500 dark:text-gray-400",
+                        span { "added {notifications::format_duration(now - row.created_at)} ago, {used}" }
+                        button {
+                            class: "text-cyan-400",
+                            onclick: move |_| ondelete(id),
+                            "Remove"
+                        }
                     }
                 }
-                p {
-                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400", "{status}"
-                }
-            }
+            })
+            Button { id: "passkey-register-btn", "Add a passkey" }
         }
     })
 }
 
-#[derive(Props)]
-struct ButtonProps<'a> {
-    #[props(optional)]
-    id: Option<&'a str>,
-    #[props(optional)]
-    onclick: Option<EventHandler<'a, MouseEvent>>,
-    children: Element<'a>,
-}
-
-fn Button<'a>(cx: Scope<'a, ButtonProps<'a>>) -> Element {
-    let ButtonProps {
-        id,
-        onclick,
-        children,
-    } = cx.props;
-    let onclick = move |event| {
-        if let Some(click) = onclick {
-            click.call(event);
-        }
+/// Bearer tokens for `/api/v1`. A new token is shown once, only its hash
+/// is kept.
+#[inline_props]
+fn ApiTokens(cx: Scope, user_id: i64, created_by: i64) -> Element {
+    let created = use_state(cx, || None::<String>);
+    let api_tokens = use_future(cx, (user_id,), |(user_id,)| async move {
+        db().api_tokens_by_user_id(user_id)
+            .await
+            .unwrap_or_default()
+    });
+    let oncreate = move |event: FormEvent| {
+        let name = form_value(&event, "name").trim().to_string();
+        let scopes = match form_value(&event, "scopes").as_str() {
+            "read" => vec![updown::Scope::Read],
+            _ => updown::Scope::ALL.to_vec(),
+        };
+        cx.spawn({
+            to_owned![api_tokens, created, user_id, created_by];
+            async move {
+                let name = Some(name)
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or("api token".to_string());
+                match db()
+                    .insert_api_token(user_id, created_by, &name, &scopes)
+                    .await
+                {
+                    Ok((_, token)) => {
+                        created.set(Some(token));
+                        api_tokens.restart();
+                    }
+                    Err(err) => tracing::warn!("could not add api token: {}", err),
+                }
+            }
+        })
     };
-    let id = id.unwrap_or_default();
-    cx.render(rsx! {
-        button {
-            id: id,
-            class: "px-4 py-3 w-full bg-cyan-400 text-white rounded-3xl box-shadow-md shadow-cyan-600 hover:box-shadow-xs hover:top-0.5 active:shadow-none active:top-1 relative",
-            onclick: onclick,
-            children
-        }
-    })
-}
-
-fn Fab<'a>(cx: Scope<'a, ButtonProps<'a>>) -> Element {
-    let ButtonProps {
-        id,
-        onclick,
-        children,
-    } = cx.props;
-    let onclick = move |event| {
-        if let Some(click) = onclick {
-            click.call(event);
-        }
+    let onrevoke = move |id: i64| {
+        cx.spawn({
+            to_owned![api_tokens, user_id];
+            async move {
+                if db().delete_api_token(user_id, id).await.is_ok() {
+                    api_tokens.restart();
+                }
+            }
+        })
     };
-    let id = id.unwrap_or_default();
+    let Some(rows) = api_tokens.value() else {
+        return cx.render(rsx! { div {} });
+    };
+    let now = Database::now();
     cx.render(rsx! {
-        div {
-            class: "absolute bottom-24 right-4 z-20",
-            button {
-                id: id,
-                class: "h-12 w-12 rounded-full bg-cyan-400 text-white box-shadow-md shadow-cyan-600 hover:box-shadow-xs hover:top-0.5 active:shadow-none active:top-1 relative",
-                onclick: onclick,
-                children
+        form {
+            onsubmit: oncreate,
+            class: "flex flex-col gap-2",
+            h2 { class: "text-sm dark:text-white", "Api tokens" }
+            rows.iter().map(|row| {
+                let id = row.id;
+                let used = match row.last_used_at {
+                    Some(at) => format!("used {} ago", notifications::format_duration(now - at)),
+                    None => "never used".to_string(),
+                };
+                rsx! {
+                    div {
+                        key: "{row.id}",
+                        class: "flex justify-between gap-2 text-xs text-gray-500 dark:text-gray-400",
+                        span { class: "truncate", "{row.name}, {row.scopes}, {used}" }
+                        button {
+                            class: "text-cyan-400",
+                            r#type: "button",
+                            onclick: move |_| onrevoke(id),
+                            "Revoke"
+                        }
+                    }
+                }
+            })
+            created.get().as_ref().map(|token| rsx! {
+                p {
+                    class: "text-xs break-all text-gray-500 dark:text-gray-400",
+                    "Copy it now, it won't be shown again: {token}"
+                }
+            })
+            TextInput { name: "name", placeholder: "What it's for" }
+            select {
+                name: "scopes",
+                class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
+                option { value: "read", "Read only" }
+                option { value: "write", "Read and write" }
             }
+            Button { "Create api token" }
         }
     })
 }
 
-#[derive(Props)]
-struct TextInputProps<'a> {
-    #[props(optional)]
-    placeholder: Option<&'a str>,
-    name: &'a str,
-}
-
-fn TextInput<'a>(cx: Scope<'a, TextInputProps<'a>>) -> Element {
-    let TextInputProps { name, placeholder } = cx.props;
+/// Where the login code was used lately, so a login that wasn't yours
+/// stands out.
+#[inline_props]
+fn RecentLogins(cx: Scope, user_id: i64) -> Element {
+    const LIMIT: i64 = 10;
+    let logins = use_future(cx, (user_id,), |(user_id,)| async move {
+        db().recent_logins(user_id, LIMIT).await.unwrap_or_default()
+    });
+    let Some(logins) = logins.value() else {
+        return cx.render(rsx! { div {} });
+    };
     cx.render(rsx! {
-        input {
-            class: "rounded-lg px-2 py-3 border dark:border-gray-700 dark:text-white dark:bg-gray-800 outline-none text-black",
-            r#type: "text",
-            name: "{name}",
-            placeholder: placeholder.unwrap_or_default()
+        div {
+            class: "flex flex-col gap-1 text-xs text-gray-500 dark:text-gray-400",
+            h2 { class: "text-sm dark:text-white", "Recent logins" }
+            logins.iter().map(|login_row| {
+                let ip_address = login_row.ip_address.clone().unwrap_or("unknown ip".to_string());
+                let user_agent = login_row
+                    .user_agent
+                    .clone()
+                    .unwrap_or("unknown browser".to_string());
+                let when = login_row.created_at.ago(Timestamp::now());
+                rsx! {
+                    div {
+                        key: "{login_row.id}",
+                        class: "flex justify-between gap-2",
+                        span { "{ip_address}" }
+                        span { class: "truncate", title: "{user_agent}", "{user_agent}" }
+                        span { class: "shrink-0", "{when}" }
+                    }
+                }
+            })
         }
     })
 }
 
 #[inline_props]
-fn Account<'a>(
-    cx: Scope,
-    current_user: &'a Option<User>,
-    onnav: EventHandler<'a, View>,
-) -> Element {
-    let login_code = match current_user {
-        Some(u) => u.login_code.clone(),
-        None => "".to_string(),
+fn ShowChannel(cx: Scope, channel: NotificationChannel) -> Element {
+    // None until tested, then whether the test alert was delivered
+    let result = use_state(cx, || None::<Result<(), AppError>>);
+    let sending = use_state(cx, || false);
+    let ontest = move |_| {
+        cx.spawn({
+            to_owned![result, sending, channel];
+            async move {
+                sending.set(true);
+                result.set(Some(dispatcher().test(&channel).await));
+                sending.set(false);
+            }
+        })
+    };
+    let status = match (sending.get(), result.get()) {
+        (true, _) => "Sending...".to_string(),
+        (false, Some(Ok(_))) => "Delivered".to_string(),
+        (false, Some(Err(err))) => format!("Failed: {}", err),
+        (false, None) if channel.verified => "Verified".to_string(),
+        (false, None) => String::with_capacity(0),
     };
     cx.render(rsx! {
         div {
-            class: "grid place-content-center gap-4",
-            LoginCodeAlert { login_code: login_code }
-            Button { onclick: move |_| onnav.call(View::Monitors), "View your sites" }
+            class: "border border-gray-200 dark:border-gray-800 dark:text-white p-2 rounded-md flex items-center justify-between",
+            div { "{channel.kind}" }
+            div {
+                class: "flex items-center gap-x-1.5",
+                p {
+                    class: "text-xs leading-5 text-gray-500 dark:text-gray-400", "{status}"
+                }
+                button {
+                    class: "text-xs leading-5 text-cyan-400",
+                    disabled: *sending.get(),
+                    onclick: ontest,
+                    "Send test"
+                }
+            }
         }
     })
 }
 
+/// How long the copy button says it copied.
+const COPIED_FOR: std::time::Duration = std::time::Duration::from_secs(2);
+
 #[inline_props]
 fn LoginCodeAlert(cx: Scope, login_code: String) -> Element {
     let blur_class = use_state(cx, || "blur-sm");
+    let copied = use_state(cx, || false);
     let onclick = move |_| {
         to_owned![blur_class];
         if blur_class == "blur-sm" {
@@ -748,13 +6346,48 @@ fn LoginCodeAlert(cx: Scope, login_code: String) -> Element {
             blur_class.set("blur-sm");
         }
     };
+    // main.js writes the code to the clipboard, the liveview can't
+    let oncopy = move |_| {
+        copied.set(true);
+        cx.spawn({
+            to_owned![copied];
+            async move {
+                tokio::time::sleep(COPIED_FOR).await;
+                copied.set(false);
+            }
+        })
+    };
+    let copy_label = match copied.get() {
+        true => "Copied",
+        false => "Copy login code",
+    };
+    let qr_code = QrCode::new(login_code.as_bytes())
+        .map(|code| {
+            code.render::<svg::Color>()
+                .min_dimensions(160, 160)
+                .dark_color(svg::Color("#3b82f6"))
+                .light_color(svg::Color("#eff6ff"))
+                .build()
+        })
+        .unwrap_or_default();
     cx.render(rsx! {
         div {
             class: "bg-blue-50 p-4 rounded-md text-blue-500 flex flex-col gap-1",
             p { "This is the only identifier you need to use updown." }
             p { "No email, no username. Just simplicity." }
-            p { "Click to show your login code." }
+            p { "Click to show your login code, or scan it with your phone." }
             div { onclick: onclick, class: "cursor-pointer font-bold text-xl {blur_class}", "{login_code}" }
+            div {
+                onclick: onclick,
+                class: "cursor-pointer self-center {blur_class}",
+                dangerous_inner_html: "{qr_code}"
+            }
+            button {
+                class: "self-start text-sm underline",
+                "data-copy": "{login_code}",
+                onclick: oncopy,
+                "{copy_label}"
+            }
         }
     })
 }
@@ -0,0 +1,778 @@
+use crate::{
+    checker::{Check, FailureKind},
+    template,
+    token::{Action, Claims, Signer},
+    AppError, Database, Incident, Login, NotificationChannel, Site,
+};
+use email::Mailer;
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, net::IpAddr, time::Duration};
+
+pub mod apprise;
+pub mod discord;
+pub mod email;
+pub mod matrix;
+pub mod ntfy;
+pub mod opsgenie;
+pub mod pagerduty;
+pub mod pushover;
+pub mod slack;
+pub mod teams;
+pub mod telegram;
+pub mod webhook;
+
+/// How many sites belonging to one user have to go down in the same
+/// watch cycle before their alerts are collapsed into a single summary.
+pub const BATCH_THRESHOLD: usize = 3;
+
+/// How many times a failed delivery is retried before giving up.
+const RETRIES: u32 = 3;
+
+/// How long the one-click links in a notification keep working.
+const LINK_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteState {
+    #[default]
+    Up,
+    Down,
+    /// Up but breaking one of the user's alert rules, like slow responses.
+    Degraded,
+}
+
+impl SiteState {
+    pub fn previous(&self) -> Self {
+        match self {
+            SiteState::Up => SiteState::Down,
+            SiteState::Down | SiteState::Degraded => SiteState::Up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SiteEvent {
+    pub site: Site,
+    pub state: SiteState,
+    pub status_code: i64,
+    pub ip: Option<IpAddr>,
+    pub failure: Option<FailureKind>,
+    pub latency_ms: i64,
+    pub incident: Option<Incident>,
+    /// Set on the "still down" reminders sent while an incident stays open.
+    pub reminder: bool,
+    /// Signed one-click link acknowledging the incident.
+    pub acknowledge_url: Option<String>,
+    /// The alert rule behind a degraded event or its recovery, e.g.
+    /// `latency-3` or `certificate`.
+    pub rule: Option<String>,
+    /// Explains events that aren't plain up or down, replacing the default
+    /// message.
+    pub detail: Option<String>,
+    /// The channel's rendered template, replacing the whole message.
+    pub text: Option<String>,
+    /// The names of the site's tags, filled in when it's dispatched.
+    #[serde(default)]
+    pub site_tags: Vec<String>,
+}
+
+impl SiteEvent {
+    /// The event for a check, up or down depending on its response.
+    pub fn new(site: &Site, check: &Check) -> Self {
+        let current = check.response();
+        let state = match current.is_up() {
+            true => SiteState::Up,
+            false => SiteState::Down,
+        };
+        Self {
+            site: site.clone(),
+            state,
+            status_code: current.status_code,
+            ip: check.ip,
+            failure: check.failure,
+            latency_ms: check.latency_ms,
+            ..Default::default()
+        }
+    }
+
+    fn tld(&self) -> Option<String> {
+        let url = Url::parse(&self.site.url).ok()?;
+        let host = url.host_str()?;
+        host.rsplit('.').next().map(|tld| tld.to_string())
+    }
+
+    /// How long the site was down, known once its incident resolves.
+    pub fn downtime(&self) -> Option<i64> {
+        self.incident.as_ref()?.duration()
+    }
+
+    pub fn last_failing_status_code(&self) -> Option<i64> {
+        let incident = self.incident.as_ref()?;
+        incident.last_status_code.or(Some(incident.status_code))
+    }
+
+    /// Stable across the down and up events of the same incident, for
+    /// integrations that pair them up on their side.
+    pub fn incident_key(&self) -> String {
+        match (&self.incident, &self.rule) {
+            (Some(incident), _) => format!("updown-incident-{}", incident.id),
+            (None, Some(rule)) => format!("updown-rule-{}-site-{}", rule, self.site.id),
+            (None, None) => format!("updown-site-{}", self.site.id),
+        }
+    }
+
+    /// Labels for integrations that support tagging alerts, the site's own
+    /// tags included.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = vec!["updown".to_string()];
+        tags.extend(self.site_tags.iter().cloned());
+        if let Some(failure) = self.failure {
+            tags.push(format!("{:?}", failure).to_lowercase());
+        }
+        tags
+    }
+
+    pub fn message(&self) -> String {
+        if let Some(text) = &self.text {
+            return text.clone();
+        }
+        match (self.state, &self.detail) {
+            (_, Some(detail)) => format!("{} {}", self.site_name(), detail),
+            (SiteState::Down, None) if self.reminder => {
+                let started_at = self.incident.as_ref().map(|incident| incident.started_at);
+                let downtime = Database::now() - started_at.unwrap_or_else(Database::now);
+                format!(
+                    "{} is still down ({}) after {}",
+                    self.site_name(),
+                    self.status_code,
+                    format_duration(downtime)
+                )
+            }
+            (SiteState::Down, None) => {
+                format!("{} is down ({})", self.site_name(), self.status_code)
+            }
+            (SiteState::Degraded, None) => format!("{} is degraded", self.site_name()),
+            (SiteState::Up, None) => match (self.downtime(), self.last_failing_status_code()) {
+                (Some(downtime), Some(status_code)) => format!(
+                    "{} is back up after {} down (last status code {})",
+                    self.site_name(),
+                    format_duration(downtime),
+                    status_code
+                ),
+                _ => format!("{} is back up", self.site_name()),
+            },
+        }
+    }
+
+    pub fn site_name(&self) -> &str {
+        self.site.display_name()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Notification {
+    Site(SiteEvent),
+    Outage {
+        user_id: i64,
+        events: Vec<SiteEvent>,
+        hint: Option<String>,
+    },
+}
+
+impl Notification {
+    /// A made up down event for checking that a channel is set up right.
+    pub fn test(user_id: i64, origin: &str) -> Self {
+        let mut site = Site::default();
+        site.user_id = user_id;
+        site.url = origin.to_string();
+        site.name = Some("updown test alert".to_string());
+        Notification::Site(SiteEvent {
+            site,
+            state: SiteState::Down,
+            status_code: 503,
+            failure: Some(FailureKind::Status),
+            ..Default::default()
+        })
+    }
+
+    pub fn user_id(&self) -> i64 {
+        match self {
+            Notification::Site(event) => event.site.user_id,
+            Notification::Outage { user_id, .. } => *user_id,
+        }
+    }
+
+    /// Narrows the notification down to the given sites, an empty list
+    /// meaning every site.
+    pub fn only(&self, site_ids: &[i64]) -> Option<Notification> {
+        if site_ids.is_empty() {
+            return Some(self.clone());
+        }
+        match self {
+            Notification::Site(event) => match site_ids.contains(&event.site.id) {
+                true => Some(self.clone()),
+                false => None,
+            },
+            Notification::Outage {
+                user_id,
+                events,
+                hint,
+            } => {
+                let events: Vec<SiteEvent> = events
+                    .iter()
+                    .filter(|event| site_ids.contains(&event.site.id))
+                    .cloned()
+                    .collect();
+                match events.is_empty() {
+                    true => None,
+                    false => Some(Notification::Outage {
+                        user_id: *user_id,
+                        events,
+                        hint: hint.clone(),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// The opposite of `only`, drops the given sites.
+    pub fn except(&self, site_ids: &[i64]) -> Option<Notification> {
+        self.filter(|event| !site_ids.contains(&event.site.id))
+    }
+
+    /// Drops the down and degraded events of the given sites, for channels
+    /// cooling down. Recoveries always go out, otherwise a site that's
+    /// back up within the cooldown would never be reported as up.
+    pub fn except_alerts(&self, site_ids: &[i64]) -> Option<Notification> {
+        self.filter(|event| event.state == SiteState::Up || !site_ids.contains(&event.site.id))
+    }
+
+    fn filter(&self, keep: impl Fn(&SiteEvent) -> bool) -> Option<Notification> {
+        match self {
+            Notification::Site(event) => match keep(event) {
+                true => Some(self.clone()),
+                false => None,
+            },
+            Notification::Outage {
+                user_id,
+                events,
+                hint,
+            } => {
+                let events: Vec<SiteEvent> =
+                    events.iter().filter(|event| keep(event)).cloned().collect();
+                match events.is_empty() {
+                    true => None,
+                    false => Some(Notification::Outage {
+                        user_id: *user_id,
+                        events,
+                        hint: hint.clone(),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Renders the template into every event's message.
+    pub fn with_template(&self, template: &str) -> Notification {
+        let mut notification = self.clone();
+        let events = match &mut notification {
+            Notification::Site(event) => vec![event],
+            Notification::Outage { events, .. } => events.iter_mut().collect(),
+        };
+        for event in events {
+            event.text = Some(template::render(template, event));
+        }
+        notification
+    }
+
+    pub fn events(&self) -> Vec<&SiteEvent> {
+        match self {
+            Notification::Site(event) => vec![event],
+            Notification::Outage { events, .. } => events.iter().collect(),
+        }
+    }
+
+    pub fn message(&self, origin: &str) -> String {
+        match self {
+            Notification::Site(event) => event.message(),
+            Notification::Outage { events, hint, .. } => {
+                let message = format!("{} monitors went down {}/?state=down", events.len(), origin);
+                match hint {
+                    Some(hint) => format!("{} ({})", message, hint),
+                    None => message,
+                }
+            }
+        }
+    }
+}
+
+/// Groups the events of one watch cycle by user and collapses the down
+/// events of any user with more than `threshold` of them into one outage.
+pub fn batch(events: Vec<SiteEvent>, threshold: usize) -> Vec<Notification> {
+    let mut by_user: BTreeMap<i64, Vec<SiteEvent>> = BTreeMap::new();
+    for event in events {
+        by_user.entry(event.site.user_id).or_default().push(event);
+    }
+    let mut notifications = vec![];
+    for (user_id, events) in by_user {
+        let (down, up): (Vec<_>, Vec<_>) = events
+            .into_iter()
+            .partition(|event| event.state == SiteState::Down);
+        if down.len() > threshold {
+            notifications.push(Notification::Outage {
+                user_id,
+                hint: hint(&down),
+                events: down,
+            });
+        } else {
+            notifications.extend(down.into_iter().map(Notification::Site));
+        }
+        notifications.extend(up.into_iter().map(Notification::Site));
+    }
+    notifications
+}
+
+/// Looks for something every failing site has in common so whoever gets
+/// the outage notification knows where to start looking.
+pub fn hint(events: &[SiteEvent]) -> Option<String> {
+    if let Some(ip) = shared(events, |event| event.ip) {
+        return Some(format!("all failing monitors resolve to {}", ip));
+    }
+    if let Some(failure) = shared(events, |event| event.failure) {
+        return Some(format!("all failing monitors fail with {}", failure));
+    }
+    if let Some(tld) = shared(events, |event| event.tld()) {
+        return Some(format!("all failing monitors are on .{}", tld));
+    }
+    None
+}
+
+fn shared<T: PartialEq>(events: &[SiteEvent], f: impl Fn(&SiteEvent) -> Option<T>) -> Option<T> {
+    let mut values = events.iter().map(f);
+    let first = values.next()??;
+    match values.all(|value| value.as_ref() == Some(&first)) {
+        true => Some(first),
+        false => None,
+    }
+}
+
+/// Fans notifications out to every channel their user has configured.
+pub struct Dispatcher<'a> {
+    db: &'a Database,
+    client: reqwest::Client,
+    origin: String,
+    signer: Option<Signer>,
+    mailer: Option<Mailer>,
+}
+
+impl<'a> Dispatcher<'a> {
+    pub fn new(db: &'a Database, origin: String) -> Self {
+        Self {
+            db,
+            client: reqwest::Client::new(),
+            origin,
+            signer: None,
+            mailer: None,
+        }
+    }
+
+    /// Lets notifications carry signed one-click action links.
+    pub fn signer(mut self, signer: Signer) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Lets email channels and digests go out through this SMTP connection.
+    pub fn mailer(mut self, mailer: Mailer) -> Self {
+        self.mailer = Some(mailer);
+        self
+    }
+
+    fn with_links(&self, mut notification: Notification) -> Notification {
+        let Some(signer) = &self.signer else {
+            return notification;
+        };
+        let events = match &mut notification {
+            Notification::Site(event) => vec![event],
+            Notification::Outage { events, .. } => events.iter_mut().collect(),
+        };
+        for event in events {
+            let Some(incident) = &event.incident else {
+                continue;
+            };
+            if event.state != SiteState::Down || incident.acknowledged_at.is_some() {
+                continue;
+            }
+            let claims = Claims::new(
+                Action::Acknowledge,
+                event.site.user_id,
+                incident.id,
+                Database::now() + LINK_TTL_SECS,
+            );
+            event.acknowledge_url = Some(format!("{}/ack/{}", self.origin, signer.sign(&claims)));
+        }
+        notification
+    }
+
+    async fn with_tags(&self, mut notification: Notification) -> Notification {
+        let site_tags = match self.db.site_tags_by_user_id(notification.user_id()).await {
+            Ok(site_tags) => site_tags,
+            Err(err) => {
+                tracing::warn!("could not load site tags: {}", err);
+                return notification;
+            }
+        };
+        let events = match &mut notification {
+            Notification::Site(event) => vec![event],
+            Notification::Outage { events, .. } => events.iter_mut().collect(),
+        };
+        for event in events {
+            event.site_tags = site_tags
+                .iter()
+                .filter(|(site_id, _)| *site_id == event.site.id)
+                .map(|(_, name)| name.clone())
+                .collect();
+        }
+        notification
+    }
+
+    pub async fn dispatch(&self, notifications: Vec<Notification>) {
+        for notification in notifications {
+            let notification = self.with_links(notification);
+            let notification = self.with_tags(notification).await;
+            let channels = match self.db.channels_by_user_id(notification.user_id()).await {
+                Ok(channels) => channels,
+                Err(err) => {
+                    tracing::error!("could not load channels: {}", err);
+                    continue;
+                }
+            };
+            if channels.is_empty() {
+                tracing::info!(
+                    user_id = notification.user_id(),
+                    "{}",
+                    notification.message(&self.origin)
+                );
+            }
+            for channel in channels {
+                self.notify(&channel, &notification).await;
+            }
+        }
+    }
+
+    /// Sends to one channel, respecting its site routes, cooldown and the
+    /// user's quiet hours, and records what was sent.
+    async fn notify(&self, channel: &NotificationChannel, notification: &Notification) {
+        // channels routed to specific sites only hear about those
+        let site_ids = self
+            .db
+            .routed_site_ids(channel.id)
+            .await
+            .unwrap_or_default();
+        let Some(notification) = notification.only(&site_ids) else {
+            return;
+        };
+        let cooling_down = match channel.cooldown_secs {
+            0 => vec![],
+            secs => self
+                .db
+                .notified_site_ids(channel.id, Database::now() - secs)
+                .await
+                .unwrap_or_default(),
+        };
+        let Some(notification) = notification.except_alerts(&cooling_down) else {
+            return;
+        };
+        let user = self
+            .db
+            .user_by_id(channel.user_id)
+            .await
+            .unwrap_or_default();
+        if !user.is_quiet(Database::now()) {
+            return self.deliver(channel, &notification).await;
+        }
+        // only critical sites get through, the rest waits for quiet hours to end
+        let critical = self.db.critical_site_ids(user.id).await.unwrap_or_default();
+        if let Some(queued) = notification.except(&critical) {
+            let json = serde_json::to_string(&queued).unwrap_or_default();
+            if let Err(err) = self.db.queue_notification(channel, &json).await {
+                tracing::error!(channel_id = channel.id, "could not queue: {}", err);
+            }
+        }
+        if critical.is_empty() {
+            return;
+        }
+        if let Some(notification) = notification.only(&critical) {
+            self.deliver(channel, &notification).await;
+        }
+    }
+
+    /// Delivers what was queued during quiet hours once they are over.
+    pub async fn flush(&self) {
+        let queued = match self.db.queued_notifications().await {
+            Ok(queued) => queued,
+            Err(err) => {
+                tracing::error!("could not load queued notifications: {}", err);
+                return;
+            }
+        };
+        let now = Database::now();
+        for queued in queued {
+            let Ok(user) = self.db.user_by_id(queued.user_id).await else {
+                continue;
+            };
+            if user.is_quiet(now) {
+                continue;
+            }
+            let channel = self.db.channel_by_id(user.id, queued.channel_id).await;
+            let notification = serde_json::from_str::<Notification>(&queued.notification);
+            if let (Ok(channel), Ok(notification)) = (channel, notification) {
+                self.deliver(&channel, &notification).await;
+            }
+            _ = self.db.delete_queued_notification(queued.id).await;
+        }
+    }
+
+    async fn deliver(&self, channel: &NotificationChannel, notification: &Notification) {
+        match self.send(channel, notification).await {
+            Ok(_) => {
+                _ = self.db.verify_channel(channel.id).await;
+                for event in notification.events() {
+                    let incident_id = event.incident.as_ref().map(|incident| incident.id);
+                    _ = self
+                        .db
+                        .insert_notification_sent(channel.id, event.site.id, incident_id)
+                        .await;
+                }
+            }
+            Err(err) => {
+                tracing::error!(channel_id = channel.id, "could not notify: {}", err.chain());
+            }
+        }
+    }
+
+    /// Re-sends "still down" notifications for open incidents on channels
+    /// with a re-notification interval, once that interval has passed.
+    pub async fn remind(&self) {
+        let incidents = match self.db.open_incidents().await {
+            Ok(incidents) => incidents,
+            Err(err) => {
+                tracing::error!("could not load open incidents: {}", err);
+                return;
+            }
+        };
+        for incident in incidents {
+            let Ok(site) = self.db.site_by_id(incident.site_id).await else {
+                continue;
+            };
+            let channels = self
+                .db
+                .channels_by_user_id(site.user_id)
+                .await
+                .unwrap_or_default();
+            for channel in channels.iter().filter(|channel| channel.renotify_secs > 0) {
+                let last = self
+                    .db
+                    .last_notified_at(channel.id, site.id)
+                    .await
+                    .ok()
+                    .flatten()
+                    .unwrap_or(incident.started_at);
+                if Database::now() - last < channel.renotify_secs {
+                    continue;
+                }
+                let notification = Notification::Site(SiteEvent {
+                    site: site.clone(),
+                    state: SiteState::Down,
+                    status_code: incident.status_code,
+                    incident: Some(incident.clone()),
+                    reminder: true,
+                    ..Default::default()
+                });
+                let notification = self.with_links(notification);
+                self.notify(channel, &notification).await;
+            }
+        }
+    }
+
+    /// Sends a test notification and marks the channel verified if it
+    /// went through.
+    pub async fn test(&self, channel: &NotificationChannel) -> Result<(), AppError> {
+        let notification = Notification::test(channel.user_id, &self.origin);
+        self.send(channel, &notification).await?;
+        _ = self.db.verify_channel(channel.id).await;
+        Ok(())
+    }
+
+    /// Mails the digest to every email channel the user has.
+    pub async fn digest(&self, user_id: i64, subject: &str, body: String) -> Result<(), AppError> {
+        let mailer = self.mailer.as_ref().ok_or(AppError::UnsupportedChannel)?;
+        let channels = self
+            .db
+            .channels_by_user_id(user_id)
+            .await
+            .map_err(|source| AppError::DatabaseSelect { source })?;
+        for channel in channels
+            .iter()
+            .filter(|channel| channel.kind == email::KIND)
+        {
+            let email::Config { to } = config(channel)?;
+            mailer.send(&to, subject, body.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Tells the user's email and webhook channels about a login from an
+    /// ip or user agent they haven't logged in from before.
+    pub async fn new_login(&self, login: &Login) {
+        let channels = self
+            .db
+            .channels_by_user_id(login.user_id)
+            .await
+            .unwrap_or_default();
+        let message = format!(
+            "New login to updown from {} ({}). If this wasn't you, your login code has leaked.",
+            login.ip_address.as_deref().unwrap_or("an unknown address"),
+            login.user_agent.as_deref().unwrap_or("unknown browser")
+        );
+        for channel in channels {
+            let result = match (channel.kind.as_str(), &self.mailer) {
+                (email::KIND, Some(mailer)) => match config::<email::Config>(&channel) {
+                    Ok(email::Config { to }) => {
+                        mailer
+                            .send(&to, "New login to updown", message.clone())
+                            .await
+                    }
+                    Err(err) => Err(err),
+                },
+                (webhook::KIND, _) => {
+                    match webhook::login_request(&self.client, &channel, login, &message) {
+                        Ok(request) => send_with_retries(request).await,
+                        Err(err) => Err(err),
+                    }
+                }
+                _ => continue,
+            };
+            if let Err(err) = result {
+                tracing::error!(
+                    channel_id = channel.id,
+                    "could not send login alert: {}",
+                    err
+                );
+            }
+        }
+    }
+
+    pub async fn send(
+        &self,
+        channel: &NotificationChannel,
+        notification: &Notification,
+    ) -> Result<(), AppError> {
+        let templated;
+        let notification = match &channel.template {
+            Some(template) => {
+                templated = notification.with_template(template);
+                &templated
+            }
+            None => notification,
+        };
+        let (client, origin) = (&self.client, self.origin.as_str());
+        let request = match channel.kind.as_str() {
+            discord::KIND => discord::request(client, channel, notification, origin)?,
+            email::KIND => {
+                let mailer = self.mailer.as_ref().ok_or(AppError::UnsupportedChannel)?;
+                return email::send(mailer, channel, notification, origin).await;
+            }
+            matrix::KIND => matrix::request(client, channel, notification, origin)?,
+            ntfy::KIND => ntfy::request(client, channel, notification, origin)?,
+            opsgenie::KIND => {
+                for request in opsgenie::requests(client, channel, notification, origin)? {
+                    send_with_retries(request).await?;
+                }
+                return Ok(());
+            }
+            pagerduty::KIND => {
+                for request in pagerduty::requests(client, channel, notification, origin)? {
+                    send_with_retries(request).await?;
+                }
+                return Ok(());
+            }
+            pushover::KIND => pushover::request(client, channel, notification, origin)?,
+            slack::KIND => slack::request(client, channel, notification, origin)?,
+            teams::KIND => teams::request(client, channel, notification, origin)?,
+            telegram::KIND => telegram::request(client, channel, notification, origin)?,
+            webhook::KIND => webhook::request(client, channel, notification, origin)?,
+            kind => {
+                tracing::warn!("unsupported channel kind {}", kind);
+                return Err(AppError::UnsupportedChannel);
+            }
+        };
+        send_with_retries(request).await
+    }
+}
+
+/// Formats seconds as a short human duration like "2h 5m".
+pub fn format_duration(secs: i64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, _) => format!("{}m", minutes),
+        _ => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Sends the request, retrying with exponential backoff on network errors
+/// and non-2xx responses.
+async fn send_with_retries(request: RequestBuilder) -> Result<(), AppError> {
+    let mut attempt = 0;
+    loop {
+        let result = match request.try_clone() {
+            Some(request) => request.send().await.and_then(|res| res.error_for_status()),
+            None => return Err(AppError::Notify),
+        };
+        match result {
+            Ok(_) => return Ok(()),
+            Err(err) if attempt < RETRIES => {
+                attempt += 1;
+                tracing::warn!("delivery attempt {} failed: {}", attempt, err);
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+            }
+            Err(_) => return Err(AppError::Notify),
+        }
+    }
+}
+
+/// Parses a channel's json config into the integration's own config type.
+fn config<T: serde::de::DeserializeOwned>(channel: &NotificationChannel) -> Result<T, AppError> {
+    serde_json::from_str(&channel.config).map_err(|_| AppError::ChannelConfig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(state: SiteState) -> SiteEvent {
+        let mut site = Site::default();
+        site.id = 1;
+        site.user_id = 1;
+        site.url = "https://example.com".to_string();
+        SiteEvent {
+            site,
+            state,
+            status_code: 503,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recoveries_get_through_a_cooldown() {
+        // the down event went out, so the site is cooling down when it
+        // comes back up
+        let cooling_down = [1];
+        let down = Notification::Site(event(SiteState::Down));
+        let up = Notification::Site(event(SiteState::Up));
+        assert_eq!(down.except_alerts(&cooling_down), None);
+        assert_eq!(up.except_alerts(&cooling_down), Some(up.clone()));
+    }
+}
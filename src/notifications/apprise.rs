@@ -0,0 +1,161 @@
+use super::{
+    discord, email, matrix, ntfy, opsgenie, pagerduty, pushover, slack, teams, telegram, webhook,
+};
+use crate::AppError;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Turns an Apprise notification url like `tgram://bottoken/chat_id` into a
+/// channel kind and config, so urls from Apprise or Uptime Kuma can be
+/// pasted as is. Email urls only take the recipient, the mail still goes out
+/// through the instance's own SMTP settings.
+pub fn parse(url: &str) -> Result<(&'static str, serde_json::Value), AppError> {
+    let (scheme, rest) = url
+        .trim()
+        .split_once("://")
+        .ok_or(AppError::ChannelConfig)?;
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect::<HashMap<_, _>>();
+    let parts = rest
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    // the first part can carry credentials, e.g. user:token@host
+    let (userinfo, host) = match parts.first().and_then(|first| first.rsplit_once('@')) {
+        Some((userinfo, host)) => (Some(userinfo), host),
+        None => (None, parts.first().copied().unwrap_or_default()),
+    };
+    let (kind, config) = match (scheme, parts.as_slice()) {
+        ("slack", [_, b, c]) => (
+            slack::KIND,
+            value(slack::Config {
+                url: format!("https://hooks.slack.com/services/{}/{}/{}", host, b, c),
+            }),
+        ),
+        ("discord", [_, token]) => (
+            discord::KIND,
+            value(discord::Config {
+                url: format!("https://discord.com/api/webhooks/{}/{}", host, token),
+            }),
+        ),
+        ("tgram", [bot_token, chat_id]) => (
+            telegram::KIND,
+            value(telegram::Config {
+                bot_token: bot_token.to_string(),
+                chat_id: chat_id.to_string(),
+            }),
+        ),
+        ("pover", [_, ..]) => (
+            pushover::KIND,
+            value(pushover::Config {
+                user_key: userinfo.ok_or(AppError::ChannelConfig)?.to_string(),
+                api_token: host.to_string(),
+            }),
+        ),
+        ("ntfy" | "ntfys", [topic]) => (
+            ntfy::KIND,
+            value(ntfy::Config {
+                server: "https://ntfy.sh".to_string(),
+                topic: topic.to_string(),
+                access_token: query.get("token").map(|token| token.to_string()),
+            }),
+        ),
+        ("ntfy" | "ntfys", [_, topic]) => (
+            ntfy::KIND,
+            value(ntfy::Config {
+                server: match scheme {
+                    "ntfys" => format!("https://{}", host),
+                    _ => format!("http://{}", host),
+                },
+                topic: topic.to_string(),
+                access_token: query.get("token").map(|token| token.to_string()),
+            }),
+        ),
+        ("msteams", [_, b, c]) => (
+            teams::KIND,
+            value(teams::Config {
+                url: format!(
+                    "https://outlook.office.com/webhook/{}/IncomingWebhook/{}/{}",
+                    host, b, c
+                ),
+            }),
+        ),
+        ("msteams", [_, a, b, c]) => (
+            teams::KIND,
+            value(teams::Config {
+                url: format!(
+                    "https://{}.webhook.office.com/webhookb2/{}/IncomingWebhook/{}/{}",
+                    host, a, b, c
+                ),
+            }),
+        ),
+        ("pagerduty", [_, ..]) => (
+            pagerduty::KIND,
+            value(pagerduty::Config {
+                routing_key: userinfo.ok_or(AppError::ChannelConfig)?.to_string(),
+            }),
+        ),
+        ("opsgenie", [api_key, ..]) => (
+            opsgenie::KIND,
+            value(opsgenie::Config {
+                api_key: api_key.to_string(),
+                region: match query.get("region") {
+                    Some(&"eu") => opsgenie::Region::Eu,
+                    _ => opsgenie::Region::Us,
+                },
+            }),
+        ),
+        ("matrix" | "matrixs", [_, room_id]) => {
+            let userinfo = userinfo.ok_or(AppError::ChannelConfig)?;
+            let access_token = userinfo
+                .split_once(':')
+                .map(|(_, token)| token)
+                .unwrap_or(userinfo);
+            let homeserver = match scheme {
+                "matrixs" => format!("https://{}", host),
+                _ => format!("http://{}", host),
+            };
+            (
+                matrix::KIND,
+                value(matrix::Config {
+                    homeserver,
+                    access_token: access_token.to_string(),
+                    room_id: room_id.to_string(),
+                }),
+            )
+        }
+        ("mailto" | "mailtos", [_, ..]) => {
+            let to = match (query.get("to"), userinfo) {
+                (Some(to), _) => to.to_string(),
+                (None, Some(userinfo)) => {
+                    let user = userinfo.split(':').next().unwrap_or_default();
+                    format!("{}@{}", user, host)
+                }
+                (None, None) => return Err(AppError::ChannelConfig),
+            };
+            (email::KIND, value(email::Config { to }))
+        }
+        ("json" | "jsons", [_, ..]) => {
+            let scheme = match scheme {
+                "jsons" => "https",
+                _ => "http",
+            };
+            (
+                webhook::KIND,
+                value(webhook::Config {
+                    url: format!("{}://{}", scheme, rest),
+                    secret: query.get("secret").unwrap_or(&"").to_string(),
+                }),
+            )
+        }
+        _ => return Err(AppError::UnsupportedChannel),
+    };
+    Ok((kind, config))
+}
+
+fn value(config: impl Serialize) -> serde_json::Value {
+    serde_json::to_value(config).unwrap_or_default()
+}
@@ -0,0 +1,57 @@
+use super::{config, Notification, SiteEvent, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const KIND: &str = "discord";
+
+const GREEN: u32 = 0x2ecc71;
+const RED: u32 = 0xe74c3c;
+const YELLOW: u32 = 0xf1c40f;
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub url: String,
+}
+
+/// Builds a Discord webhook message with one color coded embed per site.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config { url } = config(channel)?;
+    let embeds: Vec<Value> = match notification {
+        Notification::Site(event) => vec![embed(event, origin)],
+        // discord caps a message at 10 embeds
+        Notification::Outage { events, .. } => events
+            .iter()
+            .take(10)
+            .map(|event| embed(event, origin))
+            .collect(),
+    };
+    let payload = json!({
+        "content": notification.message(origin),
+        "embeds": embeds,
+    });
+    Ok(client.post(url).json(&payload))
+}
+
+fn embed(event: &SiteEvent, origin: &str) -> Value {
+    let (title, color) = match event.state {
+        SiteState::Up => (format!("{} is up", event.site_name()), GREEN),
+        SiteState::Down => (format!("{} is down", event.site_name()), RED),
+        SiteState::Degraded => (format!("{} is degraded", event.site_name()), YELLOW),
+    };
+    json!({
+        "title": title,
+        "url": origin,
+        "color": color,
+        "fields": [
+            { "name": "Status code", "value": event.status_code.to_string(), "inline": true },
+            { "name": "Latency", "value": format!("{} ms", event.latency_ms), "inline": true },
+        ],
+    })
+}
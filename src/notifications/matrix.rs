@@ -0,0 +1,75 @@
+use super::{config, format_duration, Notification, SiteEvent};
+use crate::{AppError, NotificationChannel};
+use nanoid::nanoid;
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pub const KIND: &str = "matrix";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub homeserver: String,
+    pub access_token: String,
+    pub room_id: String,
+}
+
+/// Sends an m.room.message with a plain and an html body. The transaction
+/// id is fixed per notification so retries don't post twice.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config {
+        homeserver,
+        access_token,
+        room_id,
+    } = config(channel)?;
+    let mut url = Url::parse(&homeserver).map_err(|_| AppError::ChannelConfig)?;
+    url.path_segments_mut()
+        .map_err(|_| AppError::ChannelConfig)?
+        .pop_if_empty()
+        .extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            room_id.as_str(),
+            "send",
+            "m.room.message",
+        ])
+        .push(&nanoid!());
+    let events = match notification {
+        Notification::Site(event) => vec![event],
+        Notification::Outage { events, .. } => events.iter().collect(),
+    };
+    let message = notification.message(origin);
+    let items = events
+        .iter()
+        .map(|event| format!("<li>{}</li>", escape(&line(event))))
+        .collect::<String>();
+    let payload = json!({
+        "msgtype": "m.text",
+        "body": message,
+        "format": "org.matrix.custom.html",
+        "formatted_body": format!("<p><strong>{}</strong></p><ul>{}</ul>", escape(&message), items),
+    });
+    Ok(client.put(url).bearer_auth(access_token).json(&payload))
+}
+
+fn line(event: &SiteEvent) -> String {
+    let mut line = format!("{} ({})", event.site_name(), event.status_code);
+    if let Some(downtime) = event.downtime() {
+        line.push_str(&format!(", down for {}", format_duration(downtime)));
+    }
+    line
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
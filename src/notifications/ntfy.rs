@@ -0,0 +1,44 @@
+use super::{config, Notification, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+
+pub const KIND: &str = "ntfy";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub server: String,
+    pub topic: String,
+    pub access_token: Option<String>,
+}
+
+/// Publishes a plain text message to the topic, with the title, tags and
+/// click through link passed as ntfy headers.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config {
+        server,
+        topic,
+        access_token,
+    } = config(channel)?;
+    let tags = match notification {
+        Notification::Site(event) if event.state == SiteState::Up => "white_check_mark",
+        Notification::Site(event) if event.state == SiteState::Degraded => "warning",
+        Notification::Site(_) | Notification::Outage { .. } => "rotating_light",
+    };
+    let url = format!("{}/{}", server.trim_end_matches('/'), topic);
+    let request = client
+        .post(url)
+        .header("Title", "updown")
+        .header("Tags", tags)
+        .header("Click", origin)
+        .body(notification.message(origin));
+    Ok(match access_token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    })
+}
@@ -0,0 +1,96 @@
+use super::{config, Notification, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pub const KIND: &str = "opsgenie";
+
+#[derive(Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    #[default]
+    Us,
+    Eu,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub api_key: String,
+    #[serde(default)]
+    pub region: Region,
+}
+
+/// Creates an alert per site going down and closes it when the site comes
+/// back, using the incident key as the alert alias.
+pub fn requests(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<Vec<RequestBuilder>, AppError> {
+    let Config { api_key, region } = config(channel)?;
+    let base = match region {
+        Region::Us => "https://api.opsgenie.com/v2/alerts",
+        Region::Eu => "https://api.eu.opsgenie.com/v2/alerts",
+    };
+    let events = match notification {
+        Notification::Site(event) => vec![event],
+        Notification::Outage { events, .. } => events.iter().collect(),
+    };
+    let requests = events
+        .into_iter()
+        .map(|event| {
+            let request = match event.state {
+                SiteState::Down | SiteState::Degraded => client.post(base).json(&json!({
+                    "message": event.message(),
+                    "alias": event.incident_key(),
+                    "description": notification.message(origin),
+                    "tags": event.tags(),
+                    "priority": match event.state {
+                        SiteState::Degraded => "P3",
+                        _ => "P1",
+                    },
+                    "source": "updown",
+                })),
+                SiteState::Up => client
+                    .post(format!("{}/{}/close", base, event.incident_key()))
+                    .query(&[("identifierType", "alias")])
+                    .json(&json!({ "source": "updown" })),
+            };
+            request.header("Authorization", format!("GenieKey {}", api_key))
+        })
+        .collect();
+    Ok(requests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notifications::SiteEvent, Site};
+
+    #[test]
+    fn alerts_carry_the_sites_tags() {
+        let channel = NotificationChannel {
+            kind: KIND.to_string(),
+            config: json!({ "api_key": "key" }).to_string(),
+            ..Default::default()
+        };
+        let mut site = Site::default();
+        site.id = 1;
+        site.url = "https://example.com".to_string();
+        let notification = Notification::Site(SiteEvent {
+            site,
+            state: SiteState::Down,
+            status_code: 503,
+            site_tags: vec!["checkout".to_string(), "production".to_string()],
+            ..Default::default()
+        });
+        let client = reqwest::Client::new();
+        let requests = requests(&client, &channel, &notification, "https://updown.test").unwrap();
+        let request = requests.into_iter().next().unwrap().build().unwrap();
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+        let payload = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+        assert_eq!(payload["tags"], json!(["updown", "checkout", "production"]));
+    }
+}
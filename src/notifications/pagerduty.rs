@@ -0,0 +1,61 @@
+use super::{config, Notification, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pub const KIND: &str = "pagerduty";
+
+const EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub routing_key: String,
+}
+
+/// One Events API v2 request per site. Down events trigger and up events
+/// resolve, both keyed on the incident so PagerDuty pairs them up.
+pub fn requests(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<Vec<RequestBuilder>, AppError> {
+    let Config { routing_key } = config(channel)?;
+    let events = match notification {
+        Notification::Site(event) => vec![event],
+        Notification::Outage { events, .. } => events.iter().collect(),
+    };
+    let requests = events
+        .into_iter()
+        .map(|event| {
+            let payload = match event.state {
+                SiteState::Down | SiteState::Degraded => json!({
+                    "routing_key": routing_key,
+                    "event_action": "trigger",
+                    "dedup_key": event.incident_key(),
+                    "payload": {
+                        "summary": event.message(),
+                        "source": event.site.url,
+                        "severity": match event.state {
+                            SiteState::Degraded => "warning",
+                            _ => "critical",
+                        },
+                        "custom_details": {
+                            "status_code": event.status_code,
+                            "latency_ms": event.latency_ms,
+                        },
+                    },
+                    "links": [{ "href": origin, "text": "updown" }],
+                }),
+                SiteState::Up => json!({
+                    "routing_key": routing_key,
+                    "event_action": "resolve",
+                    "dedup_key": event.incident_key(),
+                }),
+            };
+            client.post(EVENTS_URL).json(&payload)
+        })
+        .collect();
+    Ok(requests)
+}
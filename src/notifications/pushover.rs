@@ -0,0 +1,47 @@
+use super::{config, Notification, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pub const KIND: &str = "pushover";
+
+const HIGH_PRIORITY: i8 = 1;
+const NORMAL_PRIORITY: i8 = 0;
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub user_key: String,
+    pub api_token: String,
+}
+
+/// Pushes the message through the Pushover messages API. Sites going down
+/// are sent with high priority, degraded sites and recoveries with normal
+/// priority.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config {
+        user_key,
+        api_token,
+    } = config(channel)?;
+    let priority = match notification {
+        Notification::Site(event) if event.state == SiteState::Down => HIGH_PRIORITY,
+        Notification::Site(_) => NORMAL_PRIORITY,
+        Notification::Outage { .. } => HIGH_PRIORITY,
+    };
+    let payload = json!({
+        "token": api_token,
+        "user": user_key,
+        "title": "updown",
+        "message": notification.message(origin),
+        "priority": priority,
+        "url": origin,
+    });
+    Ok(client
+        .post("https://api.pushover.net/1/messages.json")
+        .json(&payload))
+}
@@ -0,0 +1,63 @@
+use super::{config, format_duration, Notification, SiteEvent, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const KIND: &str = "slack";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub url: String,
+}
+
+/// Builds the Block Kit message for a Slack incoming webhook.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config { url } = config(channel)?;
+    let mut blocks = vec![section(&notification.message(origin))];
+    match notification {
+        Notification::Site(event) => blocks.push(details(event)),
+        Notification::Outage { events, .. } => blocks.extend(events.iter().map(details)),
+    }
+    let payload = json!({
+        "text": notification.message(origin),
+        "blocks": blocks,
+    });
+    Ok(client.post(url).json(&payload))
+}
+
+fn section(text: &str) -> Value {
+    json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": text },
+    })
+}
+
+fn details(event: &SiteEvent) -> Value {
+    let icon = match event.state {
+        SiteState::Up => ":large_green_circle:",
+        SiteState::Down => ":red_circle:",
+        SiteState::Degraded => ":large_yellow_circle:",
+    };
+    let mut text = format!(
+        "{} *{}* · last status code {}",
+        icon,
+        event.site_name(),
+        event.status_code
+    );
+    if let Some(downtime) = event.downtime() {
+        text.push_str(&format!(" · down for {}", format_duration(downtime)));
+    }
+    if let Some(url) = &event.acknowledge_url {
+        text.push_str(&format!(" · <{}|acknowledge>", url));
+    }
+    json!({
+        "type": "context",
+        "elements": [{ "type": "mrkdwn", "text": text }],
+    })
+}
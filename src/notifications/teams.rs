@@ -0,0 +1,64 @@
+use super::{config, format_duration, Notification, SiteEvent, SiteState};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const KIND: &str = "teams";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub url: String,
+}
+
+/// Posts an Adaptive Card with a fact per site to a Teams incoming webhook.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config { url } = config(channel)?;
+    let (color, facts): (&str, Vec<Value>) = match notification {
+        Notification::Site(event) if event.state == SiteState::Up => ("Good", vec![fact(event)]),
+        Notification::Site(event) if event.state == SiteState::Degraded => {
+            ("Warning", vec![fact(event)])
+        }
+        Notification::Site(event) => ("Attention", vec![fact(event)]),
+        Notification::Outage { events, .. } => ("Attention", events.iter().map(fact).collect()),
+    };
+    let payload = json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": [
+                    {
+                        "type": "TextBlock",
+                        "text": notification.message(origin),
+                        "weight": "Bolder",
+                        "size": "Medium",
+                        "color": color,
+                        "wrap": true,
+                    },
+                    { "type": "FactSet", "facts": facts },
+                ],
+                "actions": [
+                    { "type": "Action.OpenUrl", "title": "Open updown", "url": origin },
+                ],
+            },
+        }],
+    });
+    Ok(client.post(url).json(&payload))
+}
+
+fn fact(event: &SiteEvent) -> Value {
+    let mut value = format!("status code {}", event.status_code);
+    if let Some(downtime) = event.downtime() {
+        value.push_str(&format!(", down for {}", format_duration(downtime)));
+    }
+    json!({ "title": event.site_name(), "value": value })
+}
@@ -0,0 +1,43 @@
+use super::{config, format_duration, Notification, SiteEvent};
+use crate::{AppError, NotificationChannel};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pub const KIND: &str = "telegram";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+/// Sends the notification through the Bot API's sendMessage method.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config { bot_token, chat_id } = config(channel)?;
+    let mut lines = vec![notification.message(origin)];
+    match notification {
+        Notification::Site(event) => lines.push(line(event)),
+        Notification::Outage { events, .. } => lines.extend(events.iter().map(line)),
+    }
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+    let payload = json!({
+        "chat_id": chat_id,
+        "text": lines.join("\n"),
+        "disable_web_page_preview": true,
+    });
+    Ok(client.post(url).json(&payload))
+}
+
+fn line(event: &SiteEvent) -> String {
+    let mut line = format!("• {} ({})", event.site_name(), event.status_code);
+    if let Some(downtime) = event.downtime() {
+        line.push_str(&format!(", down for {}", format_duration(downtime)));
+    }
+    line
+}
@@ -0,0 +1,123 @@
+use super::{config, Notification, SiteEvent, SiteState};
+use crate::{timestamp::Timestamp, AppError, Login, NotificationChannel};
+use hmac::{Hmac, Mac};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+pub const KIND: &str = "webhook";
+pub const SIGNATURE_HEADER: &str = "X-Updown-Signature";
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    pub url: String,
+    pub secret: String,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'a str,
+    message: String,
+    hint: Option<&'a str>,
+    sites: Vec<SitePayload<'a>>,
+}
+
+#[derive(Serialize)]
+struct SitePayload<'a> {
+    site_id: i64,
+    url: &'a str,
+    name: Option<&'a str>,
+    old_state: SiteState,
+    new_state: SiteState,
+    status_code: i64,
+    latency_ms: i64,
+    incident_id: Option<i64>,
+    acknowledge_url: Option<&'a str>,
+}
+
+impl<'a> From<&'a SiteEvent> for SitePayload<'a> {
+    fn from(event: &'a SiteEvent) -> Self {
+        Self {
+            site_id: event.site.id,
+            url: &event.site.url,
+            name: event.site.name.as_deref(),
+            old_state: event.state.previous(),
+            new_state: event.state,
+            status_code: event.status_code,
+            latency_ms: event.latency_ms,
+            incident_id: event.incident.as_ref().map(|incident| incident.id),
+            acknowledge_url: event.acknowledge_url.as_deref(),
+        }
+    }
+}
+
+/// Builds the signed POST for a webhook channel. The signature is the hex
+/// encoded HMAC-SHA256 of the body keyed with the channel's secret.
+pub fn request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    notification: &Notification,
+    origin: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config { url, secret } = config(channel)?;
+    let payload = match notification {
+        Notification::Site(event) => Payload {
+            event: "site",
+            message: notification.message(origin),
+            hint: None,
+            sites: vec![event.into()],
+        },
+        Notification::Outage { events, hint, .. } => Payload {
+            event: "outage",
+            message: notification.message(origin),
+            hint: hint.as_deref(),
+            sites: events.iter().map(|event| event.into()).collect(),
+        },
+    };
+    let body = serde_json::to_string(&payload).map_err(|_| AppError::Notify)?;
+    Ok(client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header(SIGNATURE_HEADER, format!("sha256={}", sign(&secret, &body)))
+        .body(body))
+}
+
+#[derive(Serialize)]
+struct LoginPayload<'a> {
+    event: &'a str,
+    message: &'a str,
+    ip_address: Option<&'a str>,
+    user_agent: Option<&'a str>,
+    created_at: Timestamp,
+}
+
+/// Builds the signed POST telling the webhook about a login from a new
+/// ip or user agent.
+pub fn login_request(
+    client: &reqwest::Client,
+    channel: &NotificationChannel,
+    login: &Login,
+    message: &str,
+) -> Result<RequestBuilder, AppError> {
+    let Config { url, secret } = config(channel)?;
+    let payload = LoginPayload {
+        event: "login",
+        message,
+        ip_address: login.ip_address.as_deref(),
+        user_agent: login.user_agent.as_deref(),
+        created_at: login.created_at,
+    };
+    let body = serde_json::to_string(&payload).map_err(|_| AppError::Notify)?;
+    Ok(client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header(SIGNATURE_HEADER, format!("sha256={}", sign(&secret, &body)))
+        .body(body))
+}
+
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashMap, sync::Mutex};
+
+/// Buckets that have been full this long are forgotten, a client that
+/// comes back later gets a full bucket anyway.
+const IDLE_SECS: f64 = 600.0;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: f64,
+}
+
+/// Token buckets per client, in memory since one server process handles
+/// every request. Each bucket holds `per_minute` requests and refills at
+/// the same rate, so short bursts are fine but a steady stream isn't.
+pub struct RateLimiter {
+    per_minute: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute: per_minute as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token from the key's bucket. When it's empty this returns
+    /// how many seconds until there's one again, for `Retry-After`.
+    pub fn take(&self, key: &str, now: f64) -> Option<u64> {
+        if self.per_minute <= 0.0 {
+            return None;
+        }
+        let refill_per_sec = self.per_minute / 60.0;
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        if buckets.len() > 10_000 {
+            buckets.retain(|_, bucket| now - bucket.updated_at < IDLE_SECS);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.per_minute,
+            updated_at: now,
+        });
+        bucket.tokens =
+            (bucket.tokens + (now - bucket.updated_at) * refill_per_sec).min(self.per_minute);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return None;
+        }
+        Some(((1.0 - bucket.tokens) / refill_per_sec).ceil() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_bucket_says_when_to_retry() {
+        let limiter = RateLimiter::new(2);
+        assert_eq!(limiter.take("ip:203.0.113.7", 0.0), None);
+        assert_eq!(limiter.take("ip:203.0.113.7", 0.0), None);
+        // two a minute refill one token every 30 seconds
+        assert_eq!(limiter.take("ip:203.0.113.7", 0.0), Some(30));
+        // other clients have buckets of their own
+        assert_eq!(limiter.take("ip:203.0.113.8", 0.0), None);
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limiter = RateLimiter::new(2);
+        limiter.take("key", 0.0);
+        limiter.take("key", 0.0);
+        assert_eq!(limiter.take("key", 15.0), Some(15));
+        assert_eq!(limiter.take("key", 30.0), None);
+        // a long break doesn't fill the bucket past per_minute
+        assert_eq!(limiter.take("key", 3600.0), None);
+        assert_eq!(limiter.take("key", 3600.0), None);
+        assert_eq!(limiter.take("key", 3600.0), Some(30));
+    }
+
+    #[test]
+    fn retry_after_rounds_up() {
+        let limiter = RateLimiter::new(1);
+        assert_eq!(limiter.take("key", 0.0), None);
+        // half a second short of a token is still a second to wait
+        assert_eq!(limiter.take("key", 59.5), Some(1));
+    }
+
+    #[test]
+    fn no_limit_per_minute_turns_it_off() {
+        let limiter = RateLimiter::new(0);
+        for _ in 0..100 {
+            assert_eq!(limiter.take("key", 0.0), None);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    expiry::{self, ExpiryKind},
+    notifications::{format_duration, Dispatcher, Notification, SiteEvent, SiteState},
+    Database, LatencyRule,
+};
+
+/// Nearest-rank percentile of the given latencies.
+pub fn percentile(latencies: &mut [i64], percentile: i64) -> Option<i64> {
+    if latencies.is_empty() {
+        return None;
+    }
+    latencies.sort_unstable();
+    let rank = (percentile.clamp(1, 100) as f64 / 100.0 * latencies.len() as f64).ceil() as usize;
+    latencies.get(rank.saturating_sub(1)).copied()
+}
+
+/// Checks every latency rule against the checks in its window, notifying
+/// when a rule starts and stops being broken.
+pub async fn evaluate_latency_rules(db: &Database, dispatcher: &Dispatcher<'_>) {
+    let rules = match db.latency_rules().await {
+        Ok(rules) => rules,
+        Err(err) => {
+            tracing::error!("could not load latency rules: {}", err);
+            return;
+        }
+    };
+    for rule in rules {
+        let since = Database::now() - rule.window_secs;
+        let mut latencies = db.latencies(rule.site_id, since).await.unwrap_or_default();
+        let Some(latency) = percentile(&mut latencies, rule.percentile) else {
+            continue;
+        };
+        let broken = latency > rule.threshold_ms;
+        if broken == rule.triggered_at.is_some() {
+            continue;
+        }
+        let Ok(site) = db.site_by_id(rule.site_id).await else {
+            continue;
+        };
+        _ = db.set_latency_rule_triggered(rule.id, broken).await;
+        let (state, detail) = match broken {
+            true => (SiteState::Degraded, detail(&rule, latency, "is over")),
+            false => (SiteState::Up, detail(&rule, latency, "is back under")),
+        };
+        let notification = Notification::Site(SiteEvent {
+            site,
+            state,
+            latency_ms: latency,
+            rule: Some(format!("latency-{}", rule.id)),
+            detail: Some(detail),
+            ..Default::default()
+        });
+        dispatcher.dispatch(vec![notification]).await;
+    }
+}
+
+fn detail(rule: &LatencyRule, latency: i64, comparison: &str) -> String {
+    format!(
+        "p{} latency over the last {} {} {} ms ({} ms)",
+        rule.percentile,
+        format_duration(rule.window_secs),
+        comparison,
+        rule.threshold_ms,
+        latency
+    )
+}
+
+/// Refreshes stale certificate and domain expiry dates.
+pub async fn refresh_expiries(db: &Database, client: &reqwest::Client) {
+    let before = Database::now() - expiry::REFRESH_SECS;
+    for kind in ExpiryKind::ALL {
+        let sites = db
+            .sites_due_expiry_lookup(kind, before)
+            .await
+            .unwrap_or_default();
+        for site in sites {
+            let expires_at = expiry::lookup(client, &site, kind).await;
+            _ = db.upsert_expiry(site.id, kind, expires_at).await;
+        }
+    }
+}
+
+/// Notifies once per expiry date when it comes within the site's threshold,
+/// 14 days for certificates and 30 for domains unless overridden.
+pub async fn evaluate_expiry_rules(db: &Database, dispatcher: &Dispatcher<'_>) {
+    let now = Database::now();
+    let expiries = match db.expiries().await {
+        Ok(expiries) => expiries,
+        Err(err) => {
+            tracing::error!("could not load expiries: {}", err);
+            return;
+        }
+    };
+    for expiry in expiries {
+        let Some(expires_at) = expiry.expires_at else {
+            continue;
+        };
+        if expiry.notified_at.is_some() || expires_at - now > expiry.days() * 86_400 {
+            continue;
+        }
+        let Ok(site) = db.site_by_id(expiry.site_id).await else {
+            continue;
+        };
+        _ = db.set_expiry_notified(expiry.site_id, expiry.kind).await;
+        let detail = match expires_at - now {
+            secs if secs <= 0 => format!("{} has expired", expiry.kind),
+            secs => format!(
+                "{} expires in {} days",
+                expiry.kind,
+                (secs + 86_399) / 86_400
+            ),
+        };
+        let notification = Notification::Site(SiteEvent {
+            site,
+            state: SiteState::Degraded,
+            rule: Some(expiry.kind.to_string()),
+            detail: Some(detail),
+            ..Default::default()
+        });
+        dispatcher.dispatch(vec![notification]).await;
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    notifications::email::Mailer,
+    token::{Action, Claims, Signer},
+    AppError, Database, Subscriber,
+};
+
+/// How long the confirmation link in the first email works.
+pub const CONFIRM_LINK_SECS: i64 = 24 * 60 * 60;
+/// How long the unsubscribe link at the bottom of each email works.
+pub const UNSUBSCRIBE_LINK_SECS: i64 = 90 * 24 * 60 * 60;
+
+fn link(
+    signer: &Signer,
+    origin: &str,
+    action: Action,
+    subscriber: &Subscriber,
+    secs: i64,
+) -> String {
+    let claims = Claims::new(action, 0, subscriber.id, Database::now() + secs);
+    let path = match action {
+        Action::Confirm => "confirm",
+        _ => "unsubscribe",
+    };
+    format!("{}/subscribers/{}/{}", origin, path, signer.sign(&claims))
+}
+
+/// Mails the link that starts the subscription.
+pub async fn send_confirmation(
+    mailer: &Mailer,
+    signer: &Signer,
+    origin: &str,
+    subscriber: &Subscriber,
+    name: &str,
+) -> Result<(), AppError> {
+    let body = format!(
+        "Follow this link to get an email when {} has an incident:\n\n{}\n\nIgnore this email if you didn't ask for it.",
+        name,
+        link(signer, origin, Action::Confirm, subscriber, CONFIRM_LINK_SECS)
+    );
+    mailer
+        .send(
+            &subscriber.email,
+            &format!("Confirm updates for {}", name),
+            body,
+        )
+        .await
+}
+
+/// Mails everyone subscribed to the site's share pages, each with their
+/// own unsubscribe link. Failures are logged and don't stop the rest.
+pub async fn notify(
+    db: &Database,
+    mailer: &Mailer,
+    signer: &Signer,
+    origin: &str,
+    site_id: i64,
+    subject: &str,
+    message: &str,
+) {
+    let subscribers = match db.confirmed_subscribers(site_id).await {
+        Ok(subscribers) => subscribers,
+        Err(err) => {
+            tracing::error!("could not load subscribers: {}", err);
+            return;
+        }
+    };
+    for subscriber in subscribers {
+        let body = format!(
+            "{}\n\nUnsubscribe: {}",
+            message,
+            link(
+                signer,
+                origin,
+                Action::Unsubscribe,
+                &subscriber,
+                UNSUBSCRIBE_LINK_SECS
+            )
+        );
+        if let Err(err) = mailer.send(&subscriber.email, subject, body).await {
+            tracing::warn!("could not email subscriber {}: {}", subscriber.id, err);
+        }
+    }
+}
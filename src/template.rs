@@ -0,0 +1,102 @@
+use crate::{
+    notifications::{format_duration, SiteEvent},
+    AppError, Database,
+};
+
+/// Every variable a template can use. Anything else is rejected when the
+/// template is saved.
+pub const VARIABLES: [&str; 12] = [
+    "site.url",
+    "site.name",
+    "state",
+    "message",
+    "incident.id",
+    "incident.duration",
+    "check.status_code",
+    "check.latency_ms",
+    "check.ip",
+    "check.failure",
+    "acknowledge_url",
+    "detail",
+];
+
+/// Checks that every `{{...}}` in the template is a known variable and
+/// that every opened placeholder is closed.
+pub fn validate(template: &str) -> Result<(), AppError> {
+    for part in parts(template) {
+        match part {
+            Part::Variable(name) if !VARIABLES.contains(&name) => return Err(AppError::Template),
+            Part::Unclosed => return Err(AppError::Template),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Fills in the template for one event. Unknown or missing values render
+/// as an empty string so a bad template never stops an alert going out.
+pub fn render(template: &str, event: &SiteEvent) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    for part in parts(template) {
+        match part {
+            Part::Text(text) => rendered.push_str(text),
+            Part::Variable(name) => rendered.push_str(&value(name, event)),
+            Part::Unclosed => {}
+        }
+    }
+    rendered
+}
+
+fn value(name: &str, event: &SiteEvent) -> String {
+    let incident = event.incident.as_ref();
+    match name {
+        "site.url" => event.site.url.clone(),
+        "site.name" => event.site_name().to_string(),
+        "state" => format!("{:?}", event.state).to_lowercase(),
+        "message" => event.message(),
+        "incident.id" => incident
+            .map(|incident| incident.id.to_string())
+            .unwrap_or_default(),
+        // still growing while the incident is open
+        "incident.duration" => incident
+            .map(|incident| incident.resolved_at.unwrap_or(Database::now()) - incident.started_at)
+            .map(format_duration)
+            .unwrap_or_default(),
+        "check.status_code" => event.status_code.to_string(),
+        "check.latency_ms" => event.latency_ms.to_string(),
+        "check.ip" => event.ip.map(|ip| ip.to_string()).unwrap_or_default(),
+        "check.failure" => event
+            .failure
+            .map(|failure| failure.to_string())
+            .unwrap_or_default(),
+        "acknowledge_url" => event.acknowledge_url.clone().unwrap_or_default(),
+        "detail" => event.detail.clone().unwrap_or_default(),
+        _ => String::with_capacity(0),
+    }
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+    Unclosed,
+}
+
+fn parts(template: &str) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        parts.push(Part::Text(&rest[..start]));
+        match rest[start + 2..].find("}}") {
+            Some(end) => {
+                parts.push(Part::Variable(rest[start + 2..start + 2 + end].trim()));
+                rest = &rest[start + 2 + end + 2..];
+            }
+            None => {
+                parts.push(Part::Unclosed);
+                rest = "";
+            }
+        }
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
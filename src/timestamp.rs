@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the unix epoch, stored as an integer. Used for the
+/// created and updated times of users, logins, sites and responses.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("unable to get epoch in Timestamp::now")
+            .as_millis();
+        Self(millis as i64)
+    }
+
+    pub fn from_secs(secs: i64) -> Self {
+        Self(secs * 1000)
+    }
+
+    pub fn as_secs(&self) -> i64 {
+        self.0 / 1000
+    }
+
+    /// How long before `now` this was, like "5 minutes ago".
+    pub fn ago(&self, now: Timestamp) -> String {
+        let secs = (now.0 - self.0).max(0) / 1000;
+        let (count, unit) = match secs {
+            0..=59 => return "just now".to_string(),
+            60..=3599 => (secs / 60, "minute"),
+            3600..=86_399 => (secs / 3600, "hour"),
+            _ => (secs / 86_400, "day"),
+        };
+        match count {
+            1 => format!("1 {} ago", unit),
+            _ => format!("{} {}s ago", count, unit),
+        }
+    }
+}
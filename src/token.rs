@@ -0,0 +1,168 @@
+use crate::AppError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// What following a link is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Stops status page email, the subject is the subscriber.
+    Unsubscribe,
+    Acknowledge,
+    Mute,
+    /// Confirms a status page subscription, the subject is the subscriber.
+    Confirm,
+    /// Logs in from the link mailed to a recovery email.
+    Recover,
+    VerifyEmail,
+    /// Joins an organization, the subject is the invitation.
+    Invite,
+    /// Shows a site's status page, the subject is the share link.
+    Share,
+    /// Starts the session for a login code checked in the liveview.
+    Login,
+    /// Ends the session for a logout clicked in the liveview.
+    Logout,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    pub action: Action,
+    pub user_id: i64,
+    /// The row the action applies to, a site, incident or channel id, or
+    /// the `fingerprint` of the email being verified.
+    pub subject_id: i64,
+    pub expires_at: i64,
+    /// Recorded once the token is used so it can't be replayed.
+    pub nonce: String,
+}
+
+impl Claims {
+    pub fn new(action: Action, user_id: i64, subject_id: i64, expires_at: i64) -> Self {
+        Self {
+            action,
+            user_id,
+            subject_id,
+            expires_at,
+            nonce: nanoid!(),
+        }
+    }
+}
+
+/// A short stand in for a value in `Claims::subject_id`, so a link only
+/// works for the value it was sent for.
+pub fn fingerprint(value: &str) -> i64 {
+    let digest = Sha256::digest(value.as_bytes());
+    i64::from_le_bytes(digest[..8].try_into().unwrap_or_default())
+}
+
+/// Hex sha256 of a secret, for storing tokens that are only shown once.
+pub fn hash(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+/// Signs and verifies the tokens embedded in notification links. A token
+/// is the base64 claims json and its HMAC-SHA256 joined by a dot.
+pub struct Signer {
+    key: Vec<u8>,
+}
+
+impl Signer {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn sign(&self, claims: &Claims) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap_or_default());
+        let signature =
+            URL_SAFE_NO_PAD.encode(self.mac(payload.as_bytes()).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    /// Checks the signature and expiry. Single use is enforced separately
+    /// by `Database::consume_token` since it needs the database.
+    pub fn verify(&self, token: &str, now: i64) -> Result<Claims, AppError> {
+        let (payload, signature) = token.split_once('.').ok_or(AppError::TokenInvalid)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AppError::TokenInvalid)?;
+        self.mac(payload.as_bytes())
+            .verify_slice(&signature)
+            .map_err(|_| AppError::TokenInvalid)?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| AppError::TokenInvalid)?;
+        let claims: Claims =
+            serde_json::from_slice(&payload).map_err(|_| AppError::TokenInvalid)?;
+        if claims.expires_at < now {
+            return Err(AppError::TokenExpired);
+        }
+        Ok(claims)
+    }
+
+    fn mac(&self, bytes: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("hmac accepts keys of any length");
+        mac.update(bytes);
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> Claims {
+        Claims::new(Action::Acknowledge, 1, 2, 100)
+    }
+
+    #[test]
+    fn signed_tokens_verify() {
+        let signer = Signer::new("key");
+        let claims = claims();
+        assert_eq!(signer.verify(&signer.sign(&claims), 50).unwrap(), claims);
+    }
+
+    #[test]
+    fn other_keys_signatures_are_refused() {
+        let token = Signer::new("someone else's key").sign(&claims());
+        assert!(matches!(
+            Signer::new("key").verify(&token, 50),
+            Err(AppError::TokenInvalid)
+        ));
+    }
+
+    #[test]
+    fn expired_tokens_are_refused() {
+        let signer = Signer::new("key");
+        let token = signer.sign(&claims());
+        assert!(signer.verify(&token, 100).is_ok());
+        assert!(matches!(
+            signer.verify(&token, 101),
+            Err(AppError::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn tampered_payloads_are_refused() {
+        let signer = Signer::new("key");
+        let token = signer.sign(&claims());
+        let (_, signature) = token.split_once('.').unwrap();
+        let mut claims = claims();
+        claims.user_id = 3;
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        assert!(matches!(
+            signer.verify(&format!("{}.{}", payload, signature), 50),
+            Err(AppError::TokenInvalid)
+        ));
+        assert!(matches!(
+            signer.verify("not a token", 50),
+            Err(AppError::TokenInvalid)
+        ));
+    }
+}
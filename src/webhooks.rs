@@ -0,0 +1,140 @@
+use crate::{
+    checker::{Check, FailureKind},
+    notifications::webhook::{sign, SIGNATURE_HEADER},
+    Database, Incident, Site,
+};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+pub const EVENT_HEADER: &str = "X-Updown-Event";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a webhook subscription can be told about. Unlike alert channels
+/// these fire for every matching event, with no batching or quiet hours.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    #[serde(rename = "site.created")]
+    SiteCreated,
+    #[serde(rename = "check.failed")]
+    CheckFailed,
+    #[serde(rename = "incident.opened")]
+    IncidentOpened,
+    #[serde(rename = "incident.resolved")]
+    IncidentResolved,
+}
+
+impl Event {
+    pub const ALL: [Event; 4] = [
+        Event::SiteCreated,
+        Event::CheckFailed,
+        Event::IncidentOpened,
+        Event::IncidentResolved,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Event::SiteCreated => "site.created",
+            Event::CheckFailed => "check.failed",
+            Event::IncidentOpened => "incident.opened",
+            Event::IncidentResolved => "incident.resolved",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Event> {
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
+}
+
+#[derive(Serialize)]
+pub struct CheckPayload {
+    pub status_code: i64,
+    pub latency_ms: i64,
+    pub failure: Option<FailureKind>,
+    pub ip: Option<String>,
+}
+
+impl From<&Check> for CheckPayload {
+    fn from(check: &Check) -> Self {
+        Self {
+            status_code: check.status_code,
+            latency_ms: check.latency_ms,
+            failure: check.failure,
+            ip: check.ip.map(|ip| ip.to_string()),
+        }
+    }
+}
+
+/// The body of every delivery, `check` and `incident` are left out when
+/// the event has none.
+#[derive(Serialize)]
+pub struct Payload {
+    pub event: Event,
+    pub created_at: i64,
+    pub site: Site,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check: Option<CheckPayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incident: Option<Incident>,
+}
+
+impl Payload {
+    pub fn new(event: Event, site: Site) -> Self {
+        Self {
+            event,
+            created_at: Database::now(),
+            site,
+            check: None,
+            incident: None,
+        }
+    }
+}
+
+/// Posts the payload to each of the site owner's webhooks subscribed to
+/// its event, signed like webhook channels are, and logs how each went.
+pub async fn deliver(db: &Database, client: &reqwest::Client, payload: &Payload) {
+    let webhooks = match db.webhooks_by_user_id(payload.site.user_id).await {
+        Ok(webhooks) => webhooks,
+        Err(err) => {
+            tracing::error!("could not load webhooks: {}", err);
+            return;
+        }
+    };
+    let Ok(body) = serde_json::to_string(payload) else {
+        return;
+    };
+    for webhook in webhooks
+        .iter()
+        .filter(|webhook| webhook.subscribes_to(payload.event))
+    {
+        let started = Instant::now();
+        let result = client
+            .post(&webhook.url)
+            .timeout(TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header(EVENT_HEADER, payload.event.as_str())
+            .header(
+                SIGNATURE_HEADER,
+                format!("sha256={}", sign(&webhook.secret, &body)),
+            )
+            .body(body.clone())
+            .send()
+            .await;
+        let (status_code, error) = match result {
+            Ok(response) => (Some(response.status().as_u16() as i64), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        let duration_ms = started.elapsed().as_millis() as i64;
+        if let Err(err) = db
+            .insert_webhook_delivery(
+                webhook.id,
+                payload.event,
+                status_code,
+                error.as_deref(),
+                duration_ms,
+            )
+            .await
+        {
+            tracing::error!("could not log webhook delivery: {}", err);
+        }
+    }
+}
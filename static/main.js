@@ -1,61 +1,345 @@
-async function login(login_code) {
-    const response = await fetch("/login", {
+// the same look as the liveview's toasts, for the pages and requests that
+// happen outside of it
+function showToast(message) {
+    let container = document.getElementById("js-toasts");
+    if(!container) {
+        container = document.createElement("div");
+        container.id = "js-toasts";
+        container.className = "fixed top-4 left-0 right-0 flex flex-col items-center gap-2 z-40 pointer-events-none";
+        document.body.appendChild(container);
+    }
+    const toast = document.createElement("div");
+    toast.className = "pointer-events-auto rounded-md px-4 py-2 text-sm shadow-md bg-red-500 text-white";
+    toast.textContent = message;
+    container.appendChild(toast);
+    setTimeout(() => toast.remove(), 5000);
+}
+
+async function signup(url) {
+    const response = await fetch("/signup", {
         method: "POST",
         headers: {
             "Accept": "application/json",
             "Content-Type": "application/json"
         },
-        body: JSON.stringify({ login_code: login_code })
+        body: JSON.stringify({ url: url })
+    });
+    try {
+        await response.json();
+        window.location.reload();
+    } catch(error) {
+        showToast("Could not sign up, check the url and try again");
+    }
+}
+
+async function deleteAccount() {
+    const response = await fetch("/api/account", {
+        method: "DELETE",
+        headers: {
+            "Content-Type": "application/json"
+        }
     });
     if(response.ok) {
         window.location.reload();
+    } else {
+        showToast("Could not delete your account, make someone else an owner of your organizations first");
     }
 }
 
-async function signup(url) {
-    const response = await fetch("/signup", {
+async function switchOrganization(organizationId) {
+    const response = await fetch("/organizations/active", {
         method: "POST",
         headers: {
             "Accept": "application/json",
             "Content-Type": "application/json"
         },
-        body: JSON.stringify({ url: url })
+        body: JSON.stringify({ organization_id: organizationId })
     });
-    try {
-        await response.json();
+    if(response.ok) {
         window.location.reload();
-    } catch(error) {
     }
 }
 
-async function logout() {
-    const response = await fetch("/logout", {
+async function joinWithNewAccount() {
+    const response = await fetch("/invites/join", {
         method: "POST",
         headers: {
+            "Accept": "application/json",
             "Content-Type": "application/json"
         }
     });
-    try {
-        await response.json();
+    if(response.ok) {
+        window.location.reload();
+    }
+}
+
+function fromBase64Url(value) {
+    const base64 = value.replace(/-/g, "+").replace(/_/g, "/");
+    return Uint8Array.from(atob(base64), c => c.charCodeAt(0));
+}
+
+function toBase64Url(buffer) {
+    const base64 = btoa(String.fromCharCode(...new Uint8Array(buffer)));
+    return base64.replace(/\+/g, "-").replace(/\//g, "_").replace(/=+$/, "");
+}
+
+async function registerPasskey() {
+    const challenge = await (await fetch("/passkeys/register")).json();
+    const options = challenge.publicKey;
+    options.challenge = fromBase64Url(options.challenge);
+    options.user.id = fromBase64Url(options.user.id);
+    options.excludeCredentials = (options.excludeCredentials || []).map(credential => (
+        { ...credential, id: fromBase64Url(credential.id) }
+    ));
+    const credential = await navigator.credentials.create({ publicKey: options });
+    const response = await fetch("/passkeys/register", {
+        method: "POST",
+        headers: {
+            "Accept": "application/json",
+            "Content-Type": "application/json"
+        },
+        body: JSON.stringify({
+            id: credential.id,
+            rawId: toBase64Url(credential.rawId),
+            type: credential.type,
+            extensions: credential.getClientExtensionResults(),
+            response: {
+                attestationObject: toBase64Url(credential.response.attestationObject),
+                clientDataJSON: toBase64Url(credential.response.clientDataJSON)
+            }
+        })
+    });
+    if(response.ok) {
         window.location.reload();
-    } catch(error) {}
+    }
 }
 
-document.addEventListener("click", (event) => {
-    if(event.target.id === "login-btn") {
-        const login_code = document.querySelector('input[name="login-code"]').value;
-        if(login_code.length !== 21) { return; }
-        login(login_code).then(x => x);
+async function loginWithPasskey() {
+    const challenge = await (await fetch("/passkeys/login")).json();
+    const options = challenge.publicKey;
+    options.challenge = fromBase64Url(options.challenge);
+    const credential = await navigator.credentials.get({ publicKey: options });
+    const userHandle = credential.response.userHandle;
+    const response = await fetch("/passkeys/login", {
+        method: "POST",
+        headers: {
+            "Accept": "application/json",
+            "Content-Type": "application/json"
+        },
+        body: JSON.stringify({
+            id: credential.id,
+            rawId: toBase64Url(credential.rawId),
+            type: credential.type,
+            extensions: credential.getClientExtensionResults(),
+            response: {
+                authenticatorData: toBase64Url(credential.response.authenticatorData),
+                clientDataJSON: toBase64Url(credential.response.clientDataJSON),
+                signature: toBase64Url(credential.response.signature),
+                userHandle: userHandle ? toBase64Url(userHandle) : null
+            }
+        })
+    });
+    if(response.ok) {
+        window.location.reload();
+    } else {
+        showToast("Could not log in with a passkey");
     }
+}
+
+document.addEventListener("click", (event) => {
     if(event.target.id === "signup-btn") {
         const url = document.querySelector('input[name="url"]').value;
         signup(url).then(x => x);
     }
-    if(event.target.id === "logout-btn") {
-        logout().then(x => x);
+    if(event.target.id === "delete-account-btn") {
+        deleteAccount().then(x => x);
+    }
+    if(event.target.id === "join-btn") {
+        joinWithNewAccount().then(x => x);
+    }
+    if(event.target.name === "switch-organization") {
+        const organizationId = event.target.value ? parseInt(event.target.value, 10) : null;
+        switchOrganization(organizationId).then(x => x);
+    }
+    if(event.target.dataset.copy) {
+        navigator.clipboard.writeText(event.target.dataset.copy)
+            .catch(() => showToast("Could not copy, select the code and copy it instead"));
+    }
+    if(event.target.id === "passkey-register-btn") {
+        registerPasskey().catch(error => console.error(error));
+    }
+    if(event.target.id === "passkey-login-btn") {
+        loginWithPasskey().catch(error => {
+            console.error(error);
+            showToast("Could not log in with a passkey");
+        });
+    }
+});
+
+// the monitors list is saved as it's rendered, so an installed app opened
+// without a connection can show how the sites looked last time
+const STATUS_KEY = "updown-last-status";
+
+new MutationObserver(() => {
+    const rows = document.querySelectorAll("[data-site]");
+    if(rows.length === 0) {
+        return;
+    }
+    const sites = Array.from(rows, row => ({ name: row.dataset.site, status: row.dataset.status }));
+    localStorage.setItem(STATUS_KEY, JSON.stringify({ savedAt: Date.now(), sites: sites }));
+}).observe(document.documentElement, {
+    childList: true,
+    subtree: true,
+    attributes: true,
+    attributeFilter: ["data-status"]
+});
+
+function showLastKnownStatus(root) {
+    const saved = JSON.parse(localStorage.getItem(STATUS_KEY) || "null");
+    const container = document.createElement("div");
+    container.className = "flex flex-col gap-2 p-6 lg:max-w-3xl lg:mx-auto";
+    const heading = document.createElement("p");
+    heading.className = "text-xs text-gray-500 dark:text-gray-400";
+    heading.textContent = saved
+        ? `You're offline, this is how your sites looked at ${new Date(saved.savedAt).toLocaleString()}`
+        : "You're offline, updown connects again once you're back";
+    container.appendChild(heading);
+    (saved?.sites || []).forEach(site => {
+        const row = document.createElement("div");
+        row.className = "border border-gray-200 dark:border-gray-800 dark:text-white p-2 rounded-md flex items-center justify-between";
+        const name = document.createElement("p");
+        name.textContent = site.name;
+        const status = document.createElement("p");
+        status.className = "text-xs leading-5 text-gray-500 dark:text-gray-400";
+        status.textContent = site.status;
+        row.append(name, status);
+        container.appendChild(row);
+    });
+    // the liveview clears this once it connects
+    root.replaceChildren(container);
+}
+
+if("serviceWorker" in navigator) {
+    navigator.serviceWorker.register("/sw.js").catch(error => console.error(error));
+}
+
+// the liveview can't set cookies, so it renders a signed link for logins
+// and logouts and the browser follows it. it also renders the path of the
+// view it shows, which goes in the address bar so views can be linked to
+let pathSynced = false;
+new MutationObserver(() => {
+    const link = document.querySelector("[data-navigate]");
+    if(link) {
+        // logging in or out, the saved status belongs to the old account
+        localStorage.removeItem(STATUS_KEY);
+        window.location.href = link.dataset.navigate;
+        return;
+    }
+    const view = document.querySelector("[data-path]");
+    if(view && view.dataset.path !== window.location.pathname) {
+        // the first view replaces the page the liveview was opened on
+        if(pathSynced) {
+            history.pushState(null, "", view.dataset.path);
+        } else {
+            history.replaceState(null, "", view.dataset.path);
+        }
+    }
+    if(view) { pathSynced = true; }
+}).observe(document.documentElement, {
+    childList: true,
+    subtree: true,
+    attributes: true,
+    attributeFilter: ["data-path", "data-navigate"]
+});
+
+// the liveview picks its view from the path when it connects
+window.addEventListener("popstate", () => window.location.reload());
+
+// keyboard shortcuts live here since key presses outside of an input
+// never reach the liveview. elements rendered with data-shortcut are
+// focused (inputs) or clicked (buttons) when their key is pressed, and an
+// open sheet gets Esc to close it and keeps tab inside it
+const focusable = 'a[href], button:not([disabled]), input:not([disabled]), textarea:not([disabled]), select:not([disabled]), [tabindex]:not([tabindex="-1"])';
+
+function trapFocus(sheet, event) {
+    const elements = Array.from(sheet.querySelectorAll(focusable));
+    if(elements.length === 0) {
+        return;
+    }
+    const first = elements[0];
+    const last = elements[elements.length - 1];
+    if(!sheet.contains(document.activeElement)) {
+        event.preventDefault();
+        first.focus();
+    } else if(event.shiftKey && document.activeElement === first) {
+        event.preventDefault();
+        last.focus();
+    } else if(!event.shiftKey && document.activeElement === last) {
+        event.preventDefault();
+        first.focus();
+    }
+}
+
+document.addEventListener("keydown", (event) => {
+    const sheet = document.querySelector('[data-sheet="open"]');
+    if(sheet && event.key === "Escape") {
+        event.preventDefault();
+        sheet.querySelector("[data-sheet-close] button")?.click();
+        return;
+    }
+    if(sheet && event.key === "Tab") {
+        trapFocus(sheet, event);
+        return;
+    }
+    const typing = event.target.closest?.("input, textarea, select, [contenteditable]");
+    if(typing && event.key === "Escape") {
+        typing.blur();
+        return;
+    }
+    if(sheet || typing || event.ctrlKey || event.metaKey || event.altKey) {
+        return;
+    }
+    const target = document.querySelector(`[data-shortcut="${CSS.escape(event.key)}"]`);
+    if(!target) {
+        return;
+    }
+    event.preventDefault();
+    if(target.matches("input, textarea, select")) {
+        target.focus();
+    } else {
+        target.click();
     }
 });
 
+// closed sheets stay in the page off screen, inert keeps tab out of them.
+// opening one moves focus to its first field and closing it puts focus
+// back where it was
+let openSheet = null;
+let focusBeforeSheet = null;
+new MutationObserver(() => {
+    document.querySelectorAll("[data-sheet]").forEach(sheet => {
+        sheet.inert = sheet.dataset.sheet !== "open";
+    });
+    const sheet = document.querySelector('[data-sheet="open"]');
+    if(sheet === openSheet) {
+        return;
+    }
+    if(sheet) {
+        focusBeforeSheet = openSheet ? focusBeforeSheet : document.activeElement;
+        const field = sheet.querySelector("input:not([type=hidden]), textarea, select") || sheet.querySelector(focusable);
+        field?.focus();
+    } else {
+        focusBeforeSheet?.focus?.();
+        focusBeforeSheet = null;
+    }
+    openSheet = sheet;
+}).observe(document.documentElement, {
+    childList: true,
+    subtree: true,
+    attributes: true,
+    attributeFilter: ["data-sheet"]
+});
+
 class ListenerMap {
   constructor(root) {
     // bubbling events can listen at the root element
@@ -1102,8 +1386,12 @@ function event_bubbles(event) {
 const WS_ADDR = document.querySelector("meta[name=\"ws-addr\"]").getAttribute("content");
 const RETRY_MS = document.querySelector("meta[name=\"retry-ms\"]").getAttribute("content");
 
+function wsUrl() {
+    return `${WS_ADDR}?path=${encodeURIComponent(window.location.pathname)}`;
+}
+
 function connect(root, interpreter) {
-    let ws = new WebSocket(WS_ADDR);
+    let ws = new WebSocket(wsUrl());
     let interval = null;
 
     ws.onopen = () => {
@@ -1150,7 +1438,7 @@ class IPC {
 
   // -> ws
   connect() {
-    let ws = new WebSocket(WS_ADDR);
+    let ws = new WebSocket(wsUrl());
     let interval = null;
 
     ws.onopen = () => {
@@ -1161,6 +1449,7 @@ class IPC {
 
     ws.onerror = (err) => {
         if(!!interval) { clearInterval(interval); }
+        if(!navigator.onLine) { showLastKnownStatus(this.root); }
         setTimeout(() => {
           this.connect()
         }, RETRY_MS);
@@ -1192,13 +1481,17 @@ function main() {
   }
 }
 
-window.addEventListener("visibilitychange", () => {
+function reconnect() {
   if(
     window.ipc?.ws?.readyState === WebSocket.CLOSING ||
     window.ipc?.ws?.readyState === WebSocket.CLOSED
   ) {
     window.ipc.connect();
   }
-});
+}
+
+window.addEventListener("online", reconnect);
+
+window.addEventListener("visibilitychange", reconnect);
 
 main();
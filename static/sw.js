@@ -0,0 +1,62 @@
+// keeps the page the liveview mounts into and the files it loads, so the
+// installed app opens without a connection. main.js then shows the last
+// status it saw until the websocket connects again
+const CACHE = "updown-v1";
+const SHELL = "/";
+
+// the shell's hashed assets, the only files that are cached for good
+async function cacheShell(response) {
+    const cache = await caches.open(CACHE);
+    const html = await response.clone().text();
+    await cache.put(SHELL, response);
+    const assets = Array.from(
+        html.matchAll(/(?:src|href)="(\/[^"]+\?v=[^"]+)"/g),
+        match => match[1]
+    );
+    await cache.addAll(assets);
+}
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(fetch(SHELL).then(cacheShell).then(() => self.skipWaiting()));
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches.keys()
+            .then(keys => Promise.all(keys.filter(key => key !== CACHE).map(key => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    const url = new URL(request.url);
+    if(request.method !== "GET" || url.origin !== self.location.origin) {
+        return;
+    }
+    // every view is the same page, so any page falls back to the shell
+    if(request.mode === "navigate") {
+        event.respondWith(
+            fetch(request)
+                .then(response => {
+                    if(url.pathname === SHELL && response.ok) {
+                        event.waitUntil(cacheShell(response.clone()));
+                    }
+                    return response;
+                })
+                .catch(() => caches.match(SHELL))
+        );
+        return;
+    }
+    if(url.searchParams.has("v")) {
+        event.respondWith(
+            caches.match(request).then(cached => cached || fetch(request).then(response => {
+                if(response.ok) {
+                    const copy = response.clone();
+                    event.waitUntil(caches.open(CACHE).then(cache => cache.put(request, copy)));
+                }
+                return response;
+            }))
+        );
+    }
+});
@@ -0,0 +1,36 @@
+// Shows a site's status as a pill where the script tag is:
+// <script src="https://your.host/widget.js" data-token="<share token>" async></script>
+(function() {
+    const script = document.currentScript;
+    if(!script || !script.dataset.token) {
+        return;
+    }
+    const origin = new URL(script.src).origin;
+    const colors = {
+        operational: "#4c1",
+        partial: "#dfb317",
+        major: "#e05d44",
+        unknown: "#9f9f9f"
+    };
+    const pill = document.createElement("a");
+    pill.style.cssText = "display:inline-flex;align-items:center;gap:6px;padding:4px 10px;border-radius:9999px;border:1px solid #e5e7eb;font:13px/1.4 system-ui,sans-serif;color:inherit;text-decoration:none";
+    script.insertAdjacentElement("afterend", pill);
+
+    async function refresh() {
+        try {
+            const response = await fetch(`${origin}/widget/${script.dataset.token}.json`);
+            if(!response.ok) {
+                return;
+            }
+            const status = await response.json();
+            const dot = document.createElement("span");
+            dot.style.cssText = `width:8px;height:8px;border-radius:50%;background:${colors[status.indicator] || colors.unknown}`;
+            pill.href = status.url;
+            pill.replaceChildren(dot, document.createTextNode(status.description));
+        } catch(error) {
+        }
+    }
+
+    refresh();
+    setInterval(refresh, 60 * 1000);
+})();